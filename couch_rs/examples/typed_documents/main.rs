@@ -1,7 +1,7 @@
 use couch_rs::document::TypedCouchDocument;
+use couch_rs::error::CouchError;
 use couch_rs::types::document::DocumentId;
 use couch_rs::CouchDocument;
-use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 
 const TEST_DB: &str = "test_db";
@@ -47,8 +47,8 @@ async fn main() {
             println!("Name: {} {}", e.first_name, e.last_name);
         }
         Err(e) => {
-            match e.status {
-                StatusCode::NOT_FOUND => {
+            match e {
+                CouchError::NotFound(_) => {
                     let mut doc = serde_json::to_value(td).unwrap();
                     // create the document
                     match db.create(&mut doc).await {
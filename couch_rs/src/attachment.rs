@@ -0,0 +1,94 @@
+use crate::error::{CouchError, CouchResult};
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::TryStreamExt;
+use reqwest::header::{HeaderMap, CONTENT_TYPE, ETAG};
+use reqwest::Response;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+type BytesStreamBox = Pin<Box<dyn Stream<Item = CouchResult<Bytes>> + Send>>;
+
+/// The content of an attachment, streamed from CouchDB, along with its metadata. The stream
+/// yields the content in chunks, so large attachments don't have to be kept in memory.
+pub struct AttachmentStream {
+    content_type: Option<String>,
+    digest: Option<String>,
+    length: Option<u64>,
+    stream: BytesStreamBox,
+}
+
+impl AttachmentStream {
+    pub(crate) fn new(response: Response) -> Self {
+        let headers = response.headers();
+        AttachmentStream {
+            content_type: header_str(headers, CONTENT_TYPE.as_str()),
+            digest: digest(headers),
+            length: response.content_length(),
+            stream: Box::pin(response.bytes_stream().map_err(CouchError::from)),
+        }
+    }
+
+    /// The content type of the attachment, as it was stored
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// The digest of the attachment, in the format of the `_attachments` stubs, e.g. `md5-...`
+    pub fn digest(&self) -> Option<&str> {
+        self.digest.as_deref()
+    }
+
+    /// The length of the attachment in bytes, `None` when the attachment is sent compressed
+    pub fn length(&self) -> Option<u64> {
+        self.length
+    }
+}
+
+impl Stream for AttachmentStream {
+    type Item = CouchResult<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.as_mut().poll_next(cx)
+    }
+}
+
+impl fmt::Debug for AttachmentStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AttachmentStream")
+            .field("content_type", &self.content_type)
+            .field("digest", &self.digest)
+            .field("length", &self.length)
+            .finish()
+    }
+}
+
+fn header_str(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+}
+
+/// CouchDB sends the (base64 encoded) MD5 digest in the `Content-MD5` header, and as the `ETag`
+fn digest(headers: &HeaderMap) -> Option<String> {
+    header_str(headers, "content-md5")
+        .or_else(|| header_str(headers, ETAG.as_str()).map(|etag| etag.trim_matches('"').to_string()))
+        .map(|md5| format!("md5-{}", md5))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_digest() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(digest(&headers), None);
+
+        headers.insert(ETAG, HeaderValue::from_static("\"XrY7u+Ae7tCTyyK7j1rNww==\""));
+        assert_eq!(digest(&headers).as_deref(), Some("md5-XrY7u+Ae7tCTyyK7j1rNww=="));
+
+        headers.insert("content-md5", HeaderValue::from_static("sZ1T3VUfhCC5eK7kwTEBHg=="));
+        assert_eq!(digest(&headers).as_deref(), Some("md5-sZ1T3VUfhCC5eK7kwTEBHg=="));
+    }
+}
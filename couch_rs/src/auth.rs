@@ -0,0 +1,413 @@
+use crate::error::{CouchError, CouchResult};
+use crate::runtime::SharedRuntime;
+use async_trait::async_trait;
+use base64::write::EncoderWriter as Base64Encoder;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, SET_COOKIE};
+use reqwest::{Request, StatusCode, Url};
+use serde_json::json;
+use std::fmt;
+use std::future::Future;
+use std::io::Write;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// The name of the cookie that holds a CouchDB session
+const AUTH_SESSION_COOKIE: &str = "AuthSession";
+
+/// Half of the default session timeout of CouchDB (10 minutes)
+const DEFAULT_RENEWAL_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Cookie authentication: the client logs in once through `/_session`, and sends the
+/// `AuthSession` cookie instead of the credentials with every request. The session is renewed in
+/// the background, and when a request is rejected with 401 (Unauthorized) because the session
+/// expired anyway, the client logs in again and retries the request once.
+/// See [cookie authentication](https://docs.couchdb.org/en/stable/api/server/authn.html#cookie-authentication).
+///
+/// Usage:
+/// ```
+/// use couch_rs::auth::SessionAuth;
+/// use couch_rs::error::CouchResult;
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let auth = SessionAuth::new("admin", "password");
+///     let client = couch_rs::Client::new_with_session("http://localhost:5984", auth).await?;
+///     let dbs = client.list_dbs().await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SessionAuth {
+    username: String,
+    password: String,
+    renewal_interval: Duration,
+}
+
+impl SessionAuth {
+    pub fn new(username: &str, password: &str) -> Self {
+        SessionAuth {
+            username: username.to_string(),
+            password: password.to_string(),
+            renewal_interval: DEFAULT_RENEWAL_INTERVAL,
+        }
+    }
+
+    /// How often the session is renewed in the background, 5 minutes by default. This should be
+    /// shorter than the session timeout (`chttpd_auth/timeout`) of the server.
+    pub fn renewal_interval(mut self, interval: Duration) -> Self {
+        self.renewal_interval = interval;
+        self
+    }
+}
+
+impl fmt::Debug for SessionAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionAuth")
+            .field("username", &self.username)
+            .field("renewal_interval", &self.renewal_interval)
+            .finish()
+    }
+}
+
+/// A CouchDB session, shared by all clones of a `Client`
+pub(crate) struct Session {
+    http: reqwest::Client,
+    uri: Url,
+    auth: SessionAuth,
+    cookie: RwLock<Option<HeaderValue>>,
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("uri", &self.uri)
+            .field("auth", &self.auth)
+            .finish()
+    }
+}
+
+impl Session {
+    /// Logs in, and renews the session in the background for as long as the session is in use
+    pub(crate) async fn start(
+        http: reqwest::Client,
+        uri: Url,
+        auth: SessionAuth,
+        runtime: SharedRuntime,
+    ) -> CouchResult<Arc<Session>> {
+        let session = Arc::new(Session {
+            http,
+            uri,
+            auth,
+            cookie: RwLock::new(None),
+        });
+        session.renew().await?;
+
+        let interval = session.auth.renewal_interval;
+        let weak = Arc::downgrade(&session);
+        let timers = runtime.clone();
+        runtime.0.spawn(Box::pin(async move {
+            loop {
+                timers.0.sleep(interval).await;
+                match weak.upgrade() {
+                    // a failed renewal is retried on the next 401
+                    Some(session) => {
+                        let _ = session.renew().await;
+                    }
+                    None => break,
+                }
+            }
+        }));
+
+        Ok(session)
+    }
+
+    /// Logs in again, and stores the new session cookie
+    pub(crate) async fn renew(&self) -> CouchResult<()> {
+        let mut uri = self.uri.clone();
+        uri.set_path("_session");
+        let body = json!({ "name": self.auth.username, "password": self.auth.password });
+
+        let response = self.http.post(uri).json(&body).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(CouchError::from_response(response).await);
+        }
+
+        match auth_session_cookie(response.headers()) {
+            Some(cookie) => {
+                self.update(cookie);
+                Ok(())
+            }
+            None => Err(CouchError::new(s!("no session cookie received"), status)),
+        }
+    }
+
+    /// The current session cookie
+    pub(crate) fn cookie(&self) -> Option<HeaderValue> {
+        self.cookie.read().expect("session lock is poisoned").clone()
+    }
+
+    /// Stores a session cookie, e.g. a refreshed one that CouchDB sent with a response
+    pub(crate) fn update(&self, mut cookie: HeaderValue) {
+        cookie.set_sensitive(true);
+        *self.cookie.write().expect("session lock is poisoned") = Some(cookie);
+    }
+}
+
+/// Provides the bearer tokens for [JWT authentication](https://docs.couchdb.org/en/stable/api/server/authn.html#jwt-authentication),
+/// which requires the `jwtauth` handler to be enabled on the server. The provider is asked for a
+/// token before every request, so a provider for expiring tokens should cache its token, and
+/// refresh it shortly before it expires.
+///
+/// A provider can be an async closure:
+/// ```
+/// use couch_rs::error::CouchResult;
+///
+/// async fn fetch_token() -> CouchResult<String> {
+///     // e.g. get a (cached) token from an identity provider
+///     Ok("eyJhbGciOiJIUzI1NiJ9...".to_string())
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::new_with_token_provider("http://localhost:5984", fetch_token)?;
+///     let dbs = client.list_dbs().await?;
+///     Ok(())
+/// }
+/// ```
+#[async_trait]
+pub trait TokenProvider: Send + Sync {
+    /// The token to send with the next request, without the `Bearer ` prefix
+    async fn token(&self) -> CouchResult<String>;
+}
+
+#[async_trait]
+impl<F, Fut> TokenProvider for F
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = CouchResult<String>> + Send,
+{
+    async fn token(&self) -> CouchResult<String> {
+        self().await
+    }
+}
+
+/// The user on whose behalf a request is sent with
+/// [proxy authentication](https://docs.couchdb.org/en/stable/api/server/authn.html#proxy-authentication),
+/// which requires the `proxy_authentication_handler` to be enabled on the server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProxyUser {
+    pub name: String,
+    pub roles: Vec<String>,
+    /// The hex encoded HMAC-SHA1 of the name, keyed with the `chttpd_auth/secret` of the server.
+    /// Required when the server has `chttpd_auth/proxy_use_secret` enabled.
+    pub token: Option<String>,
+}
+
+impl ProxyUser {
+    pub fn new(name: &str, roles: Vec<String>) -> Self {
+        ProxyUser {
+            name: name.to_string(),
+            roles,
+            token: None,
+        }
+    }
+
+    pub fn token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// The `X-Auth-CouchDB-*` headers that identify this user
+    pub(crate) fn headers(&self) -> CouchResult<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        headers.insert(PROXY_USERNAME, proxy_header(&self.name)?);
+        headers.insert(PROXY_ROLES, proxy_header(&self.roles.join(","))?);
+        if let Some(token) = &self.token {
+            let mut token = proxy_header(token)?;
+            token.set_sensitive(true);
+            headers.insert(PROXY_TOKEN, token);
+        }
+        Ok(headers)
+    }
+}
+
+const PROXY_USERNAME: HeaderName = HeaderName::from_static("x-auth-couchdb-username");
+const PROXY_ROLES: HeaderName = HeaderName::from_static("x-auth-couchdb-roles");
+const PROXY_TOKEN: HeaderName = HeaderName::from_static("x-auth-couchdb-token");
+
+fn proxy_header(value: &str) -> CouchResult<HeaderValue> {
+    HeaderValue::from_str(value).map_err(|_| {
+        CouchError::new(
+            format!("invalid proxy authentication header: {}", value),
+            StatusCode::BAD_REQUEST,
+        )
+    })
+}
+
+/// Decides for every request on whose behalf it is sent, for proxy authentication. Gateways that
+/// serve multiple users can impersonate the end user of a request, e.g. by looking up the user in
+/// a task local. Requests without a user are sent without proxy authentication headers.
+///
+/// A `ProxyUser` always sends its own requests, and a closure can pick the user per request:
+/// ```
+/// use couch_rs::auth::ProxyUser;
+/// use couch_rs::error::CouchResult;
+/// use reqwest::Request;
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::new_with_proxy_auth("http://localhost:5984", |request: &Request| {
+///         // the reports database is read as the reporting service
+///         request.url().path().starts_with("/reports").then(|| ProxyUser::new("reporter", vec![]))
+///     })?;
+///     let dbs = client.list_dbs().await?;
+///     Ok(())
+/// }
+/// ```
+pub trait ProxyAuth: Send + Sync {
+    /// The user to send the request as, if any
+    fn proxy_user(&self, request: &Request) -> Option<ProxyUser>;
+}
+
+impl ProxyAuth for ProxyUser {
+    fn proxy_user(&self, _request: &Request) -> Option<ProxyUser> {
+        Some(self.clone())
+    }
+}
+
+impl<F> ProxyAuth for F
+where
+    F: Fn(&Request) -> Option<ProxyUser> + Send + Sync,
+{
+    fn proxy_user(&self, request: &Request) -> Option<ProxyUser> {
+        self(request)
+    }
+}
+
+/// How a `Client` authenticates its requests, when not with the basic authentication it was
+/// created with
+#[derive(Clone)]
+pub(crate) enum Authenticator {
+    Basic(HeaderValue),
+    Session(Arc<Session>),
+    Token(Arc<dyn TokenProvider>),
+    Proxy(Arc<dyn ProxyAuth>),
+}
+
+impl fmt::Debug for Authenticator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Authenticator::Basic(_) => f.write_str("Basic"),
+            Authenticator::Session(session) => f.debug_tuple("Session").field(session).finish(),
+            Authenticator::Token(_) => f.write_str("Token"),
+            Authenticator::Proxy(_) => f.write_str("Proxy"),
+        }
+    }
+}
+
+/// The value of an `Authorization` header for basic authentication
+pub(crate) fn basic_header(username: &str, password: Option<&str>) -> HeaderValue {
+    let mut header_value = b"Basic ".to_vec();
+    {
+        let mut encoder = Base64Encoder::new(&mut header_value, base64::STANDARD);
+        // The unwraps here are fine because Vec::write* is infallible.
+        write!(encoder, "{}:", username).unwrap();
+        if let Some(password) = password {
+            write!(encoder, "{}", password).unwrap();
+        }
+    }
+
+    let mut value = HeaderValue::from_bytes(&header_value).expect("can not set AUTHORIZATION header");
+    value.set_sensitive(true);
+    value
+}
+
+/// The value of an `Authorization` header for a bearer token
+pub(crate) fn bearer_header(token: &str) -> CouchResult<HeaderValue> {
+    let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|_| CouchError::new(s!("invalid bearer token"), StatusCode::UNAUTHORIZED))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// Finds the `AuthSession` cookie in the `Set-Cookie` headers of a response, as the value of a
+/// `Cookie` header
+pub(crate) fn auth_session_cookie(headers: &HeaderMap) -> Option<HeaderValue> {
+    headers
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|value| value.split(';').next())
+        .find(|cookie| {
+            cookie
+                .split_once('=')
+                .is_some_and(|(name, value)| name.trim() == AUTH_SESSION_COOKIE && !value.is_empty())
+        })
+        .and_then(|cookie| HeaderValue::from_str(cookie.trim()).ok())
+        .map(|mut cookie| {
+            cookie.set_sensitive(true);
+            cookie
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_session_cookie() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(auth_session_cookie(&headers), None);
+
+        headers.append(SET_COOKIE, HeaderValue::from_static("other=1; Path=/"));
+        headers.append(
+            SET_COOKIE,
+            HeaderValue::from_static("AuthSession=YWRtaW46NjE; Version=1; Path=/; HttpOnly"),
+        );
+        assert_eq!(
+            auth_session_cookie(&headers),
+            Some(HeaderValue::from_static("AuthSession=YWRtaW46NjE"))
+        );
+        let cookie = auth_session_cookie(&headers).unwrap();
+        assert!(cookie.is_sensitive());
+        assert!(!format!("{:?}", cookie).contains("YWRtaW46NjE"));
+
+        // a logout clears the cookie
+        let mut headers = HeaderMap::new();
+        headers.append(SET_COOKIE, HeaderValue::from_static("AuthSession=; Version=1; Path=/"));
+        assert_eq!(auth_session_cookie(&headers), None);
+    }
+
+    #[test]
+    fn test_basic_header() {
+        assert_eq!(basic_header("admin", Some("password")), "Basic YWRtaW46cGFzc3dvcmQ=");
+        assert_eq!(basic_header("admin", None), "Basic YWRtaW46");
+    }
+
+    #[test]
+    fn test_bearer_header() {
+        assert_eq!(bearer_header("abc.def.ghi").unwrap(), "Bearer abc.def.ghi");
+        assert!(bearer_header("abc\n").is_err());
+    }
+
+    #[test]
+    fn test_proxy_user_headers() {
+        let user = ProxyUser::new("john", vec![s!("readers"), s!("writers")]);
+        let headers = user.headers().unwrap();
+        assert_eq!(headers["X-Auth-CouchDB-UserName"], "john");
+        assert_eq!(headers["X-Auth-CouchDB-Roles"], "readers,writers");
+        assert!(!headers.contains_key("X-Auth-CouchDB-Token"));
+
+        let headers = user.token("a1b2c3").headers().unwrap();
+        assert_eq!(headers["X-Auth-CouchDB-Token"], "a1b2c3");
+
+        assert!(ProxyUser::new("jo\nhn", vec![]).headers().is_err());
+    }
+
+    #[test]
+    fn test_debug_hides_the_password() {
+        let auth = SessionAuth::new("admin", "secret");
+        assert!(!format!("{:?}", auth).contains("secret"));
+    }
+}
@@ -0,0 +1,255 @@
+use crate::database;
+use crate::document::{DocumentCollection, TypedCouchDocument};
+use crate::error::{CouchError, CouchResult};
+use crate::types::document::{DocumentCreatedResult, DocumentId, ReadOptions, SaveOptions, SaveResult};
+use crate::types::find::FindQuery;
+use crate::types::query::{QueryParams, ViewQuery};
+use crate::types::system::{CouchStatus, DbInfo};
+use crate::types::view::ViewCollection;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+/// A synchronous `Client`, for command line tools and applications that are not async. The
+/// requests are sent by an internal tokio runtime, with one worker thread, which is shared by the
+/// clones of the client and its databases.
+///
+/// The methods block the current thread, so they must not be called from async code; use the
+/// async `Client` there.
+///
+/// Usage:
+/// ```
+/// use couch_rs::blocking::Client;
+/// use couch_rs::error::CouchResult;
+/// use serde_json::{json, Value};
+///
+/// fn main() -> CouchResult<()> {
+///     let client = Client::new_local_test()?;
+///     let db = client.db("test_db")?;
+///     let mut doc = json!({ "_id": "blocking", "thing": true });
+///     db.save(&mut doc)?;
+///     let stored: Value = db.get("blocking")?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: crate::Client,
+    runtime: Arc<Runtime>,
+}
+
+impl Client {
+    /// Wraps an async `Client`, e.g. one that was configured with `Client::builder`
+    pub fn from_async(client: crate::Client) -> CouchResult<Client> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("couch_rs-blocking")
+            .enable_all()
+            .build()
+            .map_err(|e| {
+                CouchError::new(
+                    format!("can not start runtime: {}", e),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                )
+            })?;
+        Ok(Client {
+            inner: client,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    pub fn new(uri: &str, username: &str, password: &str) -> CouchResult<Client> {
+        Self::from_async(crate::Client::new(uri, username, password)?)
+    }
+
+    pub fn new_no_auth(uri: &str) -> CouchResult<Client> {
+        Self::from_async(crate::Client::new_no_auth(uri)?)
+    }
+
+    pub fn new_local_test() -> CouchResult<Client> {
+        Self::from_async(crate::Client::new_local_test()?)
+    }
+
+    /// The async client
+    pub fn as_async(&self) -> &crate::Client {
+        &self.inner
+    }
+
+    /// Runs any future to completion on the runtime of the client, e.g. to call an async method
+    /// that doesn't have a blocking counterpart
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    pub fn list_dbs(&self) -> CouchResult<Vec<String>> {
+        self.block_on(self.inner.list_dbs())
+    }
+
+    /// Connects to an existing database, or creates it, see `Client::db`
+    pub fn db(&self, dbname: &str) -> CouchResult<Database> {
+        let db = self.block_on(self.inner.db(dbname))?;
+        Ok(self.wrap(db))
+    }
+
+    pub fn make_db(&self, dbname: &str) -> CouchResult<Database> {
+        let db = self.block_on(self.inner.make_db(dbname))?;
+        Ok(self.wrap(db))
+    }
+
+    pub fn destroy_db(&self, dbname: &str) -> CouchResult<bool> {
+        self.block_on(self.inner.destroy_db(dbname))
+    }
+
+    pub fn exists(&self, dbname: &str) -> CouchResult<bool> {
+        self.block_on(self.inner.exists(dbname))
+    }
+
+    pub fn get_info(&self, dbname: &str) -> CouchResult<DbInfo> {
+        self.block_on(self.inner.get_info(dbname))
+    }
+
+    pub fn check_status(&self) -> CouchResult<CouchStatus> {
+        self.block_on(self.inner.check_status())
+    }
+
+    fn wrap(&self, db: database::Database) -> Database {
+        Database {
+            inner: db,
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+/// A synchronous `Database`, see `blocking::Client`
+#[derive(Debug, Clone)]
+pub struct Database {
+    inner: database::Database,
+    runtime: Arc<Runtime>,
+}
+
+impl Database {
+    /// The async database, e.g. to derive a handle with `with_timeout`, which can be wrapped again
+    /// with `with_async`
+    pub fn as_async(&self) -> &database::Database {
+        &self.inner
+    }
+
+    /// This database handle with another async handle, e.g. one with an id generator
+    pub fn with_async(&self, db: database::Database) -> Database {
+        Database {
+            inner: db,
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Runs any future to completion on the runtime of the client
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    pub fn info(&self) -> CouchResult<DbInfo> {
+        self.block_on(self.inner.info())
+    }
+
+    pub fn exists(&self, id: &str) -> bool {
+        self.block_on(self.inner.exists(id))
+    }
+
+    pub fn get<T: TypedCouchDocument>(&self, id: &str) -> CouchResult<T> {
+        self.block_on(self.inner.get(id))
+    }
+
+    pub fn get_params<T: TypedCouchDocument>(&self, id: &str, options: ReadOptions) -> CouchResult<T> {
+        self.block_on(self.inner.get_params(id, options))
+    }
+
+    pub fn get_bulk<T: TypedCouchDocument>(&self, ids: Vec<DocumentId>) -> CouchResult<DocumentCollection<T>> {
+        self.block_on(self.inner.get_bulk(ids))
+    }
+
+    pub fn get_all<T: TypedCouchDocument>(&self) -> CouchResult<DocumentCollection<T>> {
+        self.block_on(self.inner.get_all())
+    }
+
+    pub fn get_all_params<T: TypedCouchDocument>(
+        &self,
+        params: Option<QueryParams>,
+    ) -> CouchResult<DocumentCollection<T>> {
+        self.block_on(self.inner.get_all_params(params))
+    }
+
+    pub fn find<T: TypedCouchDocument>(&self, query: &FindQuery) -> CouchResult<DocumentCollection<T>> {
+        self.block_on(self.inner.find(query))
+    }
+
+    pub fn save<T: TypedCouchDocument>(&self, doc: &mut T) -> DocumentCreatedResult {
+        self.block_on(self.inner.save(doc))
+    }
+
+    pub fn save_params<T: TypedCouchDocument>(&self, doc: &mut T, options: SaveOptions) -> CouchResult<SaveResult> {
+        self.block_on(self.inner.save_params(doc, options))
+    }
+
+    pub fn create<T: TypedCouchDocument>(&self, doc: &mut T) -> DocumentCreatedResult {
+        self.block_on(self.inner.create(doc))
+    }
+
+    pub fn upsert<T: TypedCouchDocument>(&self, doc: &mut T) -> DocumentCreatedResult {
+        self.block_on(self.inner.upsert(doc))
+    }
+
+    pub fn bulk_docs<T: TypedCouchDocument>(&self, docs: &mut [T]) -> CouchResult<Vec<DocumentCreatedResult>> {
+        self.block_on(self.inner.bulk_docs(docs))
+    }
+
+    pub fn remove<T: TypedCouchDocument>(&self, doc: &T) -> bool {
+        self.block_on(self.inner.remove(doc))
+    }
+
+    pub fn query<K: DeserializeOwned, V: DeserializeOwned, T: TypedCouchDocument>(
+        &self,
+        design_name: &str,
+        view_name: &str,
+        options: Option<QueryParams>,
+    ) -> CouchResult<ViewCollection<K, V, T>> {
+        self.block_on(self.inner.query(design_name, view_name, options))
+    }
+
+    pub fn query_view<K: DeserializeOwned, V: DeserializeOwned, D: TypedCouchDocument>(
+        &self,
+        design_name: &str,
+        view_name: &str,
+        query: &ViewQuery,
+    ) -> CouchResult<ViewCollection<K, V, D>> {
+        self.block_on(self.inner.query_view(design_name, view_name, query))
+    }
+
+    pub fn query_raw(
+        &self,
+        design_name: &str,
+        view_name: &str,
+        options: Option<QueryParams>,
+    ) -> CouchResult<ViewCollection<Value, Value, Value>> {
+        self.query(design_name, view_name, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocking_transport_errors() {
+        let client = Client::new("http://localhost:1", "admin", "password").unwrap();
+        let err = client.list_dbs().unwrap_err();
+        assert!(matches!(err, CouchError::Transport(_)));
+        assert!(client.clone().check_status().is_err());
+    }
+}
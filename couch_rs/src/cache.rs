@@ -0,0 +1,358 @@
+use reqwest::header::HeaderValue;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// A cache of documents with their ETags, for the conditional reads of a `Database` handle (see
+/// `Database::with_etag_cache`). A cached document is read with an `If-None-Match` header, and
+/// when it didn't change, CouchDB answers `304 Not Modified` without a body, and the document is
+/// deserialized from the cache. The server is asked on every read, so the cache never returns a
+/// stale revision.
+///
+/// The cache holds up to `capacity` documents, and forgets the documents that were added first
+/// when it is full. The clones of a cache share their documents.
+///
+/// Usage:
+/// ```
+/// use couch_rs::cache::EtagCache;
+/// use couch_rs::error::CouchResult;
+/// use serde_json::Value;
+///
+/// const TEST_DB: &str = "test_db";
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::new_local_test()?;
+///     let db = client.db(TEST_DB).await?.with_etag_cache(EtagCache::new(1000));
+///     let settings: Value = db.get("settings").await?;
+///     // only transfers the document again when it changed
+///     let settings: Value = db.get("settings").await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct EtagCache {
+    entries: Arc<Mutex<Entries>>,
+}
+
+struct Entries {
+    capacity: usize,
+    documents: HashMap<String, Cached>,
+    // the keys of the documents, in the order in which they were added
+    order: VecDeque<String>,
+}
+
+#[derive(Clone)]
+pub(crate) struct Cached {
+    pub(crate) etag: HeaderValue,
+    pub(crate) document: Arc<Value>,
+}
+
+impl EtagCache {
+    pub fn new(capacity: usize) -> Self {
+        EtagCache {
+            entries: Arc::new(Mutex::new(Entries {
+                capacity: capacity.max(1),
+                documents: HashMap::new(),
+                order: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// The number of cached documents
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets all cached documents
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.documents.clear();
+        entries.order.clear();
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<Cached> {
+        self.entries.lock().unwrap().documents.get(key).cloned()
+    }
+
+    pub(crate) fn insert(&self, key: String, etag: HeaderValue, document: Value) {
+        let mut entries = self.entries.lock().unwrap();
+        let cached = Cached {
+            etag,
+            document: Arc::new(document),
+        };
+        if entries.documents.insert(key.clone(), cached).is_some() {
+            return;
+        }
+        entries.order.push_back(key);
+        while entries.documents.len() > entries.capacity {
+            match entries.order.pop_front() {
+                Some(oldest) => entries.documents.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+
+    pub(crate) fn remove(&self, key: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.documents.remove(key).is_some() {
+            entries.order.retain(|k| k != key);
+        }
+    }
+}
+
+impl fmt::Debug for EtagCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EtagCache({})", self.len())
+    }
+}
+
+/// A cache of document revisions, keyed by id and revision, for a `Database` handle with a
+/// document cache (see `Database::with_document_cache`). This suits reference data that is read
+/// often and rarely changes. `Database::get` asks the server for the current revision of a
+/// document with a `HEAD` request, and only transfers the document when that revision is not
+/// cached. `Database::get_params` with only a `rev` reads that revision from the cache without
+/// asking the server, as a revision never changes. The revisions of a document are forgotten when
+/// it is written or removed through the same handle.
+pub trait DocumentCache: Send + Sync {
+    /// The cached revision `rev` of the document with the id
+    fn get(&self, id: &str, rev: &str) -> Option<CachedDocument>;
+
+    /// Caches a revision of a document
+    fn insert(&self, id: &str, document: CachedDocument);
+
+    /// Forgets all revisions of the document with the id
+    fn remove(&self, id: &str);
+
+    /// Forgets all documents
+    fn clear(&self);
+}
+
+/// A revision of a document in a `DocumentCache`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedDocument {
+    pub rev: String,
+    pub document: Arc<Value>,
+}
+
+/// A `DocumentCache` of up to `capacity` document revisions, which forgets the least recently
+/// read revision when it is full. The clones of a cache share their documents.
+///
+/// The cache is not told about writes by other handles or processes, so it can hold outdated
+/// revisions: do not read the current version of a document from it with `DocumentCache::get`.
+/// `Database::get` only returns a cached revision after the server confirmed that it is the
+/// current one.
+///
+/// Usage:
+/// ```
+/// use couch_rs::cache::{DocumentCache, LruCache};
+/// use couch_rs::error::CouchResult;
+/// use serde_json::Value;
+///
+/// const TEST_DB: &str = "test_db";
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::new_local_test()?;
+///     let cache = LruCache::new(500);
+///     let db = client.db(TEST_DB).await?.with_document_cache(cache.clone());
+///     let countries: Value = db.get("countries").await?;
+///     // only transfers the document again when its revision changed
+///     let countries: Value = db.get("countries").await?;
+///     // frees the memory of the cached revisions
+///     cache.clear();
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct LruCache {
+    entries: Arc<Mutex<LruEntries>>,
+}
+
+// the id and revision of a document
+type RevKey = (String, String);
+
+struct LruEntries {
+    capacity: usize,
+    // the revisions, with the moment they were last read
+    documents: HashMap<RevKey, (CachedDocument, u64)>,
+    // the keys of the revisions, by the moment they were last read
+    recency: BTreeMap<u64, RevKey>,
+    clock: u64,
+}
+
+impl LruEntries {
+    fn touch(&mut self, key: &RevKey) -> u64 {
+        self.clock += 1;
+        let now = self.clock;
+        if let Some((_, used)) = self.documents.get_mut(key) {
+            self.recency.remove(used);
+            *used = now;
+        }
+        self.recency.insert(now, key.clone());
+        now
+    }
+}
+
+impl LruCache {
+    pub fn new(capacity: usize) -> Self {
+        LruCache {
+            entries: Arc::new(Mutex::new(LruEntries {
+                capacity: capacity.max(1),
+                documents: HashMap::new(),
+                recency: BTreeMap::new(),
+                clock: 0,
+            })),
+        }
+    }
+
+    /// The number of cached revisions
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl DocumentCache for LruCache {
+    fn get(&self, id: &str, rev: &str) -> Option<CachedDocument> {
+        let mut entries = self.entries.lock().unwrap();
+        let key = (id.to_string(), rev.to_string());
+        if !entries.documents.contains_key(&key) {
+            return None;
+        }
+        entries.touch(&key);
+        entries.documents.get(&key).map(|(document, _)| document.clone())
+    }
+
+    fn insert(&self, id: &str, document: CachedDocument) {
+        let mut entries = self.entries.lock().unwrap();
+        let key = (id.to_string(), document.rev.clone());
+        let used = entries.touch(&key);
+        entries.documents.insert(key, (document, used));
+        while entries.documents.len() > entries.capacity {
+            match entries.recency.pop_first() {
+                Some((_, oldest)) => entries.documents.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+
+    fn remove(&self, id: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        let LruEntries { documents, recency, .. } = &mut *entries;
+        documents.retain(|(cached_id, _), (_, used)| {
+            let keep = cached_id != id;
+            if !keep {
+                recency.remove(used);
+            }
+            keep
+        });
+    }
+
+    fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.documents.clear();
+        entries.recency.clear();
+    }
+}
+
+impl fmt::Debug for LruCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LruCache({})", self.len())
+    }
+}
+
+/// The document cache of a `Database` handle
+#[derive(Clone)]
+pub(crate) struct SharedCache(pub(crate) Arc<dyn DocumentCache>);
+
+impl fmt::Debug for SharedCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedCache")
+    }
+}
+
+/// The key of a document in the cache: its path, with the query parameters in a stable order
+pub(crate) fn cache_key(path: &str, args: Option<&HashMap<String, String>>) -> String {
+    let mut params: Vec<_> = args.into_iter().flatten().collect();
+    params.sort();
+    params.iter().fold(path.to_string(), |key, (name, value)| {
+        let separator = if key.len() == path.len() { '?' } else { '&' };
+        format!("{}{}{}={}", key, separator, name, value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_capacity() {
+        let cache = EtagCache::new(2);
+        cache.insert(s!("a"), HeaderValue::from_static("\"1-a\""), json!({"_id": "a"}));
+        cache.insert(s!("b"), HeaderValue::from_static("\"1-b\""), json!({"_id": "b"}));
+        cache.insert(s!("a"), HeaderValue::from_static("\"2-a\""), json!({"_id": "a"}));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("a").unwrap().etag, "\"2-a\"");
+
+        cache.insert(s!("c"), HeaderValue::from_static("\"1-c\""), json!({"_id": "c"}));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("a").is_none());
+        assert_eq!(*cache.get("c").unwrap().document, json!({"_id": "c"}));
+
+        cache.remove("b");
+        assert_eq!(cache.len(), 1);
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_lru_cache() {
+        let cached = |rev: &str| CachedDocument {
+            rev: rev.to_string(),
+            document: Arc::new(json!({ "_rev": rev })),
+        };
+        let cache = LruCache::new(3);
+        cache.insert("a", cached("1-a"));
+        cache.insert("b", cached("1-b"));
+        cache.insert("c", cached("1-c"));
+        // reading a makes b the least recently used revision
+        assert_eq!(cache.get("a", "1-a"), Some(cached("1-a")));
+        assert!(cache.get("a", "2-a").is_none());
+        cache.insert("d", cached("1-d"));
+        assert_eq!(cache.len(), 3);
+        assert!(cache.get("b", "1-b").is_none());
+
+        // the revisions of a document are cached side by side, and forgotten together
+        cache.insert("a", cached("2-a"));
+        assert_eq!(cache.get("a", "2-a").unwrap().rev, "2-a");
+        assert_eq!(cache.get("a", "1-a").unwrap().rev, "1-a");
+        assert!(cache.get("c", "1-c").is_none());
+        cache.remove("a");
+        assert!(cache.get("a", "1-a").is_none());
+        assert!(cache.get("a", "2-a").is_none());
+        assert_eq!(cache.len(), 1);
+        cache.insert("e", cached("1-e"));
+        assert!(cache.get("d", "1-d").is_some());
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_key() {
+        assert_eq!(cache_key("db/doc", None), "db/doc");
+        let mut args = HashMap::new();
+        args.insert(s!("revs"), s!("true"));
+        args.insert(s!("conflicts"), s!("true"));
+        assert_eq!(cache_key("db/doc", Some(&args)), "db/doc?conflicts=true&revs=true");
+    }
+}
@@ -1,41 +1,81 @@
 use crate::client::Client;
+use crate::database::Database;
+use crate::document::TypedCouchDocument;
+use async_trait::async_trait;
 use futures_core::{Future, Stream};
 use futures_util::{ready, FutureExt, StreamExt, TryStreamExt};
-use reqwest::StatusCode;
 use reqwest::{Method, Response};
 use std::collections::HashMap;
 use std::io;
+use std::marker::PhantomData;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use tokio::io::AsyncBufReadExt;
 use tokio_stream::wrappers::LinesStream;
 use tokio_util::io::StreamReader;
 
-use crate::error::{CouchError, CouchResult};
-use crate::types::changes::{ChangeEvent, Event};
+use crate::error::{CouchError, CouchResult, ErrorDetails, RequestOrigin};
+use crate::retry::RetryPolicy;
+use crate::runtime::BoxFuture;
+use crate::types::changes::{ChangeEvent, ChangesRequest, DbUpdateEvent, DbUpdatesEvent, Event, Seq, TypedChangeEvent};
+use serde_json::{json, Value};
 
 /// The max timeout value for longpoll/continous HTTP requests
 /// that CouchDB supports (see [1]).
 ///
 /// [1]: https://docs.couchdb.org/en/stable/api/database/changes.html
-const COUCH_MAX_TIMEOUT: usize = 60000;
+pub(crate) const COUCH_MAX_TIMEOUT: usize = 60000;
+
+/// The default interval (in milliseconds) at which CouchDB sends an empty line on a continuous
+/// feed, to keep the connection alive.
+pub(crate) const DEFAULT_HEARTBEAT: usize = 5000;
+
+/// The delay before reconnecting a feed after its first failure, which doubles for every next
+/// failure, up to `FEED_MAX_BACKOFF`
+const FEED_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const FEED_MAX_BACKOFF: Duration = Duration::from_secs(30);
 
 /// The stream for the `_changes` endpoint.
 ///
-/// This is returned from [Database::changes].
+/// This is returned from [Database::changes](crate::database::Database::changes) and
+/// [Database::changes_stream](crate::database::Database::changes_stream).
+///
+/// After a temporary error (e.g. the server is unreachable, or responds with 503), the error is
+/// returned, and the next poll reconnects after a delay that grows with every consecutive failure.
+/// Other errors (e.g. 401, 403 or 404) end the stream after they are returned.
 pub struct ChangesStream {
     last_seq: Option<serde_json::Value>,
     client: Client,
     database: String,
-    state: ChangesStreamState,
+    state: FeedState,
     params: HashMap<String, String>,
+    body: Option<serde_json::Value>,
     infinite: bool,
+    failures: u32,
 }
 
-enum ChangesStreamState {
+type LinesStreamBox = Pin<Box<dyn Stream<Item = io::Result<String>> + Send>>;
+
+enum FeedState {
     Idle,
-    Requesting(Pin<Box<dyn Future<Output = CouchResult<Response>>>>),
-    Reading(Pin<Box<dyn Stream<Item = io::Result<String>>>>),
+    Requesting(Pin<Box<dyn Future<Output = CouchResult<Response>> + Send>>),
+    Reading(LinesStreamBox),
+    Backoff(BoxFuture),
+    Done,
+}
+
+/// The state of a feed after its consecutive `failures`, the last one being `err`: a delay before
+/// reconnecting when the error is temporary (see `CouchError::is_retryable`), otherwise the end
+/// of the feed
+fn after_failure(client: &Client, failures: u32, err: &CouchError) -> FeedState {
+    if !err.is_retryable() {
+        return FeedState::Done;
+    }
+    let delay = RetryPolicy::default()
+        .backoff(FEED_INITIAL_BACKOFF, FEED_MAX_BACKOFF)
+        .backoff_after(failures);
+    FeedState::Backoff(client.sleep(delay))
 }
 
 impl ChangesStream {
@@ -59,8 +99,10 @@ impl ChangesStream {
             client,
             database,
             params,
-            state: ChangesStreamState::Idle,
+            body: None,
+            state: FeedState::Idle,
             infinite: false,
+            failures: 0,
             last_seq,
         }
     }
@@ -83,6 +125,24 @@ impl ChangesStream {
         self.params.insert("timeout".to_string(), timeout);
     }
 
+    /// Set the heartbeat interval in milliseconds.
+    ///
+    /// CouchDB will send an empty line every `heartbeat` milliseconds to keep the continuous feed
+    /// alive. When set, CouchDB ignores the `timeout` parameter. Use `None` to disable the heartbeat.
+    pub fn set_heartbeat(&mut self, heartbeat: Option<usize>) {
+        match heartbeat {
+            Some(heartbeat) => self.params.insert("heartbeat".to_string(), heartbeat.to_string()),
+            None => self.params.remove("heartbeat"),
+        };
+    }
+
+    /// Apply a changes request, e.g. to filter the changes on the server. The parameters of the
+    /// request are added to (and override) the parameters of this stream.
+    pub fn set_request(&mut self, request: &ChangesRequest) {
+        self.params.extend(request.query_params());
+        self.body = request.body();
+    }
+
     /// Get the last retrieved seq.
     pub fn last_seq(&self) -> &Option<serde_json::Value> {
         &self.last_seq
@@ -92,69 +152,173 @@ impl ChangesStream {
     pub fn infinite(&self) -> bool {
         self.infinite
     }
+
+    /// Deserialize the document of every change into `T`, and include the documents in the feed.
+    /// A document that can't be deserialized doesn't end the stream: the error is returned in the
+    /// `doc` of its change.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use futures_util::StreamExt;
+    /// use serde_json::{json, Value};
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     let mut changes = db.changes_stream(Some(json!("now"))).typed::<Value>();
+    ///
+    ///     db.create(&mut json!({"thing": true})).await?;
+    ///
+    ///     if let Some(change) = changes.next().await {
+    ///         match change?.doc {
+    ///             Some(Ok(doc)) => println!("Document {} changed", doc["_id"]),
+    ///             Some(Err(err)) => println!("Invalid document: {}", err),
+    ///             None => println!("Document deleted"),
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn typed<T: TypedCouchDocument>(mut self) -> TypedChangesStream<T> {
+        self.params.insert("include_docs".to_string(), "true".to_string());
+        TypedChangesStream {
+            inner: self,
+            doc: PhantomData,
+        }
+    }
+}
+
+/// A `ChangesStream` with the documents deserialized, see [ChangesStream::typed].
+pub struct TypedChangesStream<T> {
+    inner: ChangesStream,
+    doc: PhantomData<fn() -> T>,
 }
 
-async fn get_changes(client: Client, database: String, params: HashMap<String, String>) -> CouchResult<Response> {
+impl<T> TypedChangesStream<T> {
+    /// Get the last retrieved seq.
+    pub fn last_seq(&self) -> &Option<Seq> {
+        self.inner.last_seq()
+    }
+
+    /// The untyped stream
+    pub fn into_inner(self) -> ChangesStream {
+        self.inner
+    }
+}
+
+impl<T: TypedCouchDocument> Stream for TypedChangesStream<T> {
+    type Item = CouchResult<TypedChangeEvent<T>>;
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let change = ready!(self.inner.poll_next_unpin(cx));
+        Poll::Ready(change.map(|change| change.map(TypedChangeEvent::from)))
+    }
+}
+
+/// Formats a seq for use as the `since` query parameter. Strings are sent as-is, other values
+/// (e.g. numeric seqs from CouchDB 1.x) as JSON.
+pub(crate) fn seq_param(seq: &Seq) -> String {
+    match seq {
+        Seq::String(seq) => seq.clone(),
+        seq => seq.to_string(),
+    }
+}
+
+async fn get_changes(
+    client: Client,
+    database: String,
+    params: HashMap<String, String>,
+    body: Option<serde_json::Value>,
+) -> CouchResult<Response> {
     let path = format!("{}/_changes", database);
-    let res = client.req(Method::GET, &path, Some(&params)).send().await?;
+    let request = match body {
+        Some(body) => client.request(Method::POST, &path, Some(&params)).body(js!(body)),
+        None => client.request(Method::GET, &path, Some(&params)),
+    };
+    let res = request.send().await?;
     Ok(res)
 }
 
+fn read_lines(res: Response) -> LinesStreamBox {
+    let stream = res.bytes_stream().map_err(io::Error::other);
+    let reader = StreamReader::new(stream);
+    Box::pin(LinesStream::new(reader.lines()))
+}
+
+fn status_error(res: &Response) -> CouchError {
+    CouchError::new(
+        res.status().canonical_reason().unwrap_or_default().to_string(),
+        res.status(),
+    )
+    .with_origin(RequestOrigin::of(res))
+}
+
+/// Whether an error reading a feed was caused by the connection (e.g. a timeout or a dropped
+/// connection), in which case the feed can be re-established.
+fn is_connection_error(err: &io::Error) -> bool {
+    err.get_ref()
+        .and_then(|err| err.downcast_ref::<reqwest::Error>())
+        .is_some()
+}
+
+fn read_error(err: io::Error) -> CouchError {
+    let inner = err.get_ref().and_then(|err| err.downcast_ref::<reqwest::Error>());
+    CouchError::Transport(Box::new(ErrorDetails {
+        status: inner.and_then(|err| err.status()),
+        message: inner.map(|err| err.to_string()).unwrap_or_else(|| err.to_string()),
+        url: inner.and_then(|err| err.url()).cloned(),
+        ..ErrorDetails::default()
+    }))
+}
+
 impl Stream for ChangesStream {
     type Item = CouchResult<ChangeEvent>;
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         loop {
             self.state = match self.state {
-                ChangesStreamState::Idle => {
+                FeedState::Idle => {
                     let mut params = self.params.clone();
                     if let Some(seq) = &self.last_seq {
-                        params.insert("since".to_string(), seq.to_string());
+                        params.insert("since".to_string(), seq_param(seq));
                     }
-                    let fut = get_changes(self.client.clone(), self.database.clone(), params);
-                    ChangesStreamState::Requesting(Box::pin(fut))
+                    let fut = get_changes(self.client.clone(), self.database.clone(), params, self.body.clone());
+                    FeedState::Requesting(Box::pin(fut))
                 }
-                ChangesStreamState::Requesting(ref mut fut) => match ready!(fut.poll_unpin(cx)) {
-                    Err(err) => return Poll::Ready(Some(Err(err))),
+                FeedState::Requesting(ref mut fut) => match ready!(fut.poll_unpin(cx)) {
+                    Err(err) => {
+                        // a later poll will reconnect, starting from the last retrieved seq
+                        self.failures += 1;
+                        self.state = after_failure(&self.client, self.failures, &err);
+                        return Poll::Ready(Some(Err(err)));
+                    }
                     Ok(res) => match res.status().is_success() {
                         true => {
-                            let stream = res
-                                .bytes_stream()
-                                .map_err(|err| io::Error::new(io::ErrorKind::Other, err));
-                            let reader = StreamReader::new(stream);
-                            let lines = Box::pin(LinesStream::new(reader.lines()));
-                            ChangesStreamState::Reading(lines)
+                            self.failures = 0;
+                            FeedState::Reading(read_lines(res))
                         }
                         false => {
-                            return Poll::Ready(Some(Err(CouchError::new(
-                                res.status().canonical_reason().unwrap().to_string(),
-                                res.status(),
-                            ))))
+                            let err = status_error(&res);
+                            self.failures += 1;
+                            self.state = after_failure(&self.client, self.failures, &err);
+                            return Poll::Ready(Some(Err(err)));
                         }
                     },
                 },
-                ChangesStreamState::Reading(ref mut lines) => {
+                FeedState::Reading(ref mut lines) => {
                     let line = ready!(lines.poll_next_unpin(cx));
                     match line {
-                        None => ChangesStreamState::Idle,
+                        None => FeedState::Idle,
+                        // in infinite mode, a dropped or timed out connection is re-established
+                        Some(Err(err)) if self.infinite && is_connection_error(&err) => FeedState::Idle,
                         Some(Err(err)) => {
-                            let inner = err.get_ref().and_then(|err| err.downcast_ref::<reqwest::Error>());
-                            match inner {
-                                Some(reqwest_err) if reqwest_err.is_timeout() && self.infinite => {
-                                    ChangesStreamState::Idle
-                                }
-                                Some(reqwest_err) => {
-                                    return Poll::Ready(Some(Err(CouchError::new(
-                                        reqwest_err.to_string(),
-                                        reqwest_err.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                                    ))));
-                                }
-                                _ => {
-                                    return Poll::Ready(Some(Err(CouchError::new(
-                                        format!("{}", err),
-                                        StatusCode::from_u16(500).unwrap(),
-                                    ))));
-                                }
-                            }
+                            let err = read_error(err);
+                            self.failures += 1;
+                            self.state = after_failure(&self.client, self.failures, &err);
+                            return Poll::Ready(Some(Err(err)));
                         }
                         Some(Ok(line)) if line.is_empty() => continue,
                         Some(Ok(line)) => match serde_json::from_str::<Event>(&line) {
@@ -167,7 +331,7 @@ impl Stream for ChangesStream {
                                 if !self.infinite {
                                     return Poll::Ready(None);
                                 }
-                                ChangesStreamState::Idle
+                                FeedState::Idle
                             }
                             Err(e) => {
                                 return Poll::Ready(Some(Err(e.into())));
@@ -175,16 +339,357 @@ impl Stream for ChangesStream {
                         },
                     }
                 }
+                FeedState::Backoff(ref mut sleep) => {
+                    ready!(sleep.poll_unpin(cx));
+                    FeedState::Idle
+                }
+                FeedState::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+/// The stream for the server-wide `_db_updates` endpoint, which reports the creation, update and
+/// deletion of databases.
+///
+/// This is returned from [Client::db_updates](crate::Client::db_updates). Like
+/// [Database::changes_stream](crate::database::Database::changes_stream), it reconnects when the
+/// connection is dropped, resuming from the last received `seq`, and after a temporary error with a
+/// growing delay. Other errors end the stream.
+pub struct DbUpdatesStream {
+    last_seq: Option<Seq>,
+    client: Client,
+    state: FeedState,
+    params: HashMap<String, String>,
+    failures: u32,
+}
+
+impl DbUpdatesStream {
+    /// Create a new database updates stream.
+    pub fn new(client: Client, last_seq: Option<Seq>) -> Self {
+        let mut params = HashMap::new();
+        params.insert("feed".to_string(), "continuous".to_string());
+        params.insert("heartbeat".to_string(), DEFAULT_HEARTBEAT.to_string());
+        Self {
+            last_seq,
+            client,
+            state: FeedState::Idle,
+            params,
+            failures: 0,
+        }
+    }
+
+    /// Get the last retrieved seq.
+    pub fn last_seq(&self) -> &Option<Seq> {
+        &self.last_seq
+    }
+}
+
+async fn get_db_updates(client: Client, params: HashMap<String, String>) -> CouchResult<Response> {
+    let res = client.request(Method::GET, "_db_updates", Some(&params)).send().await?;
+    Ok(res)
+}
+
+impl Stream for DbUpdatesStream {
+    type Item = CouchResult<DbUpdateEvent>;
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            self.state = match self.state {
+                FeedState::Idle => {
+                    let mut params = self.params.clone();
+                    if let Some(seq) = &self.last_seq {
+                        params.insert("since".to_string(), seq_param(seq));
+                    }
+                    FeedState::Requesting(Box::pin(get_db_updates(self.client.clone(), params)))
+                }
+                FeedState::Requesting(ref mut fut) => match ready!(fut.poll_unpin(cx)) {
+                    Err(err) => {
+                        self.failures += 1;
+                        self.state = after_failure(&self.client, self.failures, &err);
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                    Ok(res) if res.status().is_success() => {
+                        self.failures = 0;
+                        FeedState::Reading(read_lines(res))
+                    }
+                    Ok(res) => {
+                        let err = status_error(&res);
+                        self.failures += 1;
+                        self.state = after_failure(&self.client, self.failures, &err);
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                },
+                FeedState::Reading(ref mut lines) => match ready!(lines.poll_next_unpin(cx)) {
+                    None => FeedState::Idle,
+                    Some(Err(err)) if is_connection_error(&err) => FeedState::Idle,
+                    Some(Err(err)) => {
+                        let err = read_error(err);
+                        self.failures += 1;
+                        self.state = after_failure(&self.client, self.failures, &err);
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                    Some(Ok(line)) if line.is_empty() => continue,
+                    Some(Ok(line)) => match serde_json::from_str::<DbUpdatesEvent>(&line) {
+                        Ok(DbUpdatesEvent::Update(event)) => {
+                            self.last_seq = Some(event.seq.clone());
+                            return Poll::Ready(Some(Ok(event)));
+                        }
+                        Ok(DbUpdatesEvent::Finished(event)) => {
+                            self.last_seq = Some(event.last_seq);
+                            FeedState::Idle
+                        }
+                        Err(e) => {
+                            return Poll::Ready(Some(Err(e.into())));
+                        }
+                    },
+                },
+                FeedState::Backoff(ref mut sleep) => {
+                    ready!(sleep.poll_unpin(cx));
+                    FeedState::Idle
+                }
+                FeedState::Done => return Poll::Ready(None),
             }
         }
     }
 }
 
+/// Storage for the checkpoints of a [ChangesCheckpointer]. Implement this trait to keep the
+/// checkpoints somewhere else than in CouchDB, e.g. in the local file system.
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    /// Loads the last stored seq of the consumer with the given id, if any
+    async fn load(&self, id: &str) -> CouchResult<Option<Seq>>;
+    /// Stores the last processed seq of the consumer with the given id
+    async fn save(&self, id: &str, seq: &Seq) -> CouchResult<()>;
+}
+
+/// Stores checkpoints as `_local` documents in a CouchDB database. Local documents are not
+/// replicated, and do not show up in the changes feed or in views.
+#[derive(Debug, Clone)]
+pub struct LocalDocumentStore {
+    db: Database,
+}
+
+impl LocalDocumentStore {
+    /// Create a store that keeps its checkpoints in the given database. This is typically the
+    /// database whose changes are being consumed.
+    pub fn new(db: Database) -> Self {
+        LocalDocumentStore { db }
+    }
+
+    fn doc_id(id: &str) -> String {
+        format!("_local/{}", id)
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for LocalDocumentStore {
+    async fn load(&self, id: &str) -> CouchResult<Option<Seq>> {
+        match self.db.get::<Value>(&Self::doc_id(id)).await {
+            Ok(doc) => Ok(doc.get("last_seq").cloned()),
+            Err(err) if err.is_not_found() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn save(&self, id: &str, seq: &Seq) -> CouchResult<()> {
+        let mut doc = json!({
+            "_id": Self::doc_id(id),
+            "last_seq": seq,
+        });
+        self.db.upsert(&mut doc).await?;
+        Ok(())
+    }
+}
+
+/// Keeps track of the last processed change of a changes feed consumer, so it can resume where it
+/// left off after a restart.
+///
+/// Usage:
+/// ```
+/// use couch_rs::changes::{ChangesCheckpointer, LocalDocumentStore};
+/// use couch_rs::error::CouchResult;
+/// use futures_util::StreamExt;
+///
+/// const TEST_DB: &str = "test_db";
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::new_local_test()?;
+///     let db = client.db(TEST_DB).await?;
+///
+///     let mut checkpointer = ChangesCheckpointer::new("my-consumer", LocalDocumentStore::new(db.clone()));
+///     // store a checkpoint every 100 changes
+///     checkpointer.set_interval(100);
+///
+///     let mut changes = checkpointer.resume(&db).await?;
+///     while let Some(change) = changes.next().await {
+///         let change = change?;
+///         println!("Processing {}", change.id);
+///         checkpointer.processed(&change).await?;
+///         # break;
+///     }
+///
+///     checkpointer.flush().await?;
+///     Ok(())
+/// }
+/// ```
+pub struct ChangesCheckpointer<S: CheckpointStore> {
+    id: String,
+    store: S,
+    interval: u64,
+    pending: u64,
+    last_seq: Option<Seq>,
+}
+
+impl<S: CheckpointStore> ChangesCheckpointer<S> {
+    /// Create a checkpointer for the consumer with the given id. Every consumer of a changes feed
+    /// needs its own id. By default a checkpoint is stored for every processed change.
+    pub fn new(id: &str, store: S) -> Self {
+        ChangesCheckpointer {
+            id: id.to_string(),
+            store,
+            interval: 1,
+            pending: 0,
+            last_seq: None,
+        }
+    }
+
+    /// Only store a checkpoint every `interval` processed changes. After a restart, at most
+    /// `interval - 1` changes will be processed again.
+    pub fn set_interval(&mut self, interval: u64) {
+        self.interval = interval.max(1);
+    }
+
+    /// Opens the continuous changes feed of the database, starting after the last stored checkpoint.
+    pub async fn resume(&mut self, db: &Database) -> CouchResult<ChangesStream> {
+        self.resume_params(db, &ChangesRequest::default()).await
+    }
+
+    /// Opens the continuous changes feed of the database with the parameters of the given request
+    /// applied, starting after the last stored checkpoint.
+    pub async fn resume_params(&mut self, db: &Database, request: &ChangesRequest) -> CouchResult<ChangesStream> {
+        let since = self.store.load(&self.id).await?;
+        self.last_seq = since.clone();
+        self.pending = 0;
+        Ok(db.changes_stream_params(since, request))
+    }
+
+    /// Marks a change as processed. A checkpoint is stored when the interval has been reached.
+    pub async fn processed(&mut self, change: &ChangeEvent) -> CouchResult<()> {
+        self.last_seq = Some(change.seq.clone());
+        self.pending += 1;
+
+        if self.pending >= self.interval {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Stores a checkpoint for the last processed change, if it has not been stored yet. Call this
+    /// before shutting down the consumer.
+    pub async fn flush(&mut self) -> CouchResult<()> {
+        if self.pending == 0 {
+            return Ok(());
+        }
+
+        if let Some(seq) = &self.last_seq {
+            self.store.save(&self.id, seq).await?;
+        }
+        self.pending = 0;
+        Ok(())
+    }
+
+    /// The seq of the last processed change.
+    pub fn last_seq(&self) -> &Option<Seq> {
+        &self.last_seq
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::client::Client;
-    use futures_util::StreamExt;
-    use serde_json::{json, Value};
+    use super::*;
+    use crate::types::changes::Change;
+    use reqwest::StatusCode;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryStore {
+        saved: Mutex<HashMap<String, Vec<Seq>>>,
+    }
+
+    #[async_trait]
+    impl CheckpointStore for MemoryStore {
+        async fn load(&self, id: &str) -> CouchResult<Option<Seq>> {
+            let saved = self.saved.lock().unwrap();
+            Ok(saved.get(id).and_then(|seqs| seqs.last().cloned()))
+        }
+
+        async fn save(&self, id: &str, seq: &Seq) -> CouchResult<()> {
+            let mut saved = self.saved.lock().unwrap();
+            saved.entry(id.to_string()).or_default().push(seq.clone());
+            Ok(())
+        }
+    }
+
+    fn change(seq: u64) -> ChangeEvent {
+        ChangeEvent {
+            seq: json!(seq),
+            id: format!("doc_{}", seq),
+            changes: vec![Change {
+                rev: "1-abc".to_string(),
+            }],
+            deleted: false,
+            doc: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_interval() {
+        let mut checkpointer = ChangesCheckpointer::new("consumer", MemoryStore::default());
+        checkpointer.set_interval(2);
+
+        for seq in 1..=5 {
+            checkpointer.processed(&change(seq)).await.unwrap();
+        }
+        assert_eq!(checkpointer.last_seq(), &Some(json!(5)));
+        assert_eq!(
+            checkpointer.store.saved.lock().unwrap().get("consumer").unwrap(),
+            &vec![json!(2), json!(4)]
+        );
+
+        checkpointer.flush().await.unwrap();
+        // nothing left to store
+        checkpointer.flush().await.unwrap();
+        assert_eq!(
+            checkpointer.store.saved.lock().unwrap().get("consumer").unwrap(),
+            &vec![json!(2), json!(4), json!(5)]
+        );
+        assert_eq!(checkpointer.store.load("consumer").await.unwrap(), Some(json!(5)));
+    }
+
+    #[test]
+    fn test_seq_param() {
+        assert_eq!(seq_param(&json!("12-g1AAAA")), "12-g1AAAA");
+        assert_eq!(seq_param(&json!("now")), "now");
+        assert_eq!(seq_param(&json!(12)), "12");
+    }
+
+    #[tokio::test]
+    async fn test_backoff_after_failure() {
+        let client = Client::new("http://localhost:1", "admin", "password").unwrap();
+        let unauthorized = CouchError::new("Unauthorized".to_string(), StatusCode::UNAUTHORIZED);
+        assert!(matches!(after_failure(&client, 1, &unauthorized), FeedState::Done));
+        let unavailable = CouchError::new("Service Unavailable".to_string(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(matches!(after_failure(&client, 1, &unavailable), FeedState::Backoff(_)));
+
+        let mut changes = ChangesStream::new(client, "feed".to_string(), None);
+        assert!(changes.next().await.unwrap().is_err());
+        assert_eq!(changes.failures, 1);
+        assert!(matches!(changes.state, FeedState::Backoff(_)));
+    }
+
     #[tokio::test]
     async fn should_get_changes() {
         let client = Client::new_local_test().unwrap();
@@ -217,4 +722,43 @@ mod tests {
         assert!(collected_changes.len() == 10);
         t.await.unwrap();
     }
+
+    #[tokio::test]
+    async fn should_get_continuous_changes() {
+        let client = Client::new_local_test().unwrap();
+        let db = client.db("should_get_continuous_changes").await.unwrap();
+        let mut changes = db.changes_stream(Some(json!("now")));
+        assert!(changes.infinite());
+
+        let t = tokio::spawn({
+            let db = db.clone();
+            async move {
+                let mut doc = json!({ "_id": "continuous" });
+                db.create(&mut doc).await.expect("should create a document");
+            }
+        });
+
+        let change = changes.next().await.unwrap().unwrap();
+        assert_eq!(change.id, "continuous");
+        assert!(changes.last_seq().is_some());
+        t.await.unwrap();
+        client.destroy_db("should_get_continuous_changes").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_store_checkpoints_in_local_documents() {
+        let client = Client::new_local_test().unwrap();
+        let db = client.db("should_store_checkpoints_in_local_documents").await.unwrap();
+        let store = LocalDocumentStore::new(db.clone());
+
+        assert_eq!(store.load("consumer").await.unwrap(), None);
+        store.save("consumer", &json!("1-abc")).await.unwrap();
+        store.save("consumer", &json!("2-def")).await.unwrap();
+        assert_eq!(store.load("consumer").await.unwrap(), Some(json!("2-def")));
+
+        client
+            .destroy_db("should_store_checkpoints_in_local_documents")
+            .await
+            .unwrap();
+    }
 }
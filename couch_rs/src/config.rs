@@ -0,0 +1,124 @@
+use crate::client::{Client, CouchRequest};
+use crate::error::{CouchError, CouchResult};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+
+/// The configuration of all sections of a node, by section and key
+pub type ConfigSections = HashMap<String, ConfigSection>;
+
+/// The configuration values of a section, by key
+pub type ConfigSection = HashMap<String, String>;
+
+/// Manages the configuration of a CouchDB node through `/_node/{node}/_config`. Use `_local` as
+/// the node name for the node that handles the request. Changes are persisted by CouchDB, and
+/// most settings take effect immediately. See
+/// [configuration](https://docs.couchdb.org/en/stable/api/server/configuration.html) for details.
+///
+/// Usage:
+/// ```
+/// use couch_rs::error::CouchResult;
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::new_local_test()?;
+///     let config = client.node_config("_local");
+///
+///     let previous = config.set_key("chttpd", "max_http_request_size", "8388608").await?;
+///     println!("max_http_request_size was {}", previous);
+///
+///     let chttpd = config.get_section("chttpd").await?;
+///     assert_eq!(chttpd["max_http_request_size"], "8388608");
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NodeConfig {
+    client: Client,
+    node: String,
+}
+
+impl NodeConfig {
+    pub fn new(client: &Client, node: &str) -> Self {
+        NodeConfig {
+            client: client.clone(),
+            node: node.to_string(),
+        }
+    }
+
+    /// The name of the node
+    pub fn node(&self) -> &str {
+        &self.node
+    }
+
+    fn create_config_path(&self, section: Option<&str>, key: Option<&str>) -> String {
+        let node = &self.node;
+        let mut path = format!("_node/{}/_config", url_encode!(node));
+        if let Some(section) = section {
+            path = format!("{}/{}", path, url_encode!(section));
+        }
+        if let Some(key) = key {
+            path = format!("{}/{}", path, url_encode!(key));
+        }
+        path
+    }
+
+    /// Gets the configuration of all sections
+    pub async fn get_all(&self) -> CouchResult<ConfigSections> {
+        let path = self.create_config_path(None, None);
+        send(self.client.get(&path, None)).await
+    }
+
+    /// Gets the configuration values of a section
+    pub async fn get_section(&self, section: &str) -> CouchResult<ConfigSection> {
+        let path = self.create_config_path(Some(section), None);
+        send(self.client.get(&path, None)).await
+    }
+
+    /// Gets a configuration value
+    pub async fn get_key(&self, section: &str, key: &str) -> CouchResult<String> {
+        let path = self.create_config_path(Some(section), Some(key));
+        send(self.client.get(&path, None)).await
+    }
+
+    /// Sets a configuration value, and returns the previous value (empty when it wasn't set)
+    pub async fn set_key(&self, section: &str, key: &str, value: &str) -> CouchResult<String> {
+        let path = self.create_config_path(Some(section), Some(key));
+        send(self.client.put(&path, js!(value))).await
+    }
+
+    /// Deletes a configuration value, and returns the deleted value
+    pub async fn delete_key(&self, section: &str, key: &str) -> CouchResult<String> {
+        let path = self.create_config_path(Some(section), Some(key));
+        send(self.client.delete(&path, None)).await
+    }
+}
+
+async fn send<T: DeserializeOwned>(request: CouchRequest) -> CouchResult<T> {
+    let response = request.send().await?;
+    let status = response.status();
+
+    if status.is_success() {
+        Ok(response.json().await?)
+    } else {
+        Err(CouchError::from_response(response).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_paths() {
+        let client = Client::new_local_test().unwrap();
+        let config = NodeConfig::new(&client, "couchdb@127.0.0.1");
+        assert_eq!(
+            config.create_config_path(None, None),
+            "_node/couchdb%40127.0.0.1/_config"
+        );
+        assert_eq!(
+            config.create_config_path(Some("chttpd"), Some("port")),
+            "_node/couchdb%40127.0.0.1/_config/chttpd/port"
+        );
+    }
+}
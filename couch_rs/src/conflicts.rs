@@ -0,0 +1,35 @@
+use crate::document::TypedCouchDocument;
+
+/// Resolves a conflict by producing the winning document from all leaf revisions of a
+/// document. See `Database::resolve_conflicts`.
+///
+/// A resolver can be a closure:
+/// ```
+/// use couch_rs::conflicts::ConflictResolver;
+/// use serde_json::Value;
+///
+/// // keep the revision with the highest version
+/// let resolver = |revisions: Vec<Value>| {
+///     revisions
+///         .into_iter()
+///         .max_by_key(|doc| doc["version"].as_u64())
+///         .expect("there is at least one revision")
+/// };
+/// let winner = resolver.resolve(vec![serde_json::json!({"version": 1}), serde_json::json!({"version": 2})]);
+/// assert_eq!(winner["version"], 2);
+/// ```
+pub trait ConflictResolver<T: TypedCouchDocument> {
+    /// Produces the winner from the leaf revisions, which are in no specific order. The `_id`
+    /// and `_rev` of the winner are set by `resolve_conflicts`.
+    fn resolve(&self, revisions: Vec<T>) -> T;
+}
+
+impl<T, F> ConflictResolver<T> for F
+where
+    T: TypedCouchDocument,
+    F: Fn(Vec<T>) -> T,
+{
+    fn resolve(&self, revisions: Vec<T>) -> T {
+        self(revisions)
+    }
+}
@@ -1,22 +1,90 @@
-use crate::changes::ChangesStream;
-use crate::document::{DocumentCollection, TypedCouchDocument};
-use crate::error::{CouchError, CouchResult};
-use crate::types::design::DesignCreated;
-use crate::types::document::{DocumentCreatedResponse, DocumentId};
-use crate::types::find::{FindQuery, FindResult};
-use crate::types::index::{DatabaseIndexList, IndexFields};
-use crate::types::query::{QueriesCollection, QueriesParams, QueryParams};
-use crate::types::view::ViewCollection;
-use crate::{client::Client, types::document::DocumentCreatedResult};
+use crate::attachment::AttachmentStream;
+use crate::cache::{cache_key, CachedDocument, DocumentCache, EtagCache, SharedCache};
+use crate::changes::{seq_param, ChangesStream, COUCH_MAX_TIMEOUT, DEFAULT_HEARTBEAT};
+use crate::conflicts::ConflictResolver;
+use crate::document::{from_typed_value, DocumentCollection, DocumentStream, Typed, TypedCouchDocument};
+use crate::error::{CouchError, CouchResult, RequestOrigin};
+use crate::ids::{Generator, IdGenerator};
+use crate::partition::Partition;
+use crate::patch::{apply_patch, merge_patch, PatchOperation};
+use crate::types::changes::{ChangesRequest, ChangesResponse, Seq};
+use crate::types::design::{DesignCreated, DesignDocDiff, DesignDocument};
+use crate::types::document::{
+    BulkDocsOptions, BulkDocsRequest, BulkGetItem, BulkGetResponse, DocumentCreatedResponse, DocumentId, DocumentRef,
+    OpenRevResponse, OpenRevs, PurgeResult, ReadOptions, RevsDiff, SaveOptions, SaveResult,
+};
+use crate::types::find::{ExplainResult, FindQuery, FindResult, Projected, Projection, SortSpec};
+use crate::types::index::{IndexFields, IndexList, IndexOptions, IndexRequest, IndexType};
+use crate::types::query::{QueriesCollection, QueriesParams, QueryParams, ViewQuery};
+use crate::types::search::{SearchQuery, SearchResult};
+use crate::types::security::SecurityObject;
+use crate::types::system::{CouchResponse, DbInfo, PartitionInfo};
+use crate::types::tasks::{is_shard_of, ActiveTask, CompactionStatus};
+use crate::types::view::{RawViewCollection, ReducedViewCollection, ViewCollection, ViewItem, ViewStream};
 use crate::{
     client::{is_accepted, is_ok},
     types::document::DocumentCreatedDetails,
 };
-use reqwest::StatusCode;
+use crate::{
+    client::{Client, CouchRequest},
+    types::document::DocumentCreatedResult,
+};
+use bytes::Bytes;
+use futures_core::TryStream;
+use futures_util::{stream, TryStreamExt};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE, ETAG, IF_NONE_MATCH};
+use reqwest::{Body, Method, StatusCode};
 use serde::de::DeserializeOwned;
+use serde::Serialize;
 use serde_json::{json, to_string, Value};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::{AsyncRead, AsyncReadExt};
 use tokio::sync::mpsc::Sender;
+use tokio_util::io::ReaderStream;
+
+/// How many times `patch` and `apply_patch` save a document before giving up on conflicts
+const PATCH_ATTEMPTS: u32 = 5;
+
+/// How many fixtures `seed_from_json` and `seed_from_dir` write per `_bulk_docs` request
+const SEED_BATCH_SIZE: usize = 1000;
+
+/// The bookmark of a `_find` response, unless it is empty or `nil`
+fn valid_bookmark(bookmark: Option<String>) -> Option<String> {
+    bookmark.filter(|bookmark| !bookmark.is_empty() && bookmark != "nil")
+}
+
+/// The warning CouchDB includes in a `_find` response when the query is executed without index
+fn is_no_index_warning(warning: &str) -> bool {
+    warning.to_lowercase().contains("no matching index found")
+}
+
+/// Parses fixtures: JSON documents separated by whitespace, or arrays of documents
+fn parse_fixtures(json: &[u8]) -> CouchResult<Vec<Value>> {
+    let mut docs = vec![];
+    for value in serde_json::Deserializer::from_slice(json).into_iter::<Value>() {
+        match value? {
+            Value::Array(values) => docs.extend(values),
+            value => docs.push(value),
+        }
+    }
+    match docs.iter().find(|doc| !doc.is_object()) {
+        Some(doc) => Err(CouchError::new(
+            format!("fixture is not a document: {}", doc),
+            StatusCode::BAD_REQUEST,
+        )),
+        None => Ok(docs),
+    }
+}
+
+fn fixture_error(err: std::io::Error) -> CouchError {
+    CouchError::new(
+        format!("can not read fixtures: {}", err),
+        StatusCode::INTERNAL_SERVER_ERROR,
+    )
+}
 
 /// Database operations on a CouchDB Database
 /// (sometimes called Collection in other NoSQL flavors such as MongoDB).
@@ -24,11 +92,28 @@ use tokio::sync::mpsc::Sender;
 pub struct Database {
     _client: Client,
     name: String,
+    id_generator: Option<Generator>,
+    etag_cache: Option<EtagCache>,
+    document_cache: Option<SharedCache>,
 }
 
 impl Database {
     pub fn new(name: String, client: Client) -> Database {
-        Database { _client: client, name }
+        Database {
+            _client: client,
+            name,
+            id_generator: None,
+            etag_cache: None,
+            document_cache: None,
+        }
+    }
+
+    /// A handle for this database with another client, keeping the id generator and the caches
+    fn with_client(&self, client: Client) -> Database {
+        Database {
+            _client: client,
+            ..self.clone()
+        }
     }
 
     // convenience function to retrieve the name of the database
@@ -36,6 +121,223 @@ impl Database {
         &self.name
     }
 
+    pub(crate) fn client(&self) -> &Client {
+        &self._client
+    }
+
+    /// A handle for this database that sends additional headers with every request. The headers
+    /// override the headers of the client with the same names, and can be set per operation by
+    /// calling `with_headers` again on the handle.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use reqwest::header::{HeaderMap, HeaderValue};
+    /// use serde_json::Value;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     let mut headers = HeaderMap::new();
+    ///     headers.insert("X-Cloudant-IO-Priority", HeaderValue::from_static("low"));
+    ///     let background = db.with_headers(headers);
+    ///
+    ///     let mut headers = HeaderMap::new();
+    ///     headers.insert("traceparent", HeaderValue::from_static("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"));
+    ///     let doc: CouchResult<Value> = background.with_headers(headers).get("some-doc").await;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_headers(&self, headers: HeaderMap) -> Database {
+        self.with_client(self._client.with_headers(headers))
+    }
+
+    /// A handle for this database with another timeout for each request than the timeout of the
+    /// client, e.g. a short timeout for interactive reads, or a long one for queries that may have
+    /// to wait for a view to be built.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use serde_json::Value;
+    /// use std::time::Duration;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     let interactive = db.with_timeout(Duration::from_millis(500));
+    ///     let doc: CouchResult<Value> = interactive.get("some-doc").await;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_timeout(&self, timeout: Duration) -> Database {
+        self.with_client(self._client.with_timeout(timeout))
+    }
+
+    /// A handle for this database whose requests time out at the deadline, e.g. to bound the
+    /// total time of a sequence of operations. Requests after the deadline fail immediately with
+    /// a 408 (Request Timeout) error.
+    pub fn with_deadline(&self, deadline: Instant) -> Database {
+        self.with_client(self._client.with_deadline(deadline))
+    }
+
+    /// A handle for this database that sends its requests as another user, with basic
+    /// authentication, e.g. to save a document as the end user, so that validation functions see
+    /// the end user in the `userCtx`, while other operations are performed as a service account.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     let as_john = db.with_credentials("john", "secret");
+    ///     as_john.create(&mut json!({ "author": "john" })).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_credentials(&self, username: &str, password: &str) -> Database {
+        self.with_client(self._client.with_credentials(username, password))
+    }
+
+    /// A handle for this database that assigns an id from the generator to documents without an
+    /// `_id`, when they are saved, created or written in bulk. Without a generator, `create` and
+    /// `bulk_docs` leave it to the server to assign an id. See `IdGenerator` for an example.
+    pub fn with_id_generator(&self, generator: impl IdGenerator + 'static) -> Database {
+        Database {
+            id_generator: Some(Generator(Arc::new(generator))),
+            ..self.clone()
+        }
+    }
+
+    /// A handle for this database that reads documents with `get` and `get_params` through the
+    /// cache, sending the ETag of a cached document so that CouchDB only returns it when it
+    /// changed. See `EtagCache` for an example.
+    pub fn with_etag_cache(&self, cache: EtagCache) -> Database {
+        Database {
+            etag_cache: Some(cache),
+            ..self.clone()
+        }
+    }
+
+    /// A handle for this database that reads document revisions from the cache, keyed by id and
+    /// revision. `get` asks the server for the current revision with a `HEAD` request, and only
+    /// transfers the document when that revision is not cached, so it never returns an outdated
+    /// revision. `get_params` with only a `rev` reads a cached revision without asking the server.
+    /// Documents that are saved, created, written in bulk or removed through this handle are
+    /// forgotten. See `LruCache` for an example.
+    pub fn with_document_cache(&self, cache: impl DocumentCache + 'static) -> Database {
+        Database {
+            document_cache: Some(SharedCache(Arc::new(cache))),
+            ..self.clone()
+        }
+    }
+
+    /// Forgets a document that is written through this handle
+    fn forget(&self, id: &str) {
+        if let Some(cache) = &self.document_cache {
+            cache.0.remove(id);
+        }
+    }
+
+    /// Prepares a document to be written: sets its timestamps, and assigns an id from the id
+    /// generator, if any, to a document without an id
+    async fn prepare<T: TypedCouchDocument>(&self, doc: &mut T) -> CouchResult<()> {
+        doc.set_timestamps(SystemTime::now());
+        if let Some(generator) = &self.id_generator {
+            if doc.get_id().is_empty() {
+                doc.set_id(&generator.0.generate().await?);
+            }
+        }
+        Ok(())
+    }
+
+    /// A handle for the operations on one partition of a partitioned database
+    pub fn partition(&self, key: &str) -> Partition {
+        Partition::new(self.clone(), key)
+    }
+
+    /// Gets information about the partition `key`: the number of (deleted) documents and the sizes.
+    /// See [partition](https://docs.couchdb.org/en/stable/api/partitioned-dbs.html#get--db-_partition-partition)
+    /// for more details.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::system::CreateDatabaseOptions;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client
+    ///         .db_with_options("sensor_readings", CreateDatabaseOptions::default().partitioned(true))
+    ///         .await?;
+    ///     let info = db.partition_info("sensor-1").await?;
+    ///     println!("{} documents, {} bytes", info.doc_count, info.sizes.active);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn partition_info(&self, key: &str) -> CouchResult<PartitionInfo> {
+        let path = format!("{}/_partition/{}", self.name, url_encode!(key));
+        self._client
+            .get(&path, None)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .map_err(CouchError::from)
+    }
+
+    /// Gets the information of the database, like the number of documents and its sizes.
+    /// See [common](https://docs.couchdb.org/en/stable/api/database/common.html#get--db) for more details.
+    pub async fn info(&self) -> CouchResult<DbInfo> {
+        self._client
+            .get(&self.name, None)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .map_err(CouchError::from)
+    }
+
+    /// Gets the current sequence of the database, e.g. to follow only the changes after it
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let seq = db.update_seq().await?;
+    ///     let changes = db.changes_longpoll(Some(seq)).await?;
+    ///     println!("{} changes", changes.results.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn update_seq(&self) -> CouchResult<Seq> {
+        Ok(self.info().await?.update_seq)
+    }
+
     fn create_raw_path(&self, id: &str) -> String {
         format!("{}/{}", self.name, id)
     }
@@ -56,6 +358,12 @@ impl Database {
         format!("{}/_design/{}/_view/{}", self.name, encoded_design, encoded_view)
     }
 
+    fn create_search_path(&self, design_id: &str, index_id: &str) -> String {
+        let encoded_design = url_encode!(design_id);
+        let encoded_index = url_encode!(index_id);
+        format!("{}/_design/{}/_search/{}", self.name, encoded_design, encoded_index)
+    }
+
     fn create_execute_update_path(&self, design_id: &str, update_id: &str, document_id: &str) -> String {
         let encoded_design = url_encode!(design_id);
         let encoded_update = url_encode!(update_id);
@@ -66,6 +374,35 @@ impl Database {
         )
     }
 
+    fn create_execute_show_path(&self, design_id: &str, show_id: &str, document_id: Option<&str>) -> String {
+        let encoded_design = url_encode!(design_id);
+        let encoded_show = url_encode!(show_id);
+        let path = format!("{}/_design/{}/_show/{}", self.name, encoded_design, encoded_show);
+        match document_id {
+            Some(document_id) => {
+                let encoded_document = url_encode!(document_id);
+                format!("{}/{}", path, encoded_document)
+            }
+            None => path,
+        }
+    }
+
+    fn create_execute_list_path(&self, design_id: &str, list_id: &str, view_id: &str) -> String {
+        let encoded_design = url_encode!(design_id);
+        let encoded_list = url_encode!(list_id);
+        let encoded_view = url_encode!(view_id);
+        format!(
+            "{}/_design/{}/_list/{}/{}",
+            self.name, encoded_design, encoded_list, encoded_view
+        )
+    }
+
+    fn create_attachment_path(&self, id: &str, name: &str) -> String {
+        let encoded_id = url_encode!(id);
+        let encoded_name = url_encode!(name);
+        format!("{}/{}/{}", self.name, encoded_id, encoded_name)
+    }
+
     fn create_compact_path(&self, design_name: &str) -> String {
         let encoded_design = url_encode!(design_name);
         format!("{}/_compact/{}", self.name, encoded_design)
@@ -80,21 +417,125 @@ impl Database {
         is_accepted(request).await
     }
 
-    /// Starts the compaction of all views
+    /// Removes the index files of views that are no longer defined in a design document.
+    /// Same as `view_cleanup`.
     pub async fn compact_views(&self) -> bool {
-        let mut path: String = self.name.clone();
-        path.push_str("/_view_cleanup");
+        self.view_cleanup().await
+    }
 
-        let request = self._client.post(&path, "".into());
+    /// Removes the index files of views that are no longer defined in a design document, e.g.
+    /// after the views of a design document changed
+    pub async fn view_cleanup(&self) -> bool {
+        let request = self._client.post(&self.create_raw_path("_view_cleanup"), "".into());
         is_accepted(request).await
     }
 
-    /// Starts the compaction of a given index
-    pub async fn compact_index(&self, index: &str) -> bool {
-        let request = self._client.post(&self.create_compact_path(index), "".into());
+    /// Starts the compaction of the view indexes of a design document
+    pub async fn compact_view(&self, design_name: &str) -> bool {
+        let request = self._client.post(&self.create_compact_path(design_name), "".into());
         is_accepted(request).await
     }
 
+    /// Starts the compaction of the view indexes of a design document. Same as `compact_view`.
+    pub async fn compact_index(&self, index: &str) -> bool {
+        self.compact_view(index).await
+    }
+
+    /// Reports whether the database or its views are being compacted, with the progress of the
+    /// compaction tasks.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use std::time::Duration;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     db.compact().await;
+    ///
+    ///     loop {
+    ///         let status = db.compaction_status().await?;
+    ///         if !status.is_running() {
+    ///             break;
+    ///         }
+    ///         println!("compacting: {:?}%", status.progress());
+    ///         tokio::time::sleep(Duration::from_secs(1)).await;
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn compaction_status(&self) -> CouchResult<CompactionStatus> {
+        let info = self.info().await?;
+        let tasks = self
+            ._client
+            .active_tasks()
+            .await?
+            .into_iter()
+            .filter(|task| {
+                matches!(task, ActiveTask::DatabaseCompaction(_) | ActiveTask::ViewCompaction(_))
+                    && task.database().is_some_and(|shard| is_shard_of(shard, &self.name))
+            })
+            .collect();
+        Ok(CompactionStatus {
+            compact_running: info.compact_running,
+            tasks,
+        })
+    }
+
+    /// Gets the security object of the database
+    pub async fn get_security(&self) -> CouchResult<SecurityObject> {
+        self._client
+            .get(&self.create_raw_path("_security"), None)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .map_err(CouchError::from)
+    }
+
+    /// Replaces the security object of the database, e.g. to restrict access to some users and
+    /// roles while provisioning it. This requires admin privileges.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::security::{SecurityMembers, SecurityObject};
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let security = SecurityObject::default()
+    ///         .admins(SecurityMembers::default().role("ops"))
+    ///         .members(SecurityMembers::default().role("users"));
+    ///     db.set_security(&security).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn set_security(&self, security: &SecurityObject) -> CouchResult<()> {
+        let response = self
+            ._client
+            .put(&self.create_raw_path("_security"), to_string(security)?)
+            .send()
+            .await?;
+        let status = response.status();
+        let origin = RequestOrigin::of(&response);
+        let data: CouchResponse = response.json().await?;
+
+        if let Some(true) = data.ok {
+            Ok(())
+        } else {
+            Err(CouchError::write_failure(None, data.error, data.reason, status).with_origin(origin))
+        }
+    }
+
     /// Checks if a document ID exists
     ///
     /// Usage:
@@ -177,55 +618,393 @@ impl Database {
     /// }
     ///```
     pub async fn get<T: TypedCouchDocument>(&self, id: &str) -> CouchResult<T> {
-        self._client
-            .get(&self.create_document_path(id), None)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await
-            .map_err(CouchError::from)
+        let Some(cache) = &self.document_cache else {
+            return self.get_document(id, None).await;
+        };
+        let rev = self.current_rev(id).await.map_err(|err| err.with_id(id))?;
+        let cached = match rev.and_then(|rev| cache.0.get(id, &rev)) {
+            Some(cached) => cached,
+            None => self.fetch_into_cache(cache, id, None).await?,
+        };
+        from_typed_value(&cached.document).map_err(|err| err.with_id(id))
     }
 
-    /// Gets documents in bulk with provided IDs list
-    pub async fn get_bulk<T: TypedCouchDocument>(&self, ids: Vec<DocumentId>) -> CouchResult<DocumentCollection<T>> {
-        self.get_bulk_params(ids, None).await
+    /// The current revision of a document, from the ETag of a `HEAD` request
+    async fn current_rev(&self, id: &str) -> CouchResult<Option<String>> {
+        let response = self
+            ._client
+            .head(&self.create_document_path(id), None)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| etag.trim_matches('"').to_string()))
     }
 
-    /// Gets documents in bulk with provided IDs list, as raw Values
-    pub async fn get_bulk_raw(&self, ids: Vec<DocumentId>) -> CouchResult<DocumentCollection<Value>> {
-        self.get_bulk_params(ids, None).await
+    /// Gets a document, and caches the revision that was read
+    async fn fetch_into_cache(
+        &self,
+        cache: &SharedCache,
+        id: &str,
+        args: Option<&HashMap<String, String>>,
+    ) -> CouchResult<CachedDocument> {
+        let document: Value = self.get_document(id, args).await?;
+        let cached = CachedDocument {
+            rev: document.get_rev().into_owned(),
+            document: Arc::new(document),
+        };
+        cache.0.insert(id, cached.clone());
+        Ok(cached)
     }
 
-    /// Each time a document is stored or updated in CouchDB, the internal B-tree is updated.
-    /// Bulk insertion provides efficiency gains in both storage space, and time,
-    /// by consolidating many of the updates to intermediate B-tree nodes.
-    ///
-    /// See the documentation on how to use bulk_docs here: [db-bulk-docs](https://docs.couchdb.org/en/stable/api/database/bulk-api.html#db-bulk-docs)
-    ///
-    /// raw_docs is a vector of documents with or without an ID
-    ///
-    /// This endpoint can also be used to delete a set of documents by including "_deleted": true, in the document to be deleted.
-    /// When deleting or updating, both _id and _rev are mandatory.
-    ///
+    /// Gets one document with the given read options, e.g. to include the data of the attachments,
+    /// or with a read quorum (`r`) in a cluster
     /// Usage:
     /// ```
     /// use couch_rs::error::CouchResult;
-    /// use serde_json::json;
+    /// use couch_rs::types::document::{Attachment, ReadOptions};
+    /// use serde_json::{json, Value};
     ///
     /// const TEST_DB: &str = "test_db";
     ///
     /// #[tokio::main]
     /// async fn main() -> CouchResult<()> {
-    ///    let client = couch_rs::Client::new_local_test()?;
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let mut doc = json!({
+    ///         "_id": "with_inline_attachment",
+    ///         "_attachments": {"note.txt": Attachment::inline("text/plain", b"hello")}
+    ///     });
+    ///     db.save(&mut doc).await?;
+    ///
+    ///     let doc: Value = db.get_params("with_inline_attachment", ReadOptions::default().attachments(true)).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn get_params<T: TypedCouchDocument>(&self, id: &str, options: ReadOptions) -> CouchResult<T> {
+        let params = options.query_params();
+        match (&self.document_cache, &options.rev) {
+            // a revision never changes, so a cached one is never outdated
+            (Some(cache), Some(rev)) if options == ReadOptions::default().rev(rev) => {
+                let cached = match cache.0.get(id, rev) {
+                    Some(cached) => cached,
+                    None => self.fetch_into_cache(cache, id, Some(&params)).await?,
+                };
+                from_typed_value(&cached.document).map_err(|err| err.with_id(id))
+            }
+            _ => self.get_document(id, Some(&params)).await,
+        }
+    }
+
+    async fn get_document<T: TypedCouchDocument>(
+        &self,
+        id: &str,
+        args: Option<&HashMap<String, String>>,
+    ) -> CouchResult<T> {
+        let get = async {
+            let path = self.create_document_path(id);
+            if let Some(cache) = &self.etag_cache {
+                return self.get_cached(cache, &path, args).await;
+            }
+            let response = self._client.get(&path, args).send().await?.error_for_status()?;
+            match T::type_name() {
+                Some(_) => from_typed_value(&response.json().await?),
+                None => response.json().await.map_err(CouchError::from),
+            }
+        };
+        get.await.map_err(|err| err.with_id(id))
+    }
+
+    /// Gets a document with `If-None-Match`, deserializing it from the cache when it didn't change
+    async fn get_cached<T: TypedCouchDocument>(
+        &self,
+        cache: &EtagCache,
+        path: &str,
+        args: Option<&HashMap<String, String>>,
+    ) -> CouchResult<T> {
+        let key = cache_key(path, args);
+        let cached = cache.get(&key);
+        let mut request = self._client.get(path, args);
+        if let Some(cached) = &cached {
+            request = request.header(IF_NONE_MATCH, cached.etag.clone());
+        }
+        let response = request.send().await?;
+        if let (StatusCode::NOT_MODIFIED, Some(cached)) = (response.status(), &cached) {
+            return from_typed_value(&cached.document);
+        }
+        if response.status() == StatusCode::NOT_FOUND {
+            cache.remove(&key);
+        }
+
+        let response = response.error_for_status()?;
+        let etag = response.headers().get(ETAG).cloned();
+        let document: Value = response.json().await?;
+        let typed = from_typed_value(&document)?;
+        if let Some(etag) = etag {
+            cache.insert(key, etag, document);
+        }
+        Ok(typed)
+    }
+
+    /// Gets the given revisions of a document, e.g. all leaf revisions to resolve conflicts.
+    /// Revisions that are not in the database are left out.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::document::{OpenRevs, ReadOptions};
+    /// use serde_json::Value;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let doc: Value = db.get_params("conflicting_doc", ReadOptions::default().conflicts(true)).await?;
+    ///     if doc.get("_conflicts").is_some() {
+    ///         let leafs: Vec<Value> = db.get_open_revs("conflicting_doc", OpenRevs::All).await?;
+    ///         println!("{} conflicting revisions", leafs.len());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn get_open_revs<T: TypedCouchDocument>(&self, id: &str, revs: OpenRevs) -> CouchResult<Vec<T>> {
+        let options = HashMap::from([(s!("open_revs"), revs.query_value()?)]);
+        let response: Vec<OpenRevResponse<T>> = self
+            ._client
+            .get(&self.create_document_path(id), Some(&options))
+            .header(ACCEPT, HeaderValue::from_static("application/json"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(response
+            .into_iter()
+            .filter_map(|rev| match rev {
+                OpenRevResponse::Ok(doc) => Some(doc),
+                OpenRevResponse::Missing(_) => None,
+            })
+            .collect())
+    }
+
+    /// Resolves the conflicts of a document: the `resolver` produces the winner from all leaf
+    /// revisions, and in a single `_bulk_docs` request the winner is saved as the next revision of
+    /// the current winning revision, and the other leaf revisions are deleted. Returns the winner,
+    /// with its new `_rev`. When the document has no conflicts, it's returned without calling the
+    /// resolver.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use serde_json::Value;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     // merge the tags of all revisions
+    ///     let resolver = |mut revisions: Vec<Value>| {
+    ///         let mut tags: Vec<Value> = revisions.iter().flat_map(|doc| doc["tags"].as_array().cloned().unwrap_or_default()).collect();
+    ///         tags.dedup();
+    ///         let mut winner = revisions.remove(0);
+    ///         winner["tags"] = Value::from(tags);
+    ///         winner
+    ///     };
+    ///     let resolved = db.resolve_conflicts("conflicting_doc", &resolver).await?;
+    ///     println!("resolved as {}", resolved["_rev"]);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn resolve_conflicts<T: TypedCouchDocument, R: ConflictResolver<T>>(
+        &self,
+        id: &str,
+        resolver: &R,
+    ) -> CouchResult<T> {
+        let current: Value = self.get_params(id, ReadOptions::default().conflicts(true)).await?;
+        let conflicts: Vec<String> = match current.get("_conflicts") {
+            Some(conflicts) => serde_json::from_value(conflicts.clone())?,
+            None => vec![],
+        };
+        if conflicts.is_empty() {
+            return Ok(serde_json::from_value(current)?);
+        }
+
+        let mut winner = resolver.resolve(self.get_open_revs(id, OpenRevs::All).await?);
+        winner.set_id(id);
+        winner.set_rev(&current.get_rev());
+
+        let mut docs = vec![serde_json::to_value(&winner)?];
+        docs.extend(
+            conflicts
+                .into_iter()
+                .map(|rev| json!({ "_id": id, "_rev": rev, "_deleted": true })),
+        );
+        let mut results = self.bulk_docs(&mut docs).await?.into_iter();
+
+        let details = results
+            .next()
+            .unwrap_or_else(|| Err(CouchError::new(s!("missing result"), StatusCode::INTERNAL_SERVER_ERROR)))?;
+        if let Some(Err(err)) = results.find(Result::is_err) {
+            return Err(err);
+        }
+        winner.set_rev(&details.rev);
+        Ok(winner)
+    }
+
+    /// Gets documents in bulk with provided IDs list
+    pub async fn get_bulk<T: TypedCouchDocument>(&self, ids: Vec<DocumentId>) -> CouchResult<DocumentCollection<T>> {
+        self.get_bulk_params(ids, None).await
+    }
+
+    /// Populates the references to documents of this database, with a single `get_bulk`. The
+    /// `refs` function picks the references of a document, e.g. a single id field, a list of ids,
+    /// or fields of nested structs. The references to documents of another database are
+    /// populated by calling `join` on that database. A reference to a document that doesn't exist
+    /// is left as it is.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::document::DocumentCollection;
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::document::DocumentRef;
+    /// use couch_rs::types::find::FindQuery;
+    /// use couch_rs::CouchDocument;
+    /// use couch_rs::document::TypedCouchDocument;
+    /// use serde::{Deserialize, Serialize};
+    /// use serde_json::Value;
+    ///
+    /// #[derive(Serialize, Deserialize, Clone, CouchDocument)]
+    /// pub struct User {
+    ///     pub _id: String,
+    ///     pub _rev: String,
+    ///     pub name: String,
+    /// }
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// pub struct Review {
+    ///     pub reviewer: DocumentRef<User>,
+    /// }
+    ///
+    /// #[derive(Serialize, Deserialize, CouchDocument)]
+    /// pub struct Post {
+    ///     pub _id: String,
+    ///     pub _rev: String,
+    ///     pub author: DocumentRef<User>,
+    ///     pub readers: Vec<DocumentRef<User>>,
+    ///     pub reviews: Vec<Review>,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let posts = client.db("posts").await?;
+    ///     let users = client.db("users").await?;
+    ///     let mut found: DocumentCollection<Post> = posts.find(&FindQuery::find_all()).await?;
+    ///     users
+    ///         .join(&mut found.rows, |post: &mut Post| {
+    ///             let mut refs = vec![&mut post.author];
+    ///             refs.extend(post.readers.iter_mut());
+    ///             refs.extend(post.reviews.iter_mut().map(|review| &mut review.reviewer));
+    ///             refs
+    ///         })
+    ///         .await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn join<T, R, F>(&self, docs: &mut [T], refs: F) -> CouchResult<()>
+    where
+        R: TypedCouchDocument + Clone,
+        F: for<'a> Fn(&'a mut T) -> Vec<&'a mut DocumentRef<R>>,
+    {
+        let mut ids: Vec<DocumentId> = vec![];
+        for doc in docs.iter_mut() {
+            for reference in refs(doc) {
+                if let DocumentRef::Ref(id) = reference {
+                    if !ids.contains(id) {
+                        ids.push(id.clone());
+                    }
+                }
+            }
+        }
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let targets: HashMap<String, R> = self
+            .get_bulk::<R>(ids)
+            .await?
+            .into_iter()
+            .map(|target| (target.get_id().into_owned(), target))
+            .collect();
+        for doc in docs.iter_mut() {
+            for reference in refs(doc) {
+                let target = match reference {
+                    DocumentRef::Ref(id) => targets.get(id.as_str()),
+                    DocumentRef::Populated(_) => None,
+                };
+                if let Some(target) = target {
+                    *reference = DocumentRef::Populated(target.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets documents in bulk with provided IDs list, as raw Values
+    pub async fn get_bulk_raw(&self, ids: Vec<DocumentId>) -> CouchResult<DocumentCollection<Value>> {
+        self.get_bulk_params(ids, None).await
+    }
+
+    /// Each time a document is stored or updated in CouchDB, the internal B-tree is updated.
+    /// Bulk insertion provides efficiency gains in both storage space, and time,
+    /// by consolidating many of the updates to intermediate B-tree nodes.
+    ///
+    /// See the documentation on how to use bulk_docs here: [db-bulk-docs](https://docs.couchdb.org/en/stable/api/database/bulk-api.html#db-bulk-docs)
+    ///
+    /// raw_docs is a vector of documents with or without an ID
+    ///
+    /// This endpoint can also be used to delete a set of documents by including "_deleted": true, in the document to be deleted.
+    /// When deleting or updating, both _id and _rev are mandatory.
+    ///
+    /// The result holds an entry per document, in the order of `raw_docs`: the new revision, or
+    /// the error of that document, with its `id`. Use e.g. `CouchError::is_conflict` or
+    /// `CouchError::rejection` to find out why a document was not written. The request as a whole
+    /// only fails when CouchDB rejects it, e.g. when the database does not exist.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///    let client = couch_rs::Client::new_local_test()?;
     ///    let db = client.db(TEST_DB).await?;
     ///
-    ///    let _ndoc_result = db
+    ///    let results = db
     ///         .bulk_docs(&mut vec![
     ///             json!({"_id": "first", "thing": true}),
     ///             json!({"_id": "second", "thing": false}),
     ///         ]).await?;
     ///
+    ///    for result in results {
+    ///        match result {
+    ///            Ok(details) => println!("{} saved as {}", details.id, details.rev),
+    ///            Err(err) if err.is_conflict() => println!("{:?} was updated concurrently", err.id()),
+    ///            Err(err) => println!("{:?} failed: {}", err.id(), err.message()),
+    ///        }
+    ///    }
     ///    return Ok(());
     /// }
     /// ```
@@ -233,15 +1012,89 @@ impl Database {
         &self,
         raw_docs: &mut [T],
     ) -> CouchResult<Vec<DocumentCreatedResult>> {
-        let body = format!(r#"{{"docs":{} }}"#, to_string(raw_docs)?);
+        self.bulk_docs_params(raw_docs, BulkDocsOptions::default()).await
+    }
+
+    /// Writes documents in bulk with the given options. With `new_edits(false)` the documents
+    /// are stored with the `_rev` they hold, and their `_revisions`, instead of being assigned
+    /// a new revision. This allows writing revision trees from another database verbatim, as
+    /// replicators and import tools do. Those documents are not prepared either: their timestamps
+    /// are kept, and an id is not generated.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::document::BulkDocsOptions;
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     let mut docs = vec![json!({
+    ///         "_id": "imported",
+    ///         "_rev": "2-7051cbe5c8faecd085a3fa619e6e6337",
+    ///         "_revisions": { "start": 2, "ids": ["7051cbe5c8faecd085a3fa619e6e6337", "967a00dff5e02add41819138abb3284d"] },
+    ///         "thing": true
+    ///     })];
+    ///     db.bulk_docs_params(&mut docs, BulkDocsOptions::default().new_edits(false)).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "couchdb.bulk_docs", skip_all, fields(db.name = %self.name, doc_count = raw_docs.len()))
+    )]
+    pub async fn bulk_docs_params<T: TypedCouchDocument>(
+        &self,
+        raw_docs: &mut [T],
+        options: BulkDocsOptions,
+    ) -> CouchResult<Vec<DocumentCreatedResult>> {
+        if options.new_edits != Some(false) {
+            for doc in raw_docs.iter_mut() {
+                self.prepare(doc).await?;
+            }
+        }
+        let docs: Vec<_> = raw_docs.iter().map(Typed).collect();
+        let request = BulkDocsRequest {
+            docs: &docs,
+            options: &options,
+        };
+        let body = to_string(&request)?;
         let response = self
             ._client
             .post(&self.create_raw_path("_bulk_docs"), body)
             .send()
-            .await?;
+            .await;
+        raw_docs.iter().for_each(|doc| self.forget(&doc.get_id()));
+        let response = response?;
+        let status = response.status();
 
+        if !status.is_success() {
+            return Err(CouchError::from_response(response).await);
+        }
         let data: Vec<DocumentCreatedResponse> = response.json().await?;
 
+        if options.new_edits == Some(false) {
+            // only the documents that could not be written are reported
+            return Ok(raw_docs
+                .iter()
+                .map(|doc| {
+                    let id = doc.get_id();
+                    match data.iter().find(|response| response.id.as_deref() == Some(&id)) {
+                        Some(response) => response.clone().into(),
+                        None => Ok(DocumentCreatedDetails {
+                            id: id.into_owned(),
+                            rev: doc.get_rev().into_owned(),
+                        }),
+                    }
+                })
+                .collect());
+        }
+
         if raw_docs.len() != data.len() {
             return Err(CouchError::new(
                 format!(
@@ -254,7 +1107,7 @@ impl Database {
         }
         let result = raw_docs
             .iter_mut()
-            .zip(data.into_iter())
+            .zip(data)
             .map(|(doc, response): (&mut T, DocumentCreatedResponse)| {
                 let result: DocumentCreatedResult = response.into();
                 match result {
@@ -311,6 +1164,10 @@ impl Database {
     ///     Ok(())
     /// }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "couchdb.get_bulk", skip_all, fields(db.name = %self.name, doc_count = ids.len()))
+    )]
     pub async fn get_bulk_params<T: TypedCouchDocument>(
         &self,
         ids: Vec<DocumentId>,
@@ -331,6 +1188,54 @@ impl Database {
         Ok(DocumentCollection::new(response.json().await?))
     }
 
+    /// Gets documents, or specific revisions of them, in a single `_bulk_get` request. Unlike
+    /// `get_bulk`, a revision can be given per document, and a document that can't be read is
+    /// returned as an error in its position, instead of being left out.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::document::BulkGetItem;
+    /// use serde_json::Value;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let items = vec![BulkGetItem::new("first"), BulkGetItem::new("second").rev("1-967a00dff5e02add41819138abb3284d")];
+    ///     for result in db.bulk_get::<Value>(items).await? {
+    ///         match result {
+    ///             Ok(doc) => println!("read {}", doc["_id"]),
+    ///             Err(err) => println!("failed to read {:?}: {}", err.id(), err.message()),
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "couchdb.bulk_get", skip_all, fields(db.name = %self.name, doc_count = docs.len()))
+    )]
+    pub async fn bulk_get<T: TypedCouchDocument>(&self, docs: Vec<BulkGetItem>) -> CouchResult<Vec<CouchResult<T>>> {
+        let response: BulkGetResponse<T> = self
+            ._client
+            .post(&self.create_raw_path("_bulk_get"), js!(json!({ "docs": docs })))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(response
+            .results
+            .into_iter()
+            .flat_map(|result| result.docs)
+            .map(CouchResult::from)
+            .collect())
+    }
+
     /// Gets all the documents in database
     pub async fn get_all<T: TypedCouchDocument>(&self) -> CouchResult<DocumentCollection<T>> {
         self.get_all_params(None).await
@@ -417,6 +1322,82 @@ impl Database {
         }
     }
 
+    /// Streams the documents that match a Mango query, following the bookmarks of the results,
+    /// `page_size` documents at a time (1000 when 0). The `limit` and `skip` of the query apply
+    /// to the whole stream. The stream ends when a page is not full, or CouchDB returns the same
+    /// bookmark again.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::find::SelectorBuilder;
+    /// use futures_util::TryStreamExt;
+    /// use serde_json::Value;
+    ///
+    /// const TEST_DB: &str = "user_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let query = SelectorBuilder::new().eq("last_name", "Doe").into_query();
+    ///     let mut users = db.find_stream::<Value>(query, 200);
+    ///     while let Some(user) = users.try_next().await? {
+    ///         println!("{}", user["first_name"]);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn find_stream<T>(&self, query: FindQuery, page_size: u64) -> DocumentStream<T>
+    where
+        T: TypedCouchDocument + Send + 'static,
+    {
+        let page_size = if page_size > 0 { page_size } else { 1000 };
+        let db = self.clone();
+        let remaining = query.limit;
+
+        let pages = stream::unfold(Some((query, remaining)), move |state| {
+            let db = db.clone();
+            async move {
+                let (mut query, remaining) = state?;
+                let limit = remaining.map_or(page_size, |r| r.min(page_size));
+                if limit == 0 {
+                    return None;
+                }
+
+                query.limit = Some(limit);
+                let page = match db.find_result::<T>(&query).await {
+                    Ok((status, data)) => {
+                        let fetched = data.docs.as_ref().map_or(0, |docs| docs.len() as u64);
+                        Self::find_collection(status, data).map(|page| (page, fetched))
+                    }
+                    Err(err) => Err(err),
+                };
+                let (page, fetched) = match page {
+                    Ok(page) => page,
+                    Err(err) => return Some((Err(err), None)),
+                };
+
+                let next = match page.bookmark {
+                    Some(bookmark) if fetched == limit && query.bookmark.as_ref() != Some(&bookmark) => {
+                        // the skip only applies to the first page
+                        query.skip = None;
+                        query.bookmark = Some(bookmark);
+                        Some((query, remaining.map(|r| r - limit)))
+                    }
+                    _ => None,
+                };
+                Some((Ok(page.rows), next))
+            }
+        });
+
+        Box::pin(
+            pages
+                .map_ok(|rows| stream::iter(rows.into_iter().map(Ok)))
+                .try_flatten(),
+        )
+    }
+
     /// Executes multiple specified built-in view queries of all documents in this database.
     /// This enables you to request multiple queries in a single request, in place of multiple POST /{db}/_all_docs requests.
     /// [More information](https://docs.couchdb.org/en/stable/api/database/bulk-api.html#sending-multiple-queries-to-a-database)
@@ -464,7 +1445,7 @@ impl Database {
         &self,
         queries: QueriesParams,
     ) -> CouchResult<Vec<ViewCollection<Value, Value, Value>>> {
-        self.query_view_many(&self.create_raw_path("_all_docs/queries"), queries)
+        self.post_queries(&self.create_raw_path("_all_docs/queries"), &queries)
             .await
     }
 
@@ -475,25 +1456,65 @@ impl Database {
         view_name: &str,
         queries: QueriesParams,
     ) -> CouchResult<Vec<ViewCollection<Value, Value, Value>>> {
-        self.query_view_many(&self.create_query_view_path(design_name, view_name), queries)
-            .await
+        let path = format!("{}/queries", self.create_query_view_path(design_name, view_name));
+        self.post_queries(&path, &queries).await
+    }
+
+    /// Executes multiple queries against a view in one request, e.g. to fetch several key ranges.
+    /// The result sets are returned in the order of the queries. To fetch a set of keys in a single
+    /// query, use `ViewQuery::keys` with `query_view` instead.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::query::ViewQuery;
+    /// use couch_rs::types::view::{CouchFunc, CouchViews, ViewCollection};
+    /// use serde_json::{json, Value};
+    ///
+    /// const TEST_DB: &str = "view_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let views = CouchViews::new("by_date", CouchFunc::new("function(doc) { emit([doc.year, doc.month], null); }", None));
+    ///     db.create_view("dates", views).await?;
+    ///
+    ///     let queries = vec![
+    ///         ViewQuery::default().start_key(json!([2020, 1])).end_key(json!([2020, 3])),
+    ///         ViewQuery::default().start_key(json!([2021, 1])).end_key(json!([2021, 3])),
+    ///         ViewQuery::default().keys(vec![json!([2022, 6])]),
+    ///     ];
+    ///     let results: Vec<ViewCollection<Value, Value, Value>> = db.query_view_many("dates", "by_date", queries).await?;
+    ///     assert_eq!(results.len(), 3);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn query_view_many<K: DeserializeOwned, V: DeserializeOwned, D: TypedCouchDocument>(
+        &self,
+        design_name: &str,
+        view_name: &str,
+        queries: Vec<ViewQuery>,
+    ) -> CouchResult<Vec<ViewCollection<K, V, D>>> {
+        let path = format!("{}/queries", self.create_query_view_path(design_name, view_name));
+        self.post_queries(&path, &json!({ "queries": queries })).await
     }
 
-    async fn query_view_many(
+    async fn post_queries<Q: Serialize, K: DeserializeOwned, V: DeserializeOwned, D: TypedCouchDocument>(
         &self,
         view_path: &str,
-        queries: QueriesParams,
-    ) -> CouchResult<Vec<ViewCollection<Value, Value, Value>>> {
+        queries: &Q,
+    ) -> CouchResult<Vec<ViewCollection<K, V, D>>> {
         // we use POST here, because this allows for a larger set of keys to be provided, compared
         // to a GET call. It provides the same functionality
         let response = self
             ._client
-            .post(view_path, js!(&queries))
+            .post(view_path, js!(queries))
             .send()
             .await?
             .error_for_status()?;
 
-        let results: QueriesCollection<Value, Value, Value> = response.json().await?;
+        let results: QueriesCollection<K, V, D> = response.json().await?;
         Ok(results.results)
     }
 
@@ -582,11 +1603,114 @@ impl Database {
     /// }
     /// ```
     pub async fn find<T: TypedCouchDocument>(&self, query: &FindQuery) -> CouchResult<DocumentCollection<T>> {
-        let path = self.create_raw_path("_find");
-        let response = self._client.post(&path, js!(query)).send().await?;
-        let status = response.status();
-        let data: FindResult<T> = response.json().await?;
+        let (status, data) = self.find_result(query).await?;
+        Self::find_collection(status, data)
+    }
+
+    /// Finds documents like `find`, but when CouchDB reports that no index matches the query, an
+    /// index is created on the fields of the selector (and the sort) and the query is retried
+    /// once. The index is named `auto-` followed by the fields, so it is reused by later queries
+    /// on the same fields.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::find::SelectorBuilder;
+    /// use serde_json::Value;
+    ///
+    /// const TEST_DB: &str = "user_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let query = SelectorBuilder::new().eq("last_name", "Doe").into_query();
+    ///     let docs = db.find_with_auto_index::<Value>(&query).await?;
+    ///     println!("found {} documents", docs.total_rows);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn find_with_auto_index<T: TypedCouchDocument>(
+        &self,
+        query: &FindQuery,
+    ) -> CouchResult<DocumentCollection<T>> {
+        let (status, data) = self.find_result(query).await?;
+        if !data.warning.as_deref().is_some_and(is_no_index_warning) {
+            return Self::find_collection(status, data);
+        }
+
+        let fields = query.index_fields();
+        if fields.is_empty() {
+            return Self::find_collection(status, data);
+        }
+
+        let name = format!("auto-{}", fields.join("-"));
+        let spec = IndexFields::new(fields.into_iter().map(SortSpec::Simple).collect());
+        self.ensure_index(&name, spec).await?;
+        self.find(query).await
+    }
+
+    /// Finds documents like `find`, but only reads the fields of the projection `P`, which cuts
+    /// the transfer of wide documents when only some fields are needed. The `fields` of the
+    /// query are replaced by the fields of `P`.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::find::{Projection, SelectorBuilder};
+    /// use couch_rs::Projection;
+    /// use serde::Deserialize;
+    ///
+    /// const TEST_DB: &str = "user_db";
+    ///
+    /// #[derive(Deserialize, Projection)]
+    /// pub struct UserName {
+    ///     pub _id: String,
+    ///     pub first_name: String,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let query = SelectorBuilder::new().eq("last_name", "Doe").into_query();
+    ///     let names = db.find_fields::<UserName>(&query).await?;
+    ///     for name in names.docs {
+    ///         println!("{}: {}", name._id, name.first_name);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn find_fields<P: Projection>(&self, query: &FindQuery) -> CouchResult<Projected<P>> {
+        let query = query
+            .clone()
+            .fields(P::fields().iter().map(|field| field.to_string()).collect());
+        let (status, data) = self.find_result::<P>(&query).await?;
+        match data.docs {
+            Some(docs) => Ok(Projected {
+                docs,
+                bookmark: valid_bookmark(data.bookmark),
+            }),
+            None if data.error.is_some() => Err(CouchError::write_failure(None, data.error, data.reason, status)),
+            None => Ok(Projected {
+                docs: vec![],
+                bookmark: None,
+            }),
+        }
+    }
 
+    async fn find_result<T: DeserializeOwned>(&self, query: &FindQuery) -> CouchResult<(StatusCode, FindResult<T>)> {
+        let path = self.create_raw_path("_find");
+        let response = self._client.post(&path, js!(query)).send().await?;
+        let status = response.status();
+        let data: FindResult<T> = response.json().await?;
+        Ok((status, data))
+    }
+
+    pub(crate) fn find_collection<T: TypedCouchDocument>(
+        status: StatusCode,
+        data: FindResult<T>,
+    ) -> CouchResult<DocumentCollection<T>> {
         if let Some(doc_val) = data.docs {
             let documents: Vec<T> = doc_val
                 .into_iter()
@@ -597,19 +1721,47 @@ impl Database {
                 })
                 .collect();
 
-            let mut bookmark = Option::None;
-            let returned_bookmark = data.bookmark.unwrap_or_default();
+            Ok(DocumentCollection::new_from_documents(
+                documents,
+                valid_bookmark(data.bookmark),
+            ))
+        } else if data.error.is_some() {
+            Err(CouchError::write_failure(None, data.error, data.reason, status))
+        } else {
+            Ok(DocumentCollection::default())
+        }
+    }
 
-            if returned_bookmark != "nil" && !returned_bookmark.is_empty() {
-                // a valid bookmark has been returned
-                bookmark.replace(returned_bookmark);
-            }
+    /// Explains how CouchDB executes a Mango query, e.g. which index is used, without running it.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::find::SelectorBuilder;
+    ///
+    /// const TEST_DB: &str = "user_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let query = SelectorBuilder::new().eq("first_name", "John").into_query();
+    ///     let explained = db.explain(&query).await?;
+    ///     if explained.is_full_scan() {
+    ///         println!("no index for {}", query);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn explain(&self, query: &FindQuery) -> CouchResult<ExplainResult> {
+        let path = self.create_raw_path("_explain");
+        let response = self._client.post(&path, js!(query)).send().await?;
+        let status = response.status();
 
-            Ok(DocumentCollection::new_from_documents(documents, bookmark))
-        } else if let Some(err) = data.error {
-            Err(CouchError::new(err, status))
+        if status.is_success() {
+            Ok(response.json().await?)
         } else {
-            Ok(DocumentCollection::default())
+            Err(CouchError::from_response(response).await)
         }
     }
 
@@ -665,10 +1817,14 @@ impl Database {
     /// }
     ///```
     pub async fn save<T: TypedCouchDocument>(&self, doc: &mut T) -> DocumentCreatedResult {
+        self.prepare(doc).await?;
         let id = doc.get_id().to_string();
-        let body = to_string(&doc)?;
-        let response = self._client.put(&self.create_document_path(&id), body).send().await?;
+        let body = to_string(&Typed(&*doc))?;
+        let response = self._client.put(&self.create_document_path(&id), body).send().await;
+        self.forget(&id);
+        let response = response.map_err(|err| err.with_id(&id))?;
         let status = response.status();
+        let origin = RequestOrigin::of(&response);
         let data: DocumentCreatedResponse = response.json().await?;
 
         if let (Some(true), Some(id), Some(rev)) = (data.ok, data.id, data.rev) {
@@ -676,8 +1832,89 @@ impl Database {
             doc.set_rev(&rev);
             Ok(DocumentCreatedDetails { id, rev })
         } else {
-            let err = data.error.unwrap_or_else(|| s!("unspecified error"));
-            Err(CouchError::new(err, status))
+            Err(CouchError::write_failure(Some(id), data.error, data.reason, status).with_origin(origin))
+        }
+    }
+
+    /// Saves a document with the given options, e.g. in batch mode for high throughput writes
+    /// that don't need to be durable one by one, or with a write quorum (`w`) in a cluster. A batched write is reported as
+    /// `SaveResult::Accepted`, without a new revision; the `_rev` of `doc` is left unchanged.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::document::{SaveOptions, SaveResult};
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let mut entry = json!({ "_id": "log-1", "message": "started" });
+    ///     let result = db.save_params(&mut entry, SaveOptions::default().batch(true)).await?;
+    ///     assert!(matches!(result, SaveResult::Accepted { .. }));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn save_params<T: TypedCouchDocument>(
+        &self,
+        doc: &mut T,
+        options: SaveOptions,
+    ) -> CouchResult<SaveResult> {
+        self.prepare(doc).await?;
+        let id = doc.get_id().to_string();
+        let body = to_string(&Typed(&*doc))?;
+        let request = self
+            ._client
+            .request(
+                Method::PUT,
+                &self.create_document_path(&id),
+                Some(&options.query_params()),
+            )
+            .body(body);
+        let result = Self::save_result(doc, request).await;
+        self.forget(&id);
+        result
+    }
+
+    /// Creates a document with the given options, see `save_params`
+    pub async fn create_params<T: TypedCouchDocument>(
+        &self,
+        doc: &mut T,
+        options: SaveOptions,
+    ) -> CouchResult<SaveResult> {
+        self.prepare(doc).await?;
+        let body = to_string(&Typed(&*doc))?;
+        let request = self
+            ._client
+            .request(Method::POST, &self.name, Some(&options.query_params()))
+            .body(body);
+        let result = Self::save_result(doc, request).await;
+        self.forget(&doc.get_id());
+        result
+    }
+
+    async fn save_result<T: TypedCouchDocument>(doc: &mut T, request: CouchRequest) -> CouchResult<SaveResult> {
+        let response = request.send().await.map_err(|err| err.with_id(&doc.get_id()))?;
+        let status = response.status();
+        let origin = RequestOrigin::of(&response);
+        let data: DocumentCreatedResponse = response.json().await?;
+
+        match (data.ok, data.id, data.rev) {
+            (Some(true), Some(id), Some(rev)) => {
+                doc.set_id(&id);
+                doc.set_rev(&rev);
+                Ok(SaveResult::Created(DocumentCreatedDetails { id, rev }))
+            }
+            (Some(true), Some(id), None) if status == StatusCode::ACCEPTED => {
+                doc.set_id(&id);
+                Ok(SaveResult::Accepted { id })
+            }
+            _ => Err(CouchError::write_failure(None, data.error, data.reason, status)
+                .with_origin(origin)
+                .with_id(&doc.get_id())),
         }
     }
 
@@ -709,9 +1946,13 @@ impl Database {
     /// }
     /// ```
     pub async fn create<T: TypedCouchDocument>(&self, doc: &mut T) -> DocumentCreatedResult {
-        let response = self._client.post(&self.name, to_string(&doc)?).send().await?;
+        self.prepare(doc).await?;
+        let response = self._client.post(&self.name, to_string(&Typed(&*doc))?).send().await;
+        self.forget(&doc.get_id());
+        let response = response?;
 
         let status = response.status();
+        let origin = RequestOrigin::of(&response);
         let data: DocumentCreatedResponse = response.json().await?;
 
         if let Some(true) = data.ok {
@@ -722,8 +1963,9 @@ impl Database {
             doc.set_rev(&rev);
             Ok(DocumentCreatedDetails { id, rev })
         } else {
-            let err = data.error.unwrap_or_else(|| s!("unspecified error"));
-            Err(CouchError::new(err, status))
+            Err(CouchError::write_failure(None, data.error, data.reason, status)
+                .with_origin(origin)
+                .with_id(&doc.get_id()))
         }
     }
 
@@ -783,13 +2025,169 @@ impl Database {
         }
     }
 
+    /// Saves a document, and when it conflicts with a concurrent update, fetches the current
+    /// version of the document, merges it with `TypedCouchDocument::merge`, and retries, at most
+    /// `max_attempts` times in total. The document must have an `_id`.
+    pub async fn upsert_with_retry<T: TypedCouchDocument>(
+        &self,
+        doc: &mut T,
+        max_attempts: u32,
+    ) -> DocumentCreatedResult {
+        self.upsert_with_merge(doc, max_attempts, T::merge).await
+    }
+
+    /// Saves a document, and when it conflicts with a concurrent update, fetches the current
+    /// version of the document, merges it into `doc` with the `merge` closure, and retries, at
+    /// most `max_attempts` times in total. The document must have an `_id`.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     // increment a counter, even when other processes update it concurrently
+    ///     let mut counter = json!({ "_id": "counter", "count": 1 });
+    ///     db.upsert_with_merge(&mut counter, 5, |doc, current| {
+    ///         doc["count"] = json!(current["count"].as_u64().unwrap_or_default() + 1);
+    ///     })
+    ///     .await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn upsert_with_merge<T, F>(&self, doc: &mut T, max_attempts: u32, mut merge: F) -> DocumentCreatedResult
+    where
+        T: TypedCouchDocument,
+        F: FnMut(&mut T, T),
+    {
+        let id = doc.get_id().into_owned();
+        let mut attempt = 1;
+
+        loop {
+            match self.save(doc).await {
+                Err(err) if err.is_conflict() && attempt < max_attempts => {
+                    attempt += 1;
+                    match self.get::<T>(&id).await {
+                        Ok(current) => {
+                            let rev = current.get_rev().into_owned();
+                            merge(doc, current);
+                            doc.set_rev(&rev);
+                        }
+                        // deleted in the meantime, so the document can be created again
+                        Err(err) if err.is_not_found() => doc.set_rev(""),
+                        Err(err) => return Err(err),
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Updates a document with a [JSON Merge Patch](https://datatracker.ietf.org/doc/html/rfc7396),
+    /// e.g. to change a few fields of a large document: the document is fetched, patched and
+    /// saved, and when the save conflicts with a concurrent update, the patch is applied to the
+    /// current version and saved again, at most 5 times in total. Returns the patched document,
+    /// with its new `_rev`.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     db.create(&mut json!({ "_id": "john", "name": "John", "nickname": "Johnny" })).await?;
+    ///
+    ///     // change the name, and remove the nickname
+    ///     let patched = db.patch("john", &json!({ "name": "John Doe", "nickname": null })).await?;
+    ///     assert_eq!(patched["name"], "John Doe");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn patch(&self, id: &str, patch: &Value) -> CouchResult<Value> {
+        let apply = |doc: &mut Value| {
+            let rev = doc.get_rev().into_owned();
+            merge_patch(doc, patch);
+            doc.set_id(id);
+            doc.set_rev(&rev);
+        };
+
+        let mut doc: Value = self.get(id).await?;
+        apply(&mut doc);
+        self.upsert_with_merge(&mut doc, PATCH_ATTEMPTS, |doc, current| {
+            *doc = current;
+            apply(doc);
+        })
+        .await?;
+        Ok(doc)
+    }
+
+    /// Updates a document with a [JSON Patch](https://datatracker.ietf.org/doc/html/rfc6902): the
+    /// document is fetched, the operations are applied and the document is saved. When the save
+    /// conflicts with a concurrent update, the operations are applied to the current version and
+    /// saved again, at most 5 times in total, so `test` operations are checked against the version
+    /// that is saved. Returns the patched document, with its new `_rev`, or the error of the first
+    /// operation that fails, e.g. a 412 (Precondition Failed) error for a failed `test`.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::patch::PatchOperation;
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     db.create(&mut json!({ "_id": "order", "status": "open", "items": [] })).await?;
+    ///
+    ///     // add an item, but only while the order is open
+    ///     let ops = vec![
+    ///         PatchOperation::Test { path: "/status".to_string(), value: json!("open") },
+    ///         PatchOperation::Add { path: "/items/-".to_string(), value: json!("book") },
+    ///     ];
+    ///     let patched = db.apply_patch("order", &ops).await?;
+    ///     assert_eq!(patched["items"], json!(["book"]));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn apply_patch(&self, id: &str, ops: &[PatchOperation]) -> CouchResult<Value> {
+        let mut attempt = 1;
+
+        loop {
+            let mut doc: Value = self.get(id).await?;
+            let rev = doc.get_rev().into_owned();
+            apply_patch(&mut doc, ops)?;
+            doc.set_id(id);
+            doc.set_rev(&rev);
+
+            match self.save(&mut doc).await {
+                Err(err) if err.is_conflict() && attempt < PATCH_ATTEMPTS => attempt += 1,
+                Err(err) => return Err(err),
+                Ok(_) => return Ok(doc),
+            }
+        }
+    }
+
     /// Bulk upsert a list of documents.
     ///
     /// This will first fetch the latest rev for each document that does not have a rev set. It
     /// will then insert all documents into the database.
     pub async fn bulk_upsert<T: TypedCouchDocument + Clone>(
         &self,
-        mut docs: &mut Vec<T>,
+        docs: &mut [T],
     ) -> CouchResult<Vec<DocumentCreatedResult>> {
         // First collect all docs that do not have a rev set.
         let mut docs_without_rev = vec![];
@@ -822,10 +2220,132 @@ impl Database {
         }
 
         // Bulk insert the docs, this also updates the revs.
-        let res = self.bulk_docs(&mut docs).await?;
+        let res = self.bulk_docs(docs).await?;
         Ok(res)
     }
 
+    /// Loads fixtures into the database, e.g. to set up an integration test. The reader holds
+    /// documents separated by whitespace, like NDJSON, or arrays of documents. The database is
+    /// created when it doesn't exist, and fixtures that exist are overwritten, so seeding can be
+    /// repeated. A `_rev` in a fixture is ignored.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     let fixtures = r#"
+    ///         {"_id": "john", "first_name": "John"}
+    ///         {"_id": "jane", "first_name": "Jane"}
+    ///     "#;
+    ///     let results = db.seed_from_json(fixtures.as_bytes()).await?;
+    ///     assert_eq!(results.len(), 2);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn seed_from_json<R: AsyncRead + Unpin>(&self, mut reader: R) -> CouchResult<Vec<DocumentCreatedResult>> {
+        let mut json = vec![];
+        reader.read_to_end(&mut json).await.map_err(fixture_error)?;
+        self.seed(parse_fixtures(&json)?).await
+    }
+
+    /// Loads the fixtures in the `.json` and `.ndjson` files of a directory, in the order of the
+    /// file names, like `seed_from_json`. Other files are skipped, e.g. a README.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::database::Database;
+    /// use couch_rs::error::CouchResult;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     // seeding creates the database
+    ///     let db = Database::new("fixtures_db".to_string(), client);
+    ///     db.seed_from_dir("tests/fixtures").await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn seed_from_dir(&self, path: impl AsRef<Path>) -> CouchResult<Vec<DocumentCreatedResult>> {
+        let mut entries = tokio::fs::read_dir(path).await.map_err(fixture_error)?;
+        let mut files = vec![];
+        while let Some(entry) = entries.next_entry().await.map_err(fixture_error)? {
+            let path = entry.path();
+            if matches!(path.extension().and_then(|ext| ext.to_str()), Some("json" | "ndjson")) {
+                files.push(path);
+            }
+        }
+        files.sort();
+
+        let mut docs = vec![];
+        for file in files {
+            let json = tokio::fs::read(&file).await.map_err(fixture_error)?;
+            docs.extend(parse_fixtures(&json)?);
+        }
+        self.seed(docs).await
+    }
+
+    async fn seed(&self, mut docs: Vec<Value>) -> CouchResult<Vec<DocumentCreatedResult>> {
+        // the name of a handle is prefixed already
+        let mut client = self._client.clone();
+        client.set_prefix(String::new());
+        client.db(&self.name).await?;
+
+        let ids: Vec<DocumentId> = docs
+            .iter()
+            .map(|doc| doc.get_id().into_owned())
+            .filter(|id| !id.is_empty())
+            .collect();
+        let mut revs = HashMap::new();
+        for ids in ids.chunks(SEED_BATCH_SIZE) {
+            revs.extend(self.current_revs(ids).await?);
+        }
+        for doc in docs.iter_mut() {
+            match revs.get(doc.get_id().as_ref()) {
+                Some(rev) => doc.set_rev(rev),
+                None => {
+                    if let Some(doc) = doc.as_object_mut() {
+                        doc.remove("_rev");
+                    }
+                }
+            }
+        }
+
+        let mut results = vec![];
+        for docs in docs.chunks_mut(SEED_BATCH_SIZE) {
+            results.extend(self.bulk_docs(docs).await?);
+        }
+        Ok(results)
+    }
+
+    /// The current revisions of the documents that exist, and are not deleted, by id
+    async fn current_revs(&self, ids: &[DocumentId]) -> CouchResult<HashMap<String, String>> {
+        let response: Value = self
+            ._client
+            .post(&self.create_raw_path("_all_docs"), js!(json!({ "keys": ids })))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let rows = response["rows"].as_array().cloned().unwrap_or_default();
+        Ok(rows
+            .into_iter()
+            .filter(|row| row["value"]["deleted"] != json!(true))
+            .filter_map(|row| {
+                let id = row["id"].as_str()?.to_string();
+                let rev = row["value"]["rev"].as_str()?.to_string();
+                Some((id, rev))
+            })
+            .collect())
+    }
+
     /// Creates a design with one of more view documents.
     ///
     /// Usage:
@@ -863,17 +2383,13 @@ impl Database {
             .await?;
 
         let response_status = response.status();
+        let origin = RequestOrigin::of(&response);
         let result: DesignCreated = response.json().await?;
 
         if response_status.is_success() {
             Ok(result)
         } else {
-            let error_msg = result.error.unwrap_or_else(|| s!("unspecified error"));
-            Err(CouchError {
-                id: result.id,
-                status: response_status,
-                message: error_msg,
-            })
+            Err(CouchError::write_failure(result.id, result.error, result.reason, response_status).with_origin(origin))
         }
     }
 
@@ -887,18 +2403,16 @@ impl Database {
         self.query(design_name, view_name, options).await
     }
 
-    /// Executes a query against a view.
-    /// Make sure the types you use for K, V and T represent the structures the query will return.
-    /// For example, if a query can return a `null` value, but the type used for query() is <K:String, V:String, T:TypedCouchDocument>
-    /// the couchdb query will succeed but deserialising the overall result will fail ('null' cannot be deserialized to String).
-    /// In such case, you can use serde::Value since it can hold both 'null' and String.
+    /// Creates or updates a design document, only writing it when its content differs from the
+    /// design document in the database. The `_rev` of `ddoc` is set to the current revision.
+    /// Returns `true` when the design document was written, or with `dry_run` when it would be
+    /// written; a dry run never changes the database.
     ///
     /// Usage:
     /// ```
     /// use couch_rs::error::CouchResult;
-    /// use couch_rs::types::view::RawViewCollection;
-    /// use couch_rs::types::view::{CouchFunc, CouchViews};
-    /// use serde_json::json;
+    /// use couch_rs::types::design::DesignDocument;
+    /// use couch_rs::types::view::CouchFunc;
     ///
     /// const TEST_DB: &str = "view_db";
     ///
@@ -906,46 +2420,770 @@ impl Database {
     /// async fn main() -> CouchResult<()> {
     ///     let client = couch_rs::Client::new_local_test()?;
     ///     let db = client.db(TEST_DB).await?;
+    ///     let mut ddoc =
+    ///         DesignDocument::new("users").view("by_name", CouchFunc::new("function(doc) { emit(doc.name, null); }", None));
     ///
-    ///     let mut doc = json!({
-    ///                     "_id": "jdoe",
-    ///                     "first_name": "John",
-    ///                     "last_name": "Doe",
-    ///                     "funny": true
-    ///                 });
-    ///
-    ///     db.create(&mut doc).await?;
-    ///
-    ///     let couch_func = CouchFunc {
-    ///             map: "function (doc) { if (doc.funny == true) { emit(doc._id, doc.funny); } }".to_string(),
-    ///             reduce: None,
-    ///     };
-    ///
-    ///     let couch_views = CouchViews::new("funny_guys", couch_func);
+    ///     db.sync_design_doc(&mut ddoc, false).await?;
+    ///     // nothing changed, so this doesn't write
+    ///     assert!(!db.sync_design_doc(&mut ddoc, false).await?);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn sync_design_doc(&self, ddoc: &mut DesignDocument, dry_run: bool) -> CouchResult<bool> {
+        let current = self.get_design_doc(ddoc.name()).await?;
+        ddoc.set_rev(&current.as_ref().map(|c| c.get_rev().into_owned()).unwrap_or_default());
+        if current.is_some_and(|c| ddoc.same_content(&c)) {
+            return Ok(false);
+        }
+        if dry_run {
+            return Ok(true);
+        }
+
+        let response = self
+            ._client
+            .put(&self.create_design_path(ddoc.name()), to_string(&ddoc)?)
+            .send()
+            .await?;
+        let status = response.status();
+        let origin = RequestOrigin::of(&response);
+        let data: DocumentCreatedResponse = response.json().await?;
+
+        if let (Some(true), Some(rev)) = (data.ok, data.rev) {
+            ddoc.set_rev(&rev);
+            Ok(true)
+        } else {
+            Err(CouchError::write_failure(Some(ddoc._id.clone()), data.error, data.reason, status).with_origin(origin))
+        }
+    }
+
+    /// Installs a `validate_doc_update` function in the design document `design_name`, creating
+    /// the design document when it doesn't exist. The other content of the design document is
+    /// kept. Returns `true` when the design document was written.
+    ///
+    /// Writes rejected by the function fail with a `CouchError` whose `rejection()` holds the
+    /// reason thrown by the function.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::{CouchResult, Rejection};
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     db.set_validate_doc_update(
+    ///         "validation",
+    ///         "function(newDoc) { if (!newDoc._deleted && !newDoc.name) { throw({forbidden: 'name is required'}); } }",
+    ///     )
+    ///     .await?;
+    ///
+    ///     let err = db.create(&mut json!({"age": 42})).await.unwrap_err();
+    ///     assert_eq!(err.rejection(), Some(Rejection::Forbidden("name is required".to_string())));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn set_validate_doc_update(&self, design_name: &str, func: &str) -> CouchResult<bool> {
+        let mut ddoc = self
+            .get_design_doc(design_name)
+            .await?
+            .unwrap_or_else(|| DesignDocument::new(design_name))
+            .validate_doc_update(func);
+        self.sync_design_doc(&mut ddoc, false).await
+    }
+
+    /// Compares a design document with the version in the database, and returns which views,
+    /// filters and other functions `sync_design_doc` would change. Use this in CI to verify the
+    /// deployed design documents are up to date.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::design::DesignDocument;
+    /// use couch_rs::types::view::CouchFunc;
+    ///
+    /// const TEST_DB: &str = "view_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let ddoc =
+    ///         DesignDocument::new("users").view("by_name", CouchFunc::new("function(doc) { emit(doc.name, null); }", None));
+    ///
+    ///     let diff = db.diff_design_doc(&ddoc).await?;
+    ///     for view in diff.views {
+    ///         println!("view {} is {:?}", view.name, view.kind);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn diff_design_doc(&self, ddoc: &DesignDocument) -> CouchResult<DesignDocDiff> {
+        let current = self.get_design_doc(ddoc.name()).await?;
+        Ok(ddoc.diff(current.as_ref()))
+    }
+
+    /// Gets a design document by name (with or without the `_design/` prefix), `None` when it
+    /// does not exist
+    pub async fn get_design_doc(&self, name: &str) -> CouchResult<Option<DesignDocument>> {
+        let name = name.strip_prefix("_design/").unwrap_or(name);
+        let response = self._client.get(&self.create_design_path(name), None).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    /// Executes a query against a view.
+    /// Make sure the types you use for K, V and T represent the structures the query will return.
+    /// For example, if a query can return a `null` value, but the type used for query() is <K:String, V:String, T:TypedCouchDocument>
+    /// the couchdb query will succeed but deserialising the overall result will fail ('null' cannot be deserialized to String).
+    /// In such case, you can use serde::Value since it can hold both 'null' and String.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::view::RawViewCollection;
+    /// use couch_rs::types::view::{CouchFunc, CouchViews};
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "view_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     let mut doc = json!({
+    ///                     "_id": "jdoe",
+    ///                     "first_name": "John",
+    ///                     "last_name": "Doe",
+    ///                     "funny": true
+    ///                 });
+    ///
+    ///     db.create(&mut doc).await?;
+    ///
+    ///     let couch_func = CouchFunc {
+    ///             map: "function (doc) { if (doc.funny == true) { emit(doc._id, doc.funny); } }".to_string(),
+    ///             reduce: None,
+    ///     };
+    ///
+    ///     let couch_views = CouchViews::new("funny_guys", couch_func);
     ///     db.create_view("test_design", couch_views).await?;
     ///     let result: RawViewCollection<String, bool> = db.query("test_design", "funny_guys", None).await?;
     ///
-    ///     println!("Funny guys:");
-    ///     for item in result.rows.into_iter() {
-    ///         let id = item.key;
-    ///         let is_funny = item.value;
-    ///         println!("{} is funny: {}", id, is_funny);
-    ///     }
+    ///     println!("Funny guys:");
+    ///     for item in result.rows.into_iter() {
+    ///         let id = item.key;
+    ///         let is_funny = item.value;
+    ///         println!("{} is funny: {}", id, is_funny);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn query<K: DeserializeOwned, V: DeserializeOwned, T: TypedCouchDocument>(
+        &self,
+        design_name: &str,
+        view_name: &str,
+        mut options: Option<QueryParams>,
+    ) -> CouchResult<ViewCollection<K, V, T>> {
+        if options.is_none() {
+            options = Some(QueryParams::default());
+        }
+
+        self._client
+            .post(&self.create_query_view_path(design_name, view_name), js!(&options))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .map_err(CouchError::from)
+    }
+
+    /// Queries a view with a `ViewQuery`, as raw values.
+    /// Convenience function for query_view::<Value, Value, Value>(design_name, view_name, query)
+    pub async fn query_view_raw(
+        &self,
+        design_name: &str,
+        view_name: &str,
+        query: &ViewQuery,
+    ) -> CouchResult<RawViewCollection<Value, Value>> {
+        self.query_view(design_name, view_name, query).await
+    }
+
+    /// Queries a view with a `ViewQuery`, which encodes the keys as JSON values. The keys and values
+    /// of the rows are deserialized into `K` and `V`, and with `include_docs` the documents into `D`.
+    /// Use `Value` for any of them to get the raw JSON.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::query::ViewQuery;
+    /// use couch_rs::types::view::{CouchFunc, CouchViews, ViewCollection};
+    /// use serde_json::{json, Value};
+    ///
+    /// const TEST_DB: &str = "view_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let views = CouchViews::new(
+    ///         "by_date",
+    ///         CouchFunc::new("function(doc) { emit([doc.year, doc.month], doc.amount); }", None),
+    ///     );
+    ///     db.create_view("dates", views).await?;
+    ///
+    ///     let query = ViewQuery::default()
+    ///         .start_key(json!([2021, 1]))
+    ///         .end_key(json!([2021, 12]))
+    ///         .include_docs(true);
+    ///     let result: ViewCollection<(u32, u32), f64, Value> = db.query_view("dates", "by_date", &query).await?;
+    ///     for row in result.rows {
+    ///         let (year, month) = row.key;
+    ///         println!("{}-{}: {} in {:?}", year, month, row.value, row.doc);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn query_view<K: DeserializeOwned, V: DeserializeOwned, D: TypedCouchDocument>(
+        &self,
+        design_name: &str,
+        view_name: &str,
+        query: &ViewQuery,
+    ) -> CouchResult<ViewCollection<K, V, D>> {
+        self._client
+            .post(&self.create_query_view_path(design_name, view_name), js!(query))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .map_err(CouchError::from)
+    }
+
+    /// Queries a full-text search index of a design document (see `DesignDocument::search_index`),
+    /// which needs CouchDB 3.x with search enabled, or Cloudant. With `include_docs` the documents
+    /// are deserialized into `T`; use `Value` to get the raw JSON. Pass the `bookmark` of the
+    /// result to the next query to get the next page.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::design::DesignDocument;
+    /// use couch_rs::types::search::{SearchIndex, SearchQuery, SearchResult};
+    /// use serde_json::Value;
+    ///
+    /// const TEST_DB: &str = "search_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let mut ddoc = DesignDocument::new("books").search_index(
+    ///         "by_title",
+    ///         SearchIndex::new("function(doc) { index('title', doc.title, {store: true}); index('genre', doc.genre, {facet: true}); }"),
+    ///     );
+    ///     db.sync_design_doc(&mut ddoc, false).await?;
+    ///
+    ///     let query = SearchQuery::new("title:couch*")
+    ///         .include_docs(true)
+    ///         .counts(vec!["genre".to_string()])
+    ///         .highlight_fields(vec!["title".to_string()]);
+    ///     let result: SearchResult<Value> = db.search("books", "by_title", &query).await?;
+    ///     println!("{} books, by genre: {:?}", result.total_rows, result.counts.get("genre"));
+    ///     for row in result.rows {
+    ///         println!("{}: {:?}", row.id, row.highlights.get("title"));
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn search<T: DeserializeOwned>(
+        &self,
+        design_name: &str,
+        index_name: &str,
+        query: &SearchQuery,
+    ) -> CouchResult<SearchResult<T>> {
+        self._client
+            .post(&self.create_search_path(design_name, index_name), js!(query))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .map_err(CouchError::from)
+    }
+
+    /// Streams the rows of a view, fetching them in pages of `page_size` rows. This allows
+    /// iterating over very large views without loading all rows in memory. Pages continue from the
+    /// key and document id of the last row (`start_key` and `start_key_doc_id`), which is stable
+    /// when documents are added or removed while iterating. The `limit` and `skip` of the query
+    /// apply to the whole stream. Querying with `keys` is not supported, and the view is never
+    /// reduced.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::query::ViewQuery;
+    /// use couch_rs::types::view::{CouchFunc, CouchViews};
+    /// use futures_util::TryStreamExt;
+    /// use serde_json::Value;
+    ///
+    /// const TEST_DB: &str = "view_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let views = CouchViews::new("by_id", CouchFunc::new("function(doc) { emit(doc._id, null); }", None));
+    ///     db.create_view("ids", views).await?;
+    ///
+    ///     let mut rows = db.query_view_stream::<String, Value, Value>("ids", "by_id", ViewQuery::default(), 1000);
+    ///     while let Some(row) = rows.try_next().await? {
+    ///         println!("{}", row.key);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn query_view_stream<K, V, D>(
+        &self,
+        design_name: &str,
+        view_name: &str,
+        query: ViewQuery,
+        page_size: u64,
+    ) -> ViewStream<K, V, D>
+    where
+        K: DeserializeOwned + Send + 'static,
+        V: DeserializeOwned + Send + 'static,
+        D: TypedCouchDocument + Send + 'static,
+    {
+        self.paged_view(self.create_query_view_path(design_name, view_name), query, page_size)
+    }
+
+    /// Streams all documents in the database, in the order of their ids, fetching `page_size`
+    /// documents at a time (1000 when 0), so that only one page is held in memory. Design
+    /// documents are left out. Pages continue from the id of the last document, like
+    /// `query_view_stream`; the `limit` and `skip` of the query apply to the whole stream, and
+    /// count the design documents too.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::query::ViewQuery;
+    /// use futures_util::TryStreamExt;
+    /// use serde_json::Value;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     // the documents with an id from "a" up to "n"
+    ///     let query = ViewQuery::default().start_key("a").end_key("n").inclusive_end(false);
+    ///     let mut docs = db.all_docs_stream::<Value>(query, 500);
+    ///     while let Some(doc) = docs.try_next().await? {
+    ///         println!("{}", doc["_id"]);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn all_docs_stream<T>(&self, query: ViewQuery, page_size: u64) -> DocumentStream<T>
+    where
+        T: TypedCouchDocument + Send + 'static,
+    {
+        let rows = self.paged_view::<String, Value, Value>(
+            self.create_raw_path("_all_docs"),
+            query.include_docs(true),
+            page_size,
+        );
+        Box::pin(rows.try_filter_map(|row| async move {
+            match row.doc {
+                Some(doc) if !row.key.starts_with("_design/") => Ok(Some(serde_json::from_value(doc)?)),
+                _ => Ok(None),
+            }
+        }))
+    }
+
+    /// Pages through the rows of a view, see `query_view_stream`
+    fn paged_view<K, V, D>(&self, path: String, query: ViewQuery, page_size: u64) -> ViewStream<K, V, D>
+    where
+        K: DeserializeOwned + Send + 'static,
+        V: DeserializeOwned + Send + 'static,
+        D: TypedCouchDocument + Send + 'static,
+    {
+        let page_size = if page_size > 0 { page_size } else { 1000 };
+        let db = self.clone();
+        let remaining = query.limit;
+        let mut first = query.reduce(false);
+        first.keys = None;
+
+        let pages = stream::unfold(Some((first, remaining)), move |state| {
+            let db = db.clone();
+            let path = path.clone();
+            async move {
+                let (query, remaining) = state?;
+                let limit = remaining.map_or(page_size, |r| r.min(page_size));
+                if limit == 0 {
+                    return None;
+                }
+
+                // fetch one extra row, which becomes the start of the next page
+                let page_query = query.clone().limit(limit + 1);
+                let page = match db.view_page::<V, D>(&path, &page_query).await {
+                    Ok(page) => page,
+                    Err(err) => return Some((Err(err), None)),
+                };
+
+                let mut rows = page.rows;
+                let next = if rows.len() as u64 > limit {
+                    rows.pop().map(|next_row| {
+                        let mut next_query = query.clone();
+                        next_query.skip = None;
+                        next_query.start_key = Some(next_row.key);
+                        next_query.start_key_doc_id = next_row.id;
+                        (next_query, remaining.map(|r| r - limit))
+                    })
+                } else {
+                    None
+                };
+
+                let rows: CouchResult<Vec<ViewItem<K, V, D>>> = rows
+                    .into_iter()
+                    .map(|row| {
+                        Ok(ViewItem {
+                            key: serde_json::from_value(row.key)?,
+                            value: row.value,
+                            id: row.id,
+                            doc: row.doc,
+                        })
+                    })
+                    .collect();
+                Some((rows, next))
+            }
+        });
+
+        Box::pin(
+            pages
+                .map_ok(|rows| stream::iter(rows.into_iter().map(Ok)))
+                .try_flatten(),
+        )
+    }
+
+    async fn view_page<V: DeserializeOwned, D: TypedCouchDocument>(
+        &self,
+        path: &str,
+        query: &ViewQuery,
+    ) -> CouchResult<ViewCollection<Value, V, D>> {
+        self._client
+            .post(path, js!(query))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .map_err(CouchError::from)
+    }
+
+    /// Queries the reduce function of a view. `reduce` is always enabled; use `group` or
+    /// `group_level` on the query to reduce per (partial) key. The rows only hold the key and the
+    /// reduced value.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::query::ViewQuery;
+    /// use couch_rs::types::view::{CouchFunc, CouchViews, ReducedViewCollection};
+    ///
+    /// const TEST_DB: &str = "view_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let views = CouchViews::new(
+    ///         "amount_by_date",
+    ///         CouchFunc::new("function(doc) { emit([doc.year, doc.month, doc.day], doc.amount); }", Some("_sum")),
+    ///     );
+    ///     db.create_view("dates", views).await?;
+    ///
+    ///     // the total amount per year and month
+    ///     let query = ViewQuery::default().group_level(2);
+    ///     let result: ReducedViewCollection<(u32, u32), f64> =
+    ///         db.query_view_reduced("dates", "amount_by_date", &query).await?;
+    ///     for row in result.rows {
+    ///         println!("{:?}: {}", row.key, row.value);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn query_view_reduced<K: DeserializeOwned, V: DeserializeOwned>(
+        &self,
+        design_name: &str,
+        view_name: &str,
+        query: &ViewQuery,
+    ) -> CouchResult<ReducedViewCollection<K, V>> {
+        let query = query.clone().reduce(true);
+        self._client
+            .post(&self.create_query_view_path(design_name, view_name), js!(&query))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .map_err(CouchError::from)
+    }
+
+    /// Executes an update function for a document, and returns the raw response body.
+    pub async fn execute_update(
+        &self,
+        design_id: &str,
+        name: &str,
+        document_id: &str,
+        body: Option<Value>,
+    ) -> CouchResult<String> {
+        let body = match body {
+            Some(v) => to_string(&v)?,
+            None => String::default(),
+        };
+
+        let response = self
+            ._client
+            .put(&self.create_execute_update_path(design_id, name, document_id), body)
+            .send()
+            .await;
+        self.forget(document_id);
+        response?.error_for_status()?.text().await.map_err(CouchError::from)
+    }
+
+    /// Executes a show function, optionally for a document, and returns the raw response body.
+    /// The `params` are passed to the function as the query string of the request.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::design::DesignDocument;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let mut ddoc = DesignDocument::new("app").show("name", "function(doc, req) { return doc ? doc.name : 'none'; }");
+    ///     db.sync_design_doc(&mut ddoc, false).await?;
+    ///
+    ///     let html = db.execute_show("app", "name", None, None).await?;
+    ///     assert_eq!(html, "none");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn execute_show(
+        &self,
+        design_id: &str,
+        name: &str,
+        document_id: Option<&str>,
+        params: Option<HashMap<String, String>>,
+    ) -> CouchResult<String> {
+        self._client
+            .get(
+                &self.create_execute_show_path(design_id, name, document_id),
+                params.as_ref(),
+            )
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+            .map_err(CouchError::from)
+    }
+
+    /// Executes a list function over the rows of a view, and returns the raw response body.
+    /// The `params` are passed as the query string, so they hold both the view options (e.g.
+    /// `limit`) and the parameters of the list function.
+    pub async fn execute_list(
+        &self,
+        design_id: &str,
+        name: &str,
+        view_name: &str,
+        params: Option<HashMap<String, String>>,
+    ) -> CouchResult<String> {
+        self._client
+            .get(
+                &self.create_execute_list_path(design_id, name, view_name),
+                params.as_ref(),
+            )
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+            .map_err(CouchError::from)
+    }
+
+    /// Removes a document from the database. Returns success in a `bool`
+    /// Usage:
+    /// ```
+    /// use couch_rs::types::find::FindQuery;
+    /// use serde_json::{from_value, to_value, Value};
+    /// use couch_rs::types::document::DocumentId;
+    /// use couch_rs::error::CouchResult;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    /// let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     // first we need to get the document, because we need both the _id and _rev in order
+    ///     // to delete
+    ///     if let Some(doc) = db.get::<Value>("123").await.ok() {
+    ///         db.remove(&doc).await;
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///```
+    pub async fn remove<T: TypedCouchDocument>(&self, doc: &T) -> bool {
+        let mut h = HashMap::new();
+        h.insert(s!("rev"), doc.get_rev().into_owned());
+
+        let request = self._client.delete(&self.create_document_path(&doc.get_id()), Some(&h));
+        let removed = is_ok(request).await;
+        self.forget(&doc.get_id());
+        removed
+    }
+
+    /// Deletes a revision of a document, returning the revision of the tombstone
+    pub(crate) async fn delete_doc(&self, id: &str, rev: &str) -> DocumentCreatedResult {
+        let args = HashMap::from([(s!("rev"), rev.to_string())]);
+        let response = self
+            ._client
+            .delete(&self.create_document_path(id), Some(&args))
+            .send()
+            .await;
+        self.forget(id);
+        let response = response.map_err(|err| err.with_id(id))?;
+        let status = response.status();
+        let origin = RequestOrigin::of(&response);
+        let data: DocumentCreatedResponse = response.json().await?;
+
+        if let (Some(true), Some(id), Some(rev)) = (data.ok, data.id, data.rev) {
+            Ok(DocumentCreatedDetails { id, rev })
+        } else {
+            Err(CouchError::write_failure(Some(id.to_string()), data.error, data.reason, status).with_origin(origin))
+        }
+    }
+
+    /// Compares the given revisions, by document id, with the revisions in the database, and
+    /// returns the revisions that are missing. Documents of which all revisions are present are
+    /// left out. This is the basis of the replication protocol.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use std::collections::HashMap;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let revs = HashMap::from([("doc1".to_string(), vec!["1-967a00dff5e02add41819138abb3284d".to_string()])]);
+    ///     for (id, diff) in db.revs_diff(&revs).await? {
+    ///         println!("{} is missing {:?}", id, diff.missing);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn revs_diff(&self, revs: &HashMap<String, Vec<String>>) -> CouchResult<HashMap<String, RevsDiff>> {
+        self._client
+            .post(&self.create_raw_path("_revs_diff"), to_string(revs)?)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .map_err(CouchError::from)
+    }
+
+    /// Permanently removes revisions of a document, e.g. to comply with a request to erase
+    /// personal data. Unlike `remove`, which keeps a tombstone that is replicated, purged
+    /// revisions are removed from the database, and the purge is not replicated.
+    /// See [purge](https://docs.couchdb.org/en/stable/api/database/misc.html#db-purge) for more details.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::document::TypedCouchDocument;
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let mut doc = json!({ "email": "john@example.com" });
+    ///     db.create(&mut doc).await?;
+    ///
+    ///     let result = db.purge(&doc.get_id(), vec![doc.get_rev().into_owned()]).await?;
+    ///     assert_eq!(result.purged[doc.get_id().as_ref()], vec![doc.get_rev().into_owned()]);
     ///     Ok(())
     /// }
     /// ```
-    pub async fn query<K: DeserializeOwned, V: DeserializeOwned, T: TypedCouchDocument>(
-        &self,
-        design_name: &str,
-        view_name: &str,
-        mut options: Option<QueryParams>,
-    ) -> CouchResult<ViewCollection<K, V, T>> {
-        if options.is_none() {
-            options = Some(QueryParams::default());
+    pub async fn purge(&self, doc_id: &str, revs: Vec<String>) -> CouchResult<PurgeResult> {
+        let body = HashMap::from([(doc_id.to_string(), revs)]);
+        let response = self
+            ._client
+            .post(&self.create_raw_path("_purge"), to_string(&body)?)
+            .send()
+            .await;
+        self.forget(doc_id);
+        let response = response?;
+        let status = response.status();
+        let origin = RequestOrigin::of(&response);
+
+        if status.is_success() {
+            Ok(response.json().await?)
+        } else {
+            let s: CouchResponse = response.json().await?;
+            Err(CouchError::write_failure(Some(doc_id.to_string()), s.error, s.reason, status).with_origin(origin))
         }
+    }
+
+    /// Gets the number of purges of which the history is kept, to apply them to the indexes
+    pub async fn get_purged_infos_limit(&self) -> CouchResult<u64> {
+        self.get_setting("_purged_infos_limit").await
+    }
+
+    /// Sets the number of purges of which the history is kept
+    pub async fn set_purged_infos_limit(&self, limit: u64) -> CouchResult<()> {
+        self.set_setting("_purged_infos_limit", limit).await
+    }
+
+    /// Gets the number of revisions of which the history is kept for each document
+    pub async fn get_revs_limit(&self) -> CouchResult<u64> {
+        self.get_setting("_revs_limit").await
+    }
+
+    /// Sets the number of revisions of which the history is kept for each document. Lowering it
+    /// limits the growth of the revision trees of frequently updated documents, but it makes
+    /// conflicts more likely to be detected incorrectly during replication.
+    pub async fn set_revs_limit(&self, limit: u64) -> CouchResult<()> {
+        self.set_setting("_revs_limit", limit).await
+    }
 
+    /// Gets a database setting, such as `_revs_limit`
+    async fn get_setting(&self, setting: &str) -> CouchResult<u64> {
         self._client
-            .post(&self.create_query_view_path(design_name, view_name), js!(&options))
+            .get(&self.create_raw_path(setting), None)
             .send()
             .await?
             .error_for_status()?
@@ -954,98 +3192,255 @@ impl Database {
             .map_err(CouchError::from)
     }
 
-    /// Executes an update function.
-    pub async fn execute_update(
+    async fn set_setting(&self, setting: &str, value: u64) -> CouchResult<()> {
+        let response = self
+            ._client
+            .put(&self.create_raw_path(setting), value.to_string())
+            .send()
+            .await?;
+        let status = response.status();
+        let origin = RequestOrigin::of(&response);
+        let data: CouchResponse = response.json().await?;
+
+        if let Some(true) = data.ok {
+            Ok(())
+        } else {
+            Err(CouchError::write_failure(None, data.error, data.reason, status).with_origin(origin))
+        }
+    }
+
+    /// Uploads an attachment to the document with the given id and revision, streaming the body
+    /// so large attachments don't have to be kept in memory. Use an empty `rev` to create a new
+    /// document holding just the attachment.
+    /// See [attachments](https://docs.couchdb.org/en/stable/api/document/attachments.html#put--db-docid-attname)
+    /// for more details.
+    ///
+    /// Usage:
+    /// ```
+    /// use bytes::Bytes;
+    /// use couch_rs::error::CouchResult;
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let mut doc = json!({"_id": "with_attachment"});
+    ///     let details = db.create(&mut doc).await?;
+    ///
+    ///     let chunks: Vec<Result<Bytes, std::io::Error>> = vec![Ok(Bytes::from("hello ")), Ok(Bytes::from("world"))];
+    ///     let stream = futures_util::stream::iter(chunks);
+    ///     let updated = db
+    ///         .put_attachment(&details.id, &details.rev, "greeting.txt", "text/plain", stream)
+    ///         .await?;
+    ///     assert!(updated.rev.starts_with('2'));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn put_attachment<S>(
         &self,
-        design_id: &str,
+        doc_id: &str,
+        rev: &str,
         name: &str,
-        document_id: &str,
-        body: Option<Value>,
-    ) -> CouchResult<String> {
-        let body = match body {
-            Some(v) => to_string(&v)?,
-            None => String::default(),
-        };
+        content_type: &str,
+        stream: S,
+    ) -> DocumentCreatedResult
+    where
+        S: TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        let mut options = HashMap::new();
+        if !rev.is_empty() {
+            options.insert(s!("rev"), rev.to_string());
+        }
 
-        self._client
-            .put(&self.create_execute_update_path(design_id, name, document_id), body)
+        let mut headers = HeaderMap::new();
+        let content_type = HeaderValue::from_str(content_type)
+            .map_err(|e| CouchError::new(format!("invalid content type: {}", e), StatusCode::BAD_REQUEST))?;
+        headers.insert(CONTENT_TYPE, content_type);
+
+        let response = self
+            ._client
+            .request(Method::PUT, &self.create_attachment_path(doc_id, name), Some(&options))
+            .headers(headers)
+            .body(Body::wrap_stream(stream))
             .send()
-            .await?
-            .error_for_status()?
-            .text()
+            .await;
+        self.forget(doc_id);
+        let response = response?;
+        let status = response.status();
+        let origin = RequestOrigin::of(&response);
+        let data: DocumentCreatedResponse = response.json().await?;
+
+        if let (Some(true), Some(id), Some(rev)) = (data.ok, data.id, data.rev) {
+            Ok(DocumentCreatedDetails { id, rev })
+        } else {
+            Err(
+                CouchError::write_failure(Some(doc_id.to_string()), data.error, data.reason, status)
+                    .with_origin(origin),
+            )
+        }
+    }
+
+    /// Uploads an attachment from an `AsyncRead`, such as a `tokio::fs::File`. See
+    /// [put_attachment](Database::put_attachment) for details.
+    pub async fn put_attachment_reader<R>(
+        &self,
+        doc_id: &str,
+        rev: &str,
+        name: &str,
+        content_type: &str,
+        reader: R,
+    ) -> DocumentCreatedResult
+    where
+        R: AsyncRead + Send + Sync + 'static,
+    {
+        self.put_attachment(doc_id, rev, name, content_type, ReaderStream::new(reader))
             .await
-            .map_err(CouchError::from)
     }
 
-    /// Removes a document from the database. Returns success in a `bool`
+    /// Downloads an attachment of the document with the given id. The content is streamed, and
+    /// the returned `AttachmentStream` also holds the content type and digest of the attachment.
+    /// See [attachments](https://docs.couchdb.org/en/stable/api/document/attachments.html#get--db-docid-attname)
+    /// for more details.
+    ///
     /// Usage:
     /// ```
-    /// use couch_rs::types::find::FindQuery;
-    /// use serde_json::{from_value, to_value, Value};
-    /// use couch_rs::types::document::DocumentId;
     /// use couch_rs::error::CouchResult;
+    /// use futures_util::TryStreamExt;
     ///
     /// const TEST_DB: &str = "test_db";
     ///
     /// #[tokio::main]
     /// async fn main() -> CouchResult<()> {
-    /// let client = couch_rs::Client::new_local_test()?;
+    ///     let client = couch_rs::Client::new_local_test()?;
     ///     let db = client.db(TEST_DB).await?;
+    ///     let mut attachment = db.get_attachment("with_attachment", "greeting.txt").await?;
+    ///     println!("{:?} with digest {:?}", attachment.content_type(), attachment.digest());
     ///
-    ///     // first we need to get the document, because we need both the _id and _rev in order
-    ///     // to delete
-    ///     if let Some(doc) = db.get::<Value>("123").await.ok() {
-    ///         db.remove(&doc).await;
+    ///     while let Some(chunk) = attachment.try_next().await? {
+    ///         println!("received {} bytes", chunk.len());
     ///     }
-    ///
     ///     Ok(())
     /// }
-    ///```
-    pub async fn remove<T: TypedCouchDocument>(&self, doc: &T) -> bool {
-        let mut h = HashMap::new();
-        h.insert(s!("rev"), doc.get_rev().into_owned());
-
-        let request = self._client.delete(&self.create_document_path(&doc.get_id()), Some(&h));
-        is_ok(request).await
+    /// ```
+    pub async fn get_attachment(&self, doc_id: &str, name: &str) -> CouchResult<AttachmentStream> {
+        let response = self
+            ._client
+            .get(&self.create_attachment_path(doc_id, name), None)
+            .send()
+            .await?
+            .error_for_status()
+            .map_err(|err| CouchError::from(err).with_id(doc_id))?;
+        Ok(AttachmentStream::new(response))
     }
 
     /// Inserts an index in a naive way, if it already exists, will throw an
     /// `Err`
     pub async fn insert_index(&self, name: &str, spec: IndexFields) -> CouchResult<DesignCreated> {
+        self.insert_index_params(name, spec, IndexOptions::default()).await
+    }
+
+    /// Inserts an index with the given options, e.g. the design document or type of the index.
+    /// Use `IndexFields::partial_filter_selector` to only index a subset of the documents.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::find::SortSpec;
+    /// use couch_rs::types::index::{IndexFields, IndexOptions, IndexType};
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///     let spec = IndexFields::new(vec![SortSpec::Simple("year".to_string())])
+    ///         .partial_filter_selector(json!({"type": "movie"}));
+    ///     let options = IndexOptions::default().ddoc("movies").index_type(IndexType::Json);
+    ///     db.insert_index_params("movies-by-year", spec, options).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn insert_index_params(
+        &self,
+        name: &str,
+        spec: IndexFields,
+        options: IndexOptions,
+    ) -> CouchResult<DesignCreated> {
+        let request = IndexRequest {
+            name,
+            index: &spec,
+            options: &options,
+        };
         let response = self
             ._client
-            .post(
-                &self.create_raw_path("_index"),
-                js!(json!({
-                    "name": name,
-                    "index": spec
-                })),
-            )
+            .post(&self.create_raw_path("_index"), js!(&request))
             .send()
             .await?;
 
         let status = response.status();
+        let origin = RequestOrigin::of(&response);
         let data: DesignCreated = response.json().await?;
 
         if data.error.is_some() {
-            let err = data.error.unwrap_or_else(|| s!("unspecified error"));
-            Err(CouchError::new(err, status))
+            Err(CouchError::write_failure(None, data.error, data.reason, status).with_origin(origin))
         } else {
             Ok(data)
         }
     }
 
     /// Reads the database's indexes and returns them
-    pub async fn read_indexes(&self) -> CouchResult<DatabaseIndexList> {
+    pub async fn read_indexes(&self) -> CouchResult<IndexList> {
+        self.get_indexes().await
+    }
+
+    /// Lists the Mango indexes of the database, including the special `_all_docs` index
+    pub async fn get_indexes(&self) -> CouchResult<IndexList> {
         self._client
             .get(&self.create_raw_path("_index"), None)
             .send()
             .await?
+            .error_for_status()?
             .json()
             .await
             .map_err(CouchError::from)
     }
 
+    /// Deletes the json index `name` from the design document `ddoc`. The design document can be
+    /// given with or without the `_design/` prefix, as returned in `Index::ddoc`.
+    /// Returns `true` when the index was deleted.
+    pub async fn delete_index(&self, ddoc: &str, name: &str) -> CouchResult<bool> {
+        self.delete_index_params(ddoc, name, IndexType::Json).await
+    }
+
+    /// Deletes the index `name` of the given type from the design document `ddoc`
+    pub async fn delete_index_params(&self, ddoc: &str, name: &str, index_type: IndexType) -> CouchResult<bool> {
+        let ddoc = ddoc.strip_prefix("_design/").unwrap_or(ddoc);
+        let path = format!(
+            "{}/_index/{}/{}/{}",
+            self.name,
+            url_encode!(ddoc),
+            index_type.as_str(),
+            url_encode!(name)
+        );
+        let response = self._client.delete(&path, None).send().await?;
+        let status = response.status();
+        let origin = RequestOrigin::of(&response);
+        let s: CouchResponse = response.json().await?;
+
+        if status.is_success() {
+            Ok(s.ok.unwrap_or(false))
+        } else {
+            Err(CouchError::write_failure(None, s.error, s.reason, status).with_origin(origin))
+        }
+    }
+
     /// Method to ensure an index is created on the database with the following
     /// spec. Returns `true` when we created a new one, or `false` when the
     /// index was already existing.
@@ -1063,11 +3458,12 @@ impl Database {
         // Let's create it then
         let result: DesignCreated = self.insert_index(name, spec).await?;
         match result.error {
-            Some(e) => Err(CouchError {
-                id: result.id,
-                status: reqwest::StatusCode::INTERNAL_SERVER_ERROR,
-                message: e,
-            }),
+            Some(e) => Err(CouchError::write_failure(
+                result.id,
+                Some(e),
+                result.reason,
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
             // Created and alright
             None => Ok(true),
         }
@@ -1083,11 +3479,196 @@ impl Database {
     pub fn changes(&self, last_seq: Option<serde_json::Value>) -> ChangesStream {
         ChangesStream::new(self._client.clone(), self.name.clone(), last_seq)
     }
+
+    /// A continuous feed of the changes to this database, starting from `last_seq`. Use `None` to
+    /// receive all changes since the creation of the database, or `Some(json!("now"))` to only
+    /// receive new changes.
+    ///
+    /// The stream does not end by itself: CouchDB sends a heartbeat to keep the connection alive
+    /// and the stream transparently reconnects (resuming from the last received `seq`) when the
+    /// connection is dropped. Errors are returned as items on the stream; polling the stream after
+    /// a temporary error (e.g. 503) reconnects after a growing delay, while other errors (e.g. 401
+    /// or 404) end the stream.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use futures_util::StreamExt;
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     let mut changes = db.changes_stream(Some(json!("now")));
+    ///
+    ///     db.create(&mut json!({"thing": true})).await?;
+    ///
+    ///     if let Some(change) = changes.next().await {
+    ///         println!("Document {} changed", change?.id);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn changes_stream(&self, last_seq: Option<serde_json::Value>) -> ChangesStream {
+        let mut stream = ChangesStream::new(self._client.clone(), self.name.clone(), last_seq);
+        stream.set_infinite(true);
+        stream.set_heartbeat(Some(DEFAULT_HEARTBEAT));
+        stream
+    }
+
+    /// A continuous feed of the changes to this database, like `changes_stream`, with the
+    /// parameters of the given request applied. Use this to filter the changes on the server.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use couch_rs::types::changes::ChangesRequest;
+    /// use futures_util::StreamExt;
+    /// use serde_json::json;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     // only receive changes to documents with a "funny" field set to true
+    ///     let request = ChangesRequest::default().selector(json!({"funny": true}));
+    ///     let mut changes = db.changes_stream_params(Some(json!("now")), &request);
+    ///
+    ///     db.create(&mut json!({"funny": true})).await?;
+    ///
+    ///     if let Some(change) = changes.next().await {
+    ///         println!("Funny document {} changed", change?.id);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn changes_stream_params(&self, last_seq: Option<Seq>, request: &ChangesRequest) -> ChangesStream {
+        let mut stream = self.changes_stream(last_seq);
+        stream.set_request(request);
+        stream
+    }
+
+    /// A continuous feed of the new revisions of one document, e.g. of a configuration document,
+    /// or a document that is shown live. Only the revisions after the first poll of the stream
+    /// are returned, so use `get` for the current revision. A deleted document is returned as
+    /// `None`. Like `changes_stream`, the stream reconnects when the connection is dropped.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    /// use futures_util::StreamExt;
+    /// use serde_json::Value;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     let mut settings: Value = db.get("settings").await?;
+    ///     let mut revisions = db.watch_doc::<Value>("settings");
+    ///     while let Some(revision) = revisions.next().await {
+    ///         match revision? {
+    ///             Some(revision) => settings = revision,
+    ///             None => break,
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn watch_doc<T: TypedCouchDocument + Send + 'static>(&self, id: &str) -> DocumentStream<Option<T>> {
+        let request = ChangesRequest::default()
+            .doc_ids(vec![id.to_string()])
+            .include_docs(true);
+        let changes = self.changes_stream_params(Some(json!("now")), &request).typed::<T>();
+        Box::pin(changes.and_then(|change| async move {
+            match change.doc {
+                Some(doc) => doc.map(Some),
+                None if change.deleted => Ok(None),
+                None => {
+                    Err(CouchError::new(s!("missing document"), StatusCode::INTERNAL_SERVER_ERROR).with_id(&change.id))
+                }
+            }
+        }))
+    }
+
+    /// Waits for changes to this database since `since`, using the `longpoll` feed. The request
+    /// returns as soon as at least one change is available, or after 60 seconds without changes
+    /// (with an empty set of results).
+    ///
+    /// Pass the returned `last_seq` as `since` in the next call to resume where this call left
+    /// off. Use `None` to get all changes since the creation of the database.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    ///
+    /// const TEST_DB: &str = "test_db";
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let db = client.db(TEST_DB).await?;
+    ///
+    ///     let changes = db.changes_longpoll(None).await?;
+    ///     for change in changes.results {
+    ///         println!("Document {} changed", change.id);
+    ///     }
+    ///
+    ///     // wait for the next set of changes
+    ///     let _next = db.changes_longpoll(Some(changes.last_seq)).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn changes_longpoll(&self, since: Option<Seq>) -> CouchResult<ChangesResponse> {
+        self.changes_longpoll_params(since, &ChangesRequest::default().include_docs(true))
+            .await
+    }
+
+    /// Waits for changes to this database since `since`, like `changes_longpoll`, with the
+    /// parameters of the given request applied. Use this to filter the changes on the server.
+    pub async fn changes_longpoll_params(
+        &self,
+        since: Option<Seq>,
+        request: &ChangesRequest,
+    ) -> CouchResult<ChangesResponse> {
+        let mut params = request.query_params();
+        params.insert(s!("feed"), s!("longpoll"));
+        params.insert(s!("timeout"), COUCH_MAX_TIMEOUT.to_string());
+        if let Some(seq) = &since {
+            params.insert(s!("since"), seq_param(seq));
+        }
+
+        let path = self.create_raw_path("_changes");
+        let request = match request.body() {
+            Some(body) => self._client.request(Method::POST, &path, Some(&params)).body(js!(body)),
+            None => self._client.get(&path, Some(&params)),
+        };
+
+        request
+            // allow CouchDB to hold the request for its full timeout, which may exceed the client timeout
+            .timeout(Duration::from_millis(COUCH_MAX_TIMEOUT as u64 * 2))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .map_err(CouchError::from)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::couch_rs_tests::Note;
 
     #[test]
     fn test_document_paths() {
@@ -1105,7 +3686,52 @@ mod tests {
         assert_eq!(p, "testdb/_design/design%2B1/_view/view%2B1");
         let p = db.create_execute_update_path("design1", "update1", "123");
         assert_eq!(p, "testdb/_design/design1/_update/update1/123");
+        let p = db.create_execute_show_path("design1", "show1", Some("123"));
+        assert_eq!(p, "testdb/_design/design1/_show/show1/123");
+        let p = db.create_execute_show_path("design1", "show1", None);
+        assert_eq!(p, "testdb/_design/design1/_show/show1");
+        let p = db.create_execute_list_path("design1", "list1", "view1");
+        assert_eq!(p, "testdb/_design/design1/_list/list1/view1");
         let p = db.create_compact_path("view1");
         assert_eq!(p, "testdb/_compact/view1");
     }
+
+    #[test]
+    fn test_parse_fixtures() {
+        let docs = parse_fixtures(b"{\"_id\": \"a\"}\n{\"_id\": \"b\"}\n").unwrap();
+        assert_eq!(docs, vec![json!({"_id": "a"}), json!({"_id": "b"})]);
+
+        let docs = parse_fixtures(br#"[{"_id": "a"}, {"_id": "b"}] {"_id": "c"}"#).unwrap();
+        assert_eq!(docs.len(), 3);
+        assert!(parse_fixtures(b"").unwrap().is_empty());
+
+        assert!(parse_fixtures(br#"{"_id": "a"} 12"#).is_err());
+        assert!(parse_fixtures(br#"{"_id": "a""#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bulk_docs_without_new_edits_keeps_timestamps() {
+        let client = Client::new("http://localhost:1", "admin", "password").unwrap();
+        let db = Database::new(s!("testdb"), client);
+        let created = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let updated = created + Duration::from_secs(60);
+        let mut notes = vec![Note {
+            _id: s!("imported"),
+            _rev: s!("2-7051cbe5c8faecd085a3fa619e6e6337"),
+            created_at: Some(created),
+            updated_at: Some(updated),
+            text: s!("copied"),
+        }];
+
+        // the request fails without a server, after the documents would have been prepared
+        let result = db
+            .bulk_docs_params(&mut notes, BulkDocsOptions::default().new_edits(false))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(notes[0].created_at, Some(created));
+        assert_eq!(notes[0].updated_at, Some(updated));
+
+        assert!(db.bulk_docs(&mut notes).await.is_err());
+        assert_ne!(notes[0].updated_at, Some(updated));
+    }
 }
@@ -1,38 +1,111 @@
-use serde::de::DeserializeOwned;
-use serde::{Deserialize, Serialize};
+use crate::error::{CouchError, CouchResult};
+use crate::types::document::Attachments;
+use futures_core::Stream;
+use serde::de::{self, DeserializeOwned};
+use serde::{ser, Deserialize, Serialize, Serializer};
+use serde_json::value::RawValue;
 use serde_json::Value;
 use std::borrow::Cow;
 use std::ops::{Index, IndexMut};
+use std::pin::Pin;
+use std::slice;
+use std::time::SystemTime;
 
 /// Trait to deal with typed CouchDB documents.
 pub trait TypedCouchDocument: DeserializeOwned + Serialize + Sized {
     /// get the _id field
-    fn get_id(&self) -> Cow<str>;
+    fn get_id(&self) -> Cow<'_, str>;
     /// get the _rev field
-    fn get_rev(&self) -> Cow<str>;
-    /// set the _rev field
+    fn get_rev(&self) -> Cow<'_, str>;
+    /// set the _rev field, an empty rev means that the document has no revision (yet)
     fn set_rev(&mut self, rev: &str);
     /// set the _id field
     fn set_id(&mut self, id: &str);
     /// merge the _id and _rev from the other document with this one
     fn merge_ids(&mut self, other: &Self);
+    /// get the _attachments field, `None` if this type of document does not hold attachments
+    fn get_attachments(&self) -> Option<Cow<'_, Attachments>> {
+        None
+    }
+    /// merge the current version of this document in the database into this one, when an update
+    /// is retried after a conflict, see `Database::upsert_with_retry`. By default this document
+    /// replaces the current version. The `_rev` is set by the caller.
+    fn merge(&mut self, current: Self) {
+        let _ = current;
+    }
+    /// get the _conflicts field, only present when the document was read with `conflicts`;
+    /// `None` if this type of document does not hold conflicts
+    fn get_conflicts(&self) -> Option<Cow<'_, [String]>> {
+        None
+    }
+    /// get the _deleted_conflicts field, only present when the document was read with
+    /// `deleted_conflicts`; `None` if this type of document does not hold deleted conflicts
+    fn get_deleted_conflicts(&self) -> Option<Cow<'_, [String]>> {
+        None
+    }
+    /// the value of the `type` field of this type of document, for databases that hold several
+    /// types of documents. When set, the `type` field is added when the document is written, and
+    /// checked when a single document is read, e.g. with `Database::get`. `None` by default.
+    fn type_name() -> Option<&'static str> {
+        None
+    }
+    /// set the timestamps of the document, called by `Database` before the document is written,
+    /// see `timestamps::Timestamp`. By default the document has no timestamps.
+    fn set_timestamps(&mut self, now: SystemTime) {
+        let _ = now;
+    }
+}
+
+/// A document that is serialized with the `type` field of its `TypedCouchDocument::type_name`
+pub(crate) struct Typed<'a, T>(pub(crate) &'a T);
+
+impl<T: TypedCouchDocument> Serialize for Typed<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some(type_name) = T::type_name() else {
+            return self.0.serialize(serializer);
+        };
+        let mut doc = serde_json::to_value(self.0).map_err(ser::Error::custom)?;
+        if let Some(doc) = doc.as_object_mut() {
+            doc.insert(s!("type"), Value::from(type_name));
+        }
+        doc.serialize(serializer)
+    }
+}
+
+/// Deserializes a document, failing when its `type` field doesn't match the type name of `T`
+pub(crate) fn from_typed_value<T: TypedCouchDocument>(doc: &Value) -> CouchResult<T> {
+    if let Some(type_name) = T::type_name() {
+        let found = doc.get("type").and_then(Value::as_str);
+        if found != Some(type_name) {
+            return Err(CouchError::from(<serde_json::Error as de::Error>::custom(format!(
+                "expected a document of type {}, found {}",
+                type_name,
+                found.unwrap_or("none")
+            ))));
+        }
+    }
+    T::deserialize(doc).map_err(CouchError::from)
 }
 
 /// Allows dealing with _id and _rev fields in untyped (Value) documents
 impl TypedCouchDocument for Value {
-    fn get_id(&self) -> Cow<str> {
+    fn get_id(&self) -> Cow<'_, str> {
         let id: String = json_extr!(self["_id"]);
         Cow::from(id)
     }
 
-    fn get_rev(&self) -> Cow<str> {
+    fn get_rev(&self) -> Cow<'_, str> {
         let rev: String = json_extr!(self["_rev"]);
         Cow::from(rev)
     }
 
     fn set_rev(&mut self, rev: &str) {
         if let Some(o) = self.as_object_mut() {
-            o.insert("_rev".to_string(), Value::from(rev));
+            if rev.is_empty() {
+                o.remove("_rev");
+            } else {
+                o.insert("_rev".to_string(), Value::from(rev));
+            }
         }
     }
 
@@ -46,8 +119,112 @@ impl TypedCouchDocument for Value {
         self.set_id(&other.get_id());
         self.set_rev(&other.get_rev());
     }
+
+    fn get_attachments(&self) -> Option<Cow<'_, Attachments>> {
+        let attachments = self.get("_attachments")?;
+        serde_json::from_value(attachments.clone()).ok().map(Cow::Owned)
+    }
+
+    fn get_conflicts(&self) -> Option<Cow<'_, [String]>> {
+        let conflicts = self.get("_conflicts")?;
+        serde_json::from_value(conflicts.clone()).ok().map(Cow::Owned)
+    }
+
+    fn get_deleted_conflicts(&self) -> Option<Cow<'_, [String]>> {
+        let conflicts = self.get("_deleted_conflicts")?;
+        serde_json::from_value(conflicts.clone()).ok().map(Cow::Owned)
+    }
+}
+
+/// A document that is kept as raw JSON, and only deserialized when it is accessed. Use it as the
+/// document type of queries and bulk reads, e.g. `find::<RawDocument>` or `get_bulk::<RawDocument>`,
+/// when most documents are filtered out on the client, to deserialize only the ones that are
+/// used. Reading the `_id` and `_rev` parses only those fields.
+///
+/// ```
+/// use couch_rs::document::{RawDocument, TypedCouchDocument};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// let doc = RawDocument::from_json(r#"{"_id": "u1", "_rev": "1-a", "name": "John"}"#.to_string()).unwrap();
+/// assert_eq!(doc.get_id(), "u1");
+/// let user: User = doc.deserialize().unwrap();
+/// assert_eq!(user.name, "John");
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(transparent)]
+pub struct RawDocument(Box<RawValue>);
+
+/// The fields of a raw document that `TypedCouchDocument` needs
+#[derive(Deserialize, Default)]
+struct RawIds<'a> {
+    #[serde(borrow, default)]
+    _id: Cow<'a, str>,
+    #[serde(borrow, default)]
+    _rev: Cow<'a, str>,
+}
+
+impl RawDocument {
+    pub fn from_json(json: String) -> CouchResult<Self> {
+        Ok(RawDocument(RawValue::from_string(json)?))
+    }
+
+    /// The JSON of the document
+    pub fn json(&self) -> &str {
+        self.0.get()
+    }
+
+    /// Deserializes the document
+    pub fn deserialize<T: DeserializeOwned>(&self) -> CouchResult<T> {
+        serde_json::from_str(self.0.get()).map_err(CouchError::from)
+    }
+
+    fn ids(&self) -> RawIds<'_> {
+        serde_json::from_str(self.0.get()).unwrap_or_default()
+    }
+
+    /// Changes a field, which parses and serializes the whole document
+    fn set_field(&mut self, field: &str, value: &str) {
+        if let Ok(Value::Object(mut doc)) = serde_json::from_str(self.0.get()) {
+            doc.insert(field.to_string(), Value::from(value));
+            if let Ok(raw) = serde_json::value::to_raw_value(&doc) {
+                self.0 = raw;
+            }
+        }
+    }
+}
+
+impl TypedCouchDocument for RawDocument {
+    fn get_id(&self) -> Cow<'_, str> {
+        self.ids()._id
+    }
+
+    fn get_rev(&self) -> Cow<'_, str> {
+        self.ids()._rev
+    }
+
+    fn set_rev(&mut self, rev: &str) {
+        self.set_field("_rev", rev);
+    }
+
+    fn set_id(&mut self, id: &str) {
+        self.set_field("_id", id);
+    }
+
+    fn merge_ids(&mut self, other: &Self) {
+        self.set_id(&other.get_id());
+        self.set_rev(&other.get_rev());
+    }
 }
 
+/// A stream of documents, returned by [Database::all_docs_stream](crate::database::Database::all_docs_stream)
+/// and [Database::find_stream](crate::database::Database::find_stream)
+pub type DocumentStream<T> = Pin<Box<dyn Stream<Item = CouchResult<T>> + Send>>;
+
 /// Memory-optimized, iterable document collection, mostly returned in calls
 /// that involve multiple documents results Can target a specific index through
 /// implementation of `Index` and `IndexMut`
@@ -104,16 +281,7 @@ impl<T: TypedCouchDocument> DocumentCollection<T> {
                     None
                 } else {
                     // Remove _design documents
-                    if let Some(doc) = d.doc {
-                        if doc.get_id().starts_with('_') {
-                            None
-                        } else {
-                            Some(doc)
-                        }
-                    } else {
-                        // no documents retrieved
-                        None
-                    }
+                    d.doc.filter(|doc| !doc.get_id().starts_with('_'))
                 }
             })
             .collect();
@@ -150,10 +318,62 @@ impl<T: TypedCouchDocument> DocumentCollection<T> {
         }
     }
 
-    /// Returns raw JSON data from documents
+    /// Returns the documents, without cloning them
     pub fn get_data(&self) -> &Vec<T> {
         &self.rows
     }
+
+    /// Takes the documents out of the collection, without cloning them
+    pub fn into_data(self) -> Vec<T> {
+        self.rows
+    }
+
+    /// An iterator over the documents
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.rows.iter()
+    }
+
+    /// An iterator that allows modifying the documents
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.rows.iter_mut()
+    }
+}
+
+impl DocumentCollection<Value> {
+    /// An iterator that deserializes the documents into `U` one at a time, e.g. to stop at the
+    /// first document of interest without deserializing the others
+    pub fn iter_docs<U: DeserializeOwned>(&self) -> impl Iterator<Item = CouchResult<U>> + '_ {
+        self.rows
+            .iter()
+            .map(|row| U::deserialize(row).map_err(CouchError::from))
+    }
+}
+
+impl<T: TypedCouchDocument> IntoIterator for DocumentCollection<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.into_iter()
+    }
+}
+
+impl<'a, T: TypedCouchDocument> IntoIterator for &'a DocumentCollection<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.iter()
+    }
+}
+
+impl<'a, T: TypedCouchDocument> IntoIterator for &'a mut DocumentCollection<T> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.iter_mut()
+    }
 }
 
 impl<T: TypedCouchDocument> Index<usize> for DocumentCollection<T> {
@@ -174,8 +394,12 @@ impl<T: TypedCouchDocument> IndexMut<usize> for DocumentCollection<T> {
 mod tests {
     use crate as couch_rs;
     use crate::document::TypedCouchDocument;
-    use couch_rs_derive::CouchDocument;
+    use crate::document::{from_typed_value, DocumentCollection, RawDocument, Typed};
+    use crate::types::find::FindQuery;
+    use couch_rs_derive::{CouchDocument, CouchDocumentEnum};
     use serde::{Deserialize, Serialize};
+    use serde_json::{json, Value};
+    use std::time::{Duration, SystemTime};
 
     #[derive(Serialize, Deserialize, CouchDocument, Debug, Default)]
     struct TestDocument {
@@ -196,4 +420,235 @@ mod tests {
         assert_eq!(id, "1");
         assert_eq!(rev, "2");
     }
+
+    #[derive(Serialize, Deserialize, CouchDocument, Debug, Default)]
+    struct MappedDocument {
+        #[couch(id)]
+        #[serde(rename = "_id", skip_serializing_if = "String::is_empty")]
+        pub id: String,
+        #[couch(rev)]
+        #[serde(rename = "_rev", skip_serializing_if = "String::is_empty")]
+        pub rev: String,
+        pub name: String,
+    }
+
+    #[test]
+    fn test_derive_mapped_fields() {
+        let mut doc: MappedDocument = serde_json::from_value(json!({"_id": "1", "_rev": "1-a", "name": "a"})).unwrap();
+        assert_eq!(doc.get_id(), "1");
+        assert_eq!(doc.get_rev(), "1-a");
+
+        doc.set_id("2");
+        doc.set_rev("2-b");
+        assert_eq!(doc.id, "2");
+        assert_eq!(
+            serde_json::to_value(&doc).unwrap(),
+            json!({"_id": "2", "_rev": "2-b", "name": "a"})
+        );
+    }
+
+    #[derive(Serialize, Deserialize, CouchDocument, Debug, Default)]
+    struct NewDocument {
+        #[serde(skip_serializing_if = "String::is_empty")]
+        pub _id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub _rev: Option<String>,
+    }
+
+    #[test]
+    fn test_derive_optional_rev() {
+        let mut doc = NewDocument {
+            _id: "1".to_string(),
+            _rev: None,
+        };
+        assert_eq!(doc.get_rev(), "");
+        assert_eq!(serde_json::to_value(&doc).unwrap(), json!({"_id": "1"}));
+
+        doc.set_rev("1-a");
+        assert_eq!(doc._rev.as_deref(), Some("1-a"));
+        assert_eq!(doc.get_rev(), "1-a");
+        doc.set_rev("");
+        assert_eq!(doc._rev, None);
+    }
+
+    #[derive(Serialize, Deserialize, CouchDocument, Debug, Default)]
+    #[couch(type_name = "invoice")]
+    struct Invoice {
+        #[serde(skip_serializing_if = "String::is_empty")]
+        pub _id: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        pub _rev: String,
+    }
+
+    #[test]
+    fn test_derive_type_name() {
+        assert_eq!(Invoice::type_name(), Some("invoice"));
+        assert_eq!(TestDocument::type_name(), None);
+        let invoice = Invoice {
+            _id: "1".to_string(),
+            _rev: String::new(),
+        };
+        let typed = serde_json::to_value(Typed(&invoice)).unwrap();
+        assert_eq!(typed, json!({"_id": "1", "type": "invoice"}));
+        let untyped = serde_json::to_value(Typed(&json!({"_id": "1"}))).unwrap();
+        assert_eq!(untyped, json!({"_id": "1"}));
+
+        assert_eq!(from_typed_value::<Invoice>(&typed).unwrap()._id, "1");
+        assert!(from_typed_value::<Invoice>(&json!({"_id": "1", "type": "order"})).is_err());
+        assert!(from_typed_value::<Invoice>(&json!({"_id": "1"})).is_err());
+        assert!(from_typed_value::<Value>(&json!({"_id": "1", "type": "order"})).is_ok());
+
+        assert_eq!(FindQuery::for_type::<Invoice>().selector, json!({"type": "invoice"}));
+        assert_eq!(
+            FindQuery::for_type::<TestDocument>().selector,
+            FindQuery::find_all().selector
+        );
+    }
+
+    #[derive(Serialize, Deserialize, CouchDocument, Debug, Default)]
+    #[couch(timestamps)]
+    struct Note {
+        #[serde(skip_serializing_if = "String::is_empty")]
+        pub _id: String,
+        #[serde(skip_serializing_if = "String::is_empty")]
+        pub _rev: String,
+        pub created_at: Option<SystemTime>,
+        pub updated_at: Option<SystemTime>,
+    }
+
+    #[test]
+    fn test_derive_timestamps() {
+        let mut note = Note::default();
+        let created = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+        note.set_timestamps(created);
+        assert_eq!(note.created_at, Some(created));
+        assert_eq!(note.updated_at, Some(created));
+
+        note.set_rev("1-a");
+        let updated = created + Duration::from_secs(1);
+        note.set_timestamps(updated);
+        assert_eq!(note.created_at, Some(created));
+        assert_eq!(note.updated_at, Some(updated));
+    }
+
+    #[derive(Serialize, Deserialize, CouchDocument, Debug, Default)]
+    #[serde(rename_all = "camelCase")]
+    struct Envelope<T> {
+        #[serde(rename = "_id", skip_serializing_if = "String::is_empty")]
+        id: String,
+        #[serde(rename = "_rev", skip_serializing_if = "String::is_empty")]
+        rev: String,
+        sent_by: String,
+        #[serde(flatten)]
+        payload: T,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+    struct Greeting {
+        text: String,
+    }
+
+    #[test]
+    fn test_derive_generic_document() {
+        let json = json!({"_id": "1", "_rev": "1-a", "sentBy": "me", "text": "hello"});
+        let mut doc: Envelope<Greeting> = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(doc.get_id(), "1");
+        assert_eq!(doc.get_rev(), "1-a");
+        assert_eq!(doc.payload.text, "hello");
+        assert_eq!(serde_json::to_value(&doc).unwrap(), json);
+
+        doc.set_rev("2-b");
+        assert_eq!(doc.rev, "2-b");
+        let untyped: Envelope<Value> = serde_json::from_value(json).unwrap();
+        assert_eq!(untyped.get_id(), "1");
+    }
+
+    #[derive(Serialize, Deserialize, CouchDocumentEnum, Debug)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    enum Entity {
+        Invoice(Invoice),
+        Note(Note),
+    }
+
+    #[test]
+    fn test_derive_document_enum() {
+        let docs = json!([
+            {"_id": "1", "_rev": "1-a", "type": "invoice"},
+            {"_id": "2", "_rev": "", "type": "note", "created_at": null, "updated_at": null}
+        ]);
+        let mut entities: Vec<Entity> = serde_json::from_value(docs).unwrap();
+        assert!(matches!(&entities[0], Entity::Invoice(invoice) if invoice._id == "1"));
+        assert_eq!(entities[0].get_rev(), "1-a");
+        assert_eq!(entities[1].get_id(), "2");
+
+        let now = SystemTime::now();
+        entities[1].set_timestamps(now);
+        entities[1].set_rev("1-b");
+        match &entities[1] {
+            Entity::Note(note) => {
+                assert_eq!(note._rev, "1-b");
+                assert_eq!(note.created_at, Some(now));
+            }
+            other => panic!("expected a note, found {:?}", other),
+        }
+        assert_eq!(
+            serde_json::to_value(&entities[0]).unwrap(),
+            json!({"_id": "1", "_rev": "1-a", "type": "invoice"})
+        );
+    }
+
+    #[test]
+    fn test_set_empty_rev_on_value() {
+        let mut doc = serde_json::json!({"_id": "1", "_rev": "2-abc"});
+        doc.set_rev("3-def");
+        assert_eq!(doc["_rev"], "3-def");
+        doc.set_rev("");
+        assert!(doc.get("_rev").is_none());
+        assert_eq!(doc.get_rev(), "");
+    }
+
+    #[test]
+    fn test_iterate_collection() {
+        let docs = vec![
+            json!({"_id": "1", "_rev": "1-a"}),
+            json!({"_id": "2"}),
+            json!({"_id": 3}),
+        ];
+        let mut collection: DocumentCollection<Value> = DocumentCollection::new_from_documents(docs, None);
+
+        let ids: Vec<_> = (&collection).into_iter().map(|doc| doc.get_id()).collect();
+        assert_eq!(ids, vec!["1", "2", ""]);
+
+        let typed: Vec<_> = collection.iter_docs::<TestDocument>().collect();
+        assert_eq!(typed[0].as_ref().unwrap()._rev, "1-a");
+        assert!(typed[1].is_err());
+        assert!(typed[2].is_err());
+
+        for doc in &mut collection {
+            doc.set_rev("2-b");
+        }
+        assert_eq!(collection.get_data().len(), 3);
+        let owned: Vec<Value> = collection.clone().into_iter().collect();
+        assert!(owned.iter().all(|doc| doc["_rev"] == "2-b"));
+        assert_eq!(collection.into_data(), owned);
+    }
+
+    #[test]
+    fn test_raw_document() {
+        let json = r#"{"_id":"a\"b","_rev":"1-a","nested":{"_id":"other"}}"#;
+        let mut doc = RawDocument::from_json(json.to_string()).unwrap();
+        assert_eq!(doc.get_id(), "a\"b");
+        assert_eq!(doc.get_rev(), "1-a");
+        assert_eq!(doc.json(), json);
+
+        doc.set_rev("2-b");
+        let value: Value = doc.deserialize().unwrap();
+        assert_eq!(value, json!({"_id": "a\"b", "_rev": "2-b", "nested": {"_id": "other"}}));
+
+        let docs: Vec<RawDocument> = serde_json::from_str(r#"[{"_id": "1"}, {"no_id": true}]"#).unwrap();
+        assert_eq!(docs[0].get_id(), "1");
+        assert_eq!(docs[1].get_id(), "");
+        assert_eq!(docs[1].get_rev(), "");
+        assert_eq!(serde_json::to_string(&docs[1]).unwrap(), r#"{"no_id": true}"#);
+    }
 }
@@ -0,0 +1,229 @@
+use crate::client::Client;
+use crate::error::{CouchError, CouchResult};
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Generates the ids of new documents, for a `Database` handle with an id generator (see
+/// `Database::with_id_generator`). The id is assigned when a document without an `_id` is
+/// saved, created or written in bulk.
+///
+/// Ids that start with the time, like `UuidV7` and `Ulid`, are stored in the order in which they
+/// are created, which keeps the database files and indexes of CouchDB small. A generator can
+/// also be a closure:
+/// ```
+/// use couch_rs::error::CouchResult;
+/// use couch_rs::ids::{Prefixed, UuidV7};
+/// use serde_json::json;
+/// use std::sync::atomic::{AtomicU64, Ordering};
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::new_local_test()?;
+///     let orders = client.db("orders").await?.with_id_generator(Prefixed::new("order:", UuidV7));
+///     let mut order = json!({ "total": 42 });
+///     let details = orders.create(&mut order).await?;
+///     assert!(details.id.starts_with("order:"));
+///
+///     let counter = AtomicU64::new(0);
+///     let numbered = orders.with_id_generator(move || format!("{:08}", counter.fetch_add(1, Ordering::Relaxed)));
+///     Ok(())
+/// }
+/// ```
+#[async_trait]
+pub trait IdGenerator: Send + Sync {
+    /// The id of the next document
+    async fn generate(&self) -> CouchResult<String>;
+}
+
+#[async_trait]
+impl<F> IdGenerator for F
+where
+    F: Fn() -> String + Send + Sync,
+{
+    async fn generate(&self) -> CouchResult<String> {
+        Ok(self())
+    }
+}
+
+/// Version 7 UUIDs: the time in milliseconds, followed by random bits, formatted like
+/// `01890a5d-ac96-774b-bcce-b302099a8057`. The random bits are not cryptographically secure, so
+/// the ids should not be used as secrets.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV7;
+
+#[async_trait]
+impl IdGenerator for UuidV7 {
+    async fn generate(&self) -> CouchResult<String> {
+        Ok(uuid_v7(unix_millis(), random_u64(), random_u64()))
+    }
+}
+
+/// [ULIDs](https://github.com/ulid/spec): the time in milliseconds, followed by random bits, in
+/// 26 characters of Crockford's base32, like `01ARZ3NDEKTSV4RRFFQ69G5FAV`. The random bits are not
+/// cryptographically secure, so the ids should not be used as secrets.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ulid;
+
+#[async_trait]
+impl IdGenerator for Ulid {
+    async fn generate(&self) -> CouchResult<String> {
+        Ok(ulid(unix_millis(), random_u64(), random_u64()))
+    }
+}
+
+/// UUIDs of the server, see `Client::uuids`, with the algorithm that is configured in
+/// `uuids/algorithm` (`sequential` by default). The UUIDs are fetched in batches of 100, and
+/// shared by the clones of the generator.
+#[derive(Debug, Clone)]
+pub struct ServerUuids {
+    client: Client,
+    batch_size: usize,
+    cache: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl ServerUuids {
+    pub fn new(client: &Client) -> Self {
+        ServerUuids {
+            client: client.clone(),
+            batch_size: 100,
+            cache: Arc::default(),
+        }
+    }
+
+    /// The number of UUIDs to fetch at once, up to the `uuids/max_count` of the server
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+}
+
+#[async_trait]
+impl IdGenerator for ServerUuids {
+    async fn generate(&self) -> CouchResult<String> {
+        if let Some(uuid) = self.cache.lock().unwrap().pop_front() {
+            return Ok(uuid);
+        }
+        let mut uuids: VecDeque<String> = self.client.uuids(self.batch_size).await?.into();
+        let uuid = uuids
+            .pop_front()
+            .ok_or_else(|| CouchError::new(s!("no uuids received"), StatusCode::INTERNAL_SERVER_ERROR))?;
+        self.cache.lock().unwrap().extend(uuids);
+        Ok(uuid)
+    }
+}
+
+/// The ids of another generator with a prefix, e.g. the type of the document, or the partition
+/// of a partitioned database followed by `:`
+pub struct Prefixed<G> {
+    prefix: String,
+    generator: G,
+}
+
+impl<G: IdGenerator> Prefixed<G> {
+    pub fn new(prefix: &str, generator: G) -> Self {
+        Prefixed {
+            prefix: prefix.to_string(),
+            generator,
+        }
+    }
+}
+
+#[async_trait]
+impl<G: IdGenerator> IdGenerator for Prefixed<G> {
+    async fn generate(&self) -> CouchResult<String> {
+        Ok(format!("{}{}", self.prefix, self.generator.generate().await?))
+    }
+}
+
+impl<G> fmt::Debug for Prefixed<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Prefixed").field("prefix", &self.prefix).finish()
+    }
+}
+
+/// The id generator of a `Database` handle
+#[derive(Clone)]
+pub(crate) struct Generator(pub(crate) Arc<dyn IdGenerator>);
+
+impl fmt::Debug for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Generator")
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// A random number, from the randomly keyed hasher of the standard library
+fn random_u64() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.finish()
+}
+
+fn uuid_v7(millis: u64, random_a: u64, random_b: u64) -> String {
+    let high = (millis & 0xffff_ffff_ffff) << 16 | 0x7000 | (random_a & 0x0fff);
+    let low = 0x8000_0000_0000_0000 | (random_b & 0x3fff_ffff_ffff_ffff);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+fn ulid(millis: u64, random_a: u64, random_b: u64) -> String {
+    const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+    let random = (u128::from(random_a & 0xffff) << 64) | u128::from(random_b);
+    let value = (u128::from(millis & 0xffff_ffff_ffff) << 80) | random;
+    (0..26)
+        .map(|i| ALPHABET[((value >> (5 * (25 - i))) & 0x1f) as usize] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uuid_v7() {
+        let uuid = uuid_v7(0x0189_0a5d_ac96, 0x74b, 0xbcce_b302_099a_8057);
+        assert_eq!(uuid, "01890a5d-ac96-774b-bcce-b302099a8057");
+        assert_eq!(uuid_v7(0, u64::MAX, u64::MAX), "00000000-0000-7fff-bfff-ffffffffffff");
+        assert!(uuid_v7(1, 0, 0) > uuid_v7(0, u64::MAX, u64::MAX));
+    }
+
+    #[test]
+    fn test_ulid() {
+        assert_eq!(ulid(0, 0, 0), "00000000000000000000000000");
+        assert_eq!(ulid(0xffff_ffff_ffff, u64::MAX, u64::MAX), "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+        assert_eq!(&ulid(1_469_918_176_385, 0, 0)[..10], "01ARYZ6S41");
+        assert!(ulid(2, 0, 0) > ulid(1, u64::MAX, u64::MAX));
+    }
+
+    #[tokio::test]
+    async fn test_generators() {
+        let first = UuidV7.generate().await.unwrap();
+        let second = UuidV7.generate().await.unwrap();
+        assert_ne!(first, second);
+        assert_eq!(Ulid.generate().await.unwrap().len(), 26);
+
+        let prefixed = Prefixed::new("invoice:", || s!("1"));
+        assert_eq!(prefixed.generate().await.unwrap(), "invoice:1");
+    }
+}
@@ -0,0 +1,109 @@
+/// The operation of a request, derived from its path, e.g. `view` for
+/// `/{db}/_design/{ddoc}/_view/{view}`. A label with a low cardinality, for traces and metrics.
+pub(crate) fn operation(path: &str) -> String {
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    let endpoint = |segment: &str| segment.trim_start_matches('_').to_string();
+
+    match segments.as_slice() {
+        [""] => s!("server"),
+        [server, ..] if server.starts_with('_') => endpoint(server),
+        [_db] => s!("database"),
+        [_db, "_design", _ddoc] => s!("design"),
+        [_db, "_design", _ddoc, function, ..] => endpoint(function),
+        [_db, "_local", ..] => s!("local_document"),
+        [_db, endpoint_or_doc, rest @ ..] if endpoint_or_doc.starts_with('_') && !rest.is_empty() => {
+            // e.g. /{db}/_partition/{key}/_find
+            endpoint(
+                rest.last()
+                    .filter(|last| last.starts_with('_'))
+                    .unwrap_or(endpoint_or_doc),
+            )
+        }
+        [_db, endpoint_or_doc] if endpoint_or_doc.starts_with('_') => endpoint(endpoint_or_doc),
+        [_db, _doc] => s!("document"),
+        _ => s!("attachment"),
+    }
+}
+
+/// The (url encoded) database of a request, derived from its path
+pub(crate) fn database(path: &str) -> Option<&str> {
+    path.trim_start_matches('/')
+        .split('/')
+        .next()
+        .filter(|db| !db.is_empty() && !db.starts_with('_'))
+}
+
+/// Sends a request in a `couchdb.request` span, that records the database, operation, method,
+/// path, status and duration of the request
+#[cfg(feature = "tracing")]
+pub(crate) async fn traced<F>(
+    method: &reqwest::Method,
+    url: &reqwest::Url,
+    send: F,
+) -> crate::error::CouchResult<reqwest::Response>
+where
+    F: std::future::Future<Output = crate::error::CouchResult<reqwest::Response>>,
+{
+    use tracing::field::Empty;
+    use tracing::Instrument;
+
+    let path = url.path();
+    let span = tracing::info_span!(
+        "couchdb.request",
+        db.system = "couchdb",
+        db.name = database(path),
+        db.operation = %operation(path),
+        http.method = %method,
+        url.path = path,
+        http.status_code = Empty,
+        duration_ms = Empty,
+        error = Empty,
+    );
+
+    let started = std::time::Instant::now();
+    let outcome = send.instrument(span.clone()).await;
+    span.record("duration_ms", started.elapsed().as_millis() as u64);
+    match &outcome {
+        Ok(response) => span.record("http.status_code", response.status().as_u16()),
+        Err(err) => span.record("error", tracing::field::display(err)),
+    };
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation() {
+        let cases = vec![
+            ("/", "server"),
+            ("/_all_dbs", "all_dbs"),
+            ("/_node/_local/_config", "node"),
+            ("/db", "database"),
+            ("/db/_all_docs", "all_docs"),
+            ("/db/_bulk_docs", "bulk_docs"),
+            ("/db/_find", "find"),
+            ("/db/_index/_design/idx/json/by_name", "index"),
+            ("/db/_partition/sensor/_all_docs", "all_docs"),
+            ("/db/_partition/sensor", "partition"),
+            ("/db/_design/app", "design"),
+            ("/db/_design/app/_view/by_name", "view"),
+            ("/db/_design/app/_show/detail/doc1", "show"),
+            ("/db/_local/checkpoint", "local_document"),
+            ("/db/doc1", "document"),
+            ("/db/doc1/photo.jpg", "attachment"),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(operation(path), expected, "path {}", path);
+        }
+    }
+
+    #[test]
+    fn test_database() {
+        assert_eq!(database("/db/_find"), Some("db"));
+        assert_eq!(database("/my%2Fdb"), Some("my%2Fdb"));
+        assert_eq!(database("/_all_dbs"), None);
+        assert_eq!(database("/"), None);
+    }
+}
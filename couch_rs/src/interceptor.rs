@@ -0,0 +1,111 @@
+use reqwest::header::HeaderMap;
+use reqwest::{Method, Request, Response, StatusCode, Url};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The parts of a request that an `Interceptor` can inspect and change before it is sent
+pub struct RequestParts<'a> {
+    pub method: &'a Method,
+    pub url: &'a Url,
+    /// The headers, including the authentication headers of the client
+    pub headers: &'a mut HeaderMap,
+    /// The body, unless it is streamed
+    pub body: Option<&'a [u8]>,
+}
+
+/// The outcome of a request, as an `Interceptor` sees it
+pub struct ResponseParts<'a> {
+    pub method: &'a Method,
+    pub url: &'a Url,
+    /// The status code, or `None` when the request failed before a response was received
+    pub status: Option<StatusCode>,
+    pub headers: Option<&'a HeaderMap>,
+    /// The time until the response headers were received, or the request failed
+    pub elapsed: Duration,
+}
+
+/// Hooks into every request that a `Client` sends, including every retry, e.g. to add request
+/// ids or signatures, or for logging and metrics. Interceptors run in the order in which they
+/// were added.
+///
+/// Usage:
+/// ```
+/// use couch_rs::error::CouchResult;
+/// use couch_rs::interceptor::{Interceptor, RequestParts, ResponseParts};
+/// use reqwest::header::HeaderValue;
+///
+/// struct RequestLogger;
+///
+/// impl Interceptor for RequestLogger {
+///     fn before(&self, request: &mut RequestParts) {
+///         request.headers.insert("X-Request-Id", HeaderValue::from_static("4fa3c2"));
+///     }
+///
+///     fn after(&self, response: &ResponseParts) {
+///         println!("{} {} -> {:?} in {:?}", response.method, response.url, response.status, response.elapsed);
+///     }
+/// }
+///
+/// fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::builder("http://localhost:5984").interceptor(RequestLogger).build()?;
+///     Ok(())
+/// }
+/// ```
+pub trait Interceptor: Send + Sync {
+    /// Called before a request is sent
+    fn before(&self, _request: &mut RequestParts) {}
+
+    /// Called after a response was received, or the request failed
+    fn after(&self, _response: &ResponseParts) {}
+}
+
+/// The interceptors of a `Client`
+#[derive(Clone, Default)]
+pub(crate) struct Interceptors(Vec<Arc<dyn Interceptor>>);
+
+impl Interceptors {
+    pub(crate) fn push(&mut self, interceptor: Arc<dyn Interceptor>) {
+        self.0.push(interceptor);
+    }
+
+    /// Sends a request with the interceptors around it
+    pub(crate) async fn execute(&self, http: &reqwest::Client, mut request: Request) -> reqwest::Result<Response> {
+        if self.0.is_empty() {
+            return http.execute(request).await;
+        }
+
+        let method = request.method().clone();
+        let url = request.url().clone();
+        // moved out of the request, to lend them to the interceptors
+        let body = request.body_mut().take();
+        let mut headers = std::mem::take(request.headers_mut());
+        let mut parts = RequestParts {
+            method: &method,
+            url: &url,
+            headers: &mut headers,
+            body: body.as_ref().and_then(|body| body.as_bytes()),
+        };
+        self.0.iter().for_each(|interceptor| interceptor.before(&mut parts));
+        *request.headers_mut() = headers;
+        *request.body_mut() = body;
+
+        let started = Instant::now();
+        let outcome = http.execute(request).await;
+        let parts = ResponseParts {
+            method: &method,
+            url: &url,
+            status: outcome.as_ref().ok().map(|response| response.status()),
+            headers: outcome.as_ref().ok().map(|response| response.headers()),
+            elapsed: started.elapsed(),
+        };
+        self.0.iter().for_each(|interceptor| interceptor.after(&parts));
+        outcome
+    }
+}
+
+impl fmt::Debug for Interceptors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Interceptors({})", self.0.len())
+    }
+}
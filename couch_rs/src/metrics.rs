@@ -0,0 +1,168 @@
+use reqwest::{Method, StatusCode};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The metrics of one operation of a `Client`, including its retries
+#[derive(Debug, Clone)]
+pub struct RequestMetrics<'a> {
+    /// The operation, derived from the path, e.g. `document`, `find`, `bulk_docs` or `view`
+    pub operation: &'a str,
+    pub method: &'a Method,
+    /// The (url encoded) database, unless it is a server operation
+    pub database: Option<&'a str>,
+    /// The status code, or `None` when the request failed before a response was received
+    pub status: Option<StatusCode>,
+    /// The time until the response headers were received, or the request failed
+    pub latency: Duration,
+    /// The size of the request body as sent, unless it is streamed
+    pub request_size: Option<u64>,
+    /// The size of the response body, when the server sent its length
+    pub response_size: Option<u64>,
+}
+
+/// Receives the metrics of every operation of a `Client`, e.g. to export them to a monitoring
+/// system. With the `prometheus` feature, `PrometheusSink` records them in a prometheus registry.
+///
+/// Usage:
+/// ```
+/// use couch_rs::error::CouchResult;
+/// use couch_rs::metrics::{MetricsSink, RequestMetrics};
+///
+/// struct SlowRequestLogger;
+///
+/// impl MetricsSink for SlowRequestLogger {
+///     fn record(&self, metrics: &RequestMetrics) {
+///         if metrics.latency.as_secs() >= 1 {
+///             println!("slow {} on {:?}: {:?}", metrics.operation, metrics.database, metrics.latency);
+///         }
+///     }
+/// }
+///
+/// fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::builder("http://localhost:5984").metrics(SlowRequestLogger).build()?;
+///     Ok(())
+/// }
+/// ```
+pub trait MetricsSink: Send + Sync {
+    fn record(&self, metrics: &RequestMetrics);
+}
+
+/// The metrics sink of a `Client`
+#[derive(Clone)]
+pub(crate) struct Metrics(pub(crate) Arc<dyn MetricsSink>);
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Metrics")
+    }
+}
+
+/// Records the metrics of a `Client` in a prometheus registry, as:
+/// - `couchdb_request_duration_seconds`: a histogram of the latency
+/// - `couchdb_request_size_bytes` and `couchdb_response_size_bytes`: histograms of the payload sizes
+///
+/// All with the labels `operation`, `method` and `status` (`error` for failed requests).
+///
+/// Usage:
+/// ```
+/// use couch_rs::metrics::PrometheusSink;
+/// use std::error::Error;
+///
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let registry = prometheus::Registry::new();
+///     let sink = PrometheusSink::new(&registry)?;
+///     let client = couch_rs::Client::builder("http://localhost:5984").metrics(sink).build()?;
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "prometheus")]
+#[derive(Debug, Clone)]
+pub struct PrometheusSink {
+    duration: prometheus::HistogramVec,
+    request_size: prometheus::HistogramVec,
+    response_size: prometheus::HistogramVec,
+}
+
+#[cfg(feature = "prometheus")]
+impl PrometheusSink {
+    /// Creates the metrics, and registers them in the registry
+    pub fn new(registry: &prometheus::Registry) -> prometheus::Result<Self> {
+        use prometheus::{exponential_buckets, HistogramOpts, HistogramVec};
+
+        const LABELS: [&str; 3] = ["operation", "method", "status"];
+        let size_buckets = exponential_buckets(64.0, 4.0, 10)?;
+        let sink = PrometheusSink {
+            duration: HistogramVec::new(
+                HistogramOpts::new("couchdb_request_duration_seconds", "The latency of CouchDB requests"),
+                &LABELS,
+            )?,
+            request_size: HistogramVec::new(
+                HistogramOpts::new("couchdb_request_size_bytes", "The body sizes of CouchDB requests")
+                    .buckets(size_buckets.clone()),
+                &LABELS,
+            )?,
+            response_size: HistogramVec::new(
+                HistogramOpts::new("couchdb_response_size_bytes", "The body sizes of CouchDB responses")
+                    .buckets(size_buckets),
+                &LABELS,
+            )?,
+        };
+        registry.register(Box::new(sink.duration.clone()))?;
+        registry.register(Box::new(sink.request_size.clone()))?;
+        registry.register(Box::new(sink.response_size.clone()))?;
+        Ok(sink)
+    }
+}
+
+#[cfg(feature = "prometheus")]
+impl MetricsSink for PrometheusSink {
+    fn record(&self, metrics: &RequestMetrics) {
+        let status = metrics
+            .status
+            .map_or_else(|| s!("error"), |status| status.as_u16().to_string());
+        let labels = [metrics.operation, metrics.method.as_str(), status.as_str()];
+
+        self.duration
+            .with_label_values(&labels)
+            .observe(metrics.latency.as_secs_f64());
+        if let Some(size) = metrics.request_size {
+            self.request_size.with_label_values(&labels).observe(size as f64);
+        }
+        if let Some(size) = metrics.response_size {
+            self.response_size.with_label_values(&labels).observe(size as f64);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "prometheus"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prometheus_sink() {
+        let registry = prometheus::Registry::new();
+        let sink = PrometheusSink::new(&registry).unwrap();
+        sink.record(&RequestMetrics {
+            operation: "find",
+            method: &Method::POST,
+            database: Some("db"),
+            status: Some(StatusCode::OK),
+            latency: Duration::from_millis(20),
+            request_size: Some(120),
+            response_size: None,
+        });
+
+        let families = registry.gather();
+        let duration = families
+            .iter()
+            .find(|family| family.get_name() == "couchdb_request_duration_seconds")
+            .unwrap();
+        let histogram = duration.get_metric()[0].get_histogram();
+        assert_eq!(histogram.get_sample_count(), 1);
+        let response_size = families
+            .iter()
+            .find(|family| family.get_name() == "couchdb_response_size_bytes");
+        assert!(response_size.is_none());
+    }
+}
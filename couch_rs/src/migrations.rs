@@ -0,0 +1,402 @@
+use crate::database::Database;
+use crate::document::{from_typed_value, TypedCouchDocument};
+use crate::error::{CouchError, CouchResult};
+use crate::types::query::ViewQuery;
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// The `_local` document that holds the applied version, when no other id is set
+const DEFAULT_STATE_ID: &str = "_local/migrations";
+
+const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// The progress of a migration, passed to the handler of `Migrator::on_progress` after every batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    pub version: u64,
+    pub name: String,
+    /// The number of documents that were read, including those of an earlier, interrupted run
+    pub processed: u64,
+    /// The number of documents that were changed and written, including those of an earlier,
+    /// interrupted run
+    pub migrated: u64,
+}
+
+/// The outcome of `Migrator::run`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MigrationReport {
+    /// The version of the database before the run
+    pub from_version: u64,
+    /// The version of the database after the run
+    pub to_version: u64,
+    /// The versions of the migrations that were applied, in order
+    pub applied: Vec<u64>,
+    /// The number of documents that were changed and written in this run
+    pub migrated: u64,
+}
+
+/// The applied version of a database, and the position of the migration that is running
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+struct State {
+    #[serde(default)]
+    version: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    running: Option<Running>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct Running {
+    version: u64,
+    /// The id of the last document of the last written batch
+    last_id: String,
+    processed: u64,
+    migrated: u64,
+}
+
+impl Running {
+    fn new(version: u64) -> Self {
+        Running {
+            version,
+            last_id: String::new(),
+            processed: 0,
+            migrated: 0,
+        }
+    }
+}
+
+/// Migrates a batch of documents
+#[async_trait]
+trait Migrate: Send + Sync {
+    /// Returns the number of documents that were written
+    async fn migrate(&self, db: &Database, docs: Vec<Value>) -> CouchResult<u64>;
+}
+
+struct TypedMigration<T, F> {
+    f: F,
+    doc: PhantomData<fn() -> T>,
+}
+
+#[async_trait]
+impl<T, F> Migrate for TypedMigration<T, F>
+where
+    T: TypedCouchDocument + Send + Sync,
+    F: Fn(&mut T) -> bool + Send + Sync,
+{
+    async fn migrate(&self, db: &Database, docs: Vec<Value>) -> CouchResult<u64> {
+        let mut changed = vec![];
+        for doc in docs {
+            if !is_of_type::<T>(&doc) {
+                continue;
+            }
+            let mut typed: T = from_typed_value(&doc).map_err(|err| err.with_id(&doc.get_id()))?;
+            if (self.f)(&mut typed) {
+                changed.push(typed);
+            }
+        }
+        if changed.is_empty() {
+            return Ok(0);
+        }
+        for result in db.bulk_docs(&mut changed).await? {
+            result?;
+        }
+        Ok(changed.len() as u64)
+    }
+}
+
+/// Whether a migration over `T` applies to the document; all documents when `T` has no type name
+fn is_of_type<T: TypedCouchDocument>(doc: &Value) -> bool {
+    match T::type_name() {
+        Some(type_name) => doc.get("type").and_then(Value::as_str) == Some(type_name),
+        None => true,
+    }
+}
+
+type ProgressHandler = Box<dyn Fn(&Progress) + Send + Sync>;
+
+struct Migration {
+    version: u64,
+    name: String,
+    migrate: Box<dyn Migrate>,
+}
+
+/// Applies ordered migrations to the documents of a database, e.g. to add a field with a default
+/// value, or to rename a field. Every migration has a version; the version of the database is
+/// kept in the `_local/migrations` document, and `run` applies the migrations with a higher
+/// version, in the order of their versions.
+///
+/// A migration is a function over typed documents, which changes a document and returns whether
+/// it did. It is called for every document in the database, except design documents. When the
+/// type has a `type_name` (see `#[couch(type_name)]`) only documents of that type are migrated,
+/// otherwise every document must deserialize into the type. The changed documents are written in
+/// batches.
+///
+/// The position of a running migration is stored after every batch, so when a run fails, e.g.
+/// because a document was changed in the meantime, the next run resumes with the batch that
+/// failed. Documents of that batch that were written can be passed to the migration again, so it
+/// must leave migrated documents unchanged.
+///
+/// Usage:
+/// ```
+/// use couch_rs::error::CouchResult;
+/// use couch_rs::migrations::Migrator;
+/// use serde_json::{json, Value};
+///
+/// const TEST_DB: &str = "test_db";
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::new_local_test()?;
+///     let db = client.db(TEST_DB).await?;
+///
+///     let report = Migrator::new(&db)
+///         .migration(1, "add status", |doc: &mut Value| match doc.get("status") {
+///             Some(_) => false,
+///             None => {
+///                 doc["status"] = json!("active");
+///                 true
+///             }
+///         })
+///         .on_progress(|progress| println!("{}: {} documents", progress.name, progress.processed))
+///         .run()
+///         .await?;
+///     println!("migrated {} documents to version {}", report.migrated, report.to_version);
+///     Ok(())
+/// }
+/// ```
+pub struct Migrator {
+    db: Database,
+    state_id: String,
+    batch_size: usize,
+    migrations: Vec<Migration>,
+    on_progress: Option<ProgressHandler>,
+}
+
+impl Migrator {
+    pub fn new(db: &Database) -> Self {
+        Migrator {
+            db: db.clone(),
+            state_id: DEFAULT_STATE_ID.to_string(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            migrations: vec![],
+            on_progress: None,
+        }
+    }
+
+    /// Adds a migration. The versions of the migrations must be unique, the order in which they
+    /// are added doesn't matter.
+    pub fn migration<T, F>(mut self, version: u64, name: &str, f: F) -> Self
+    where
+        T: TypedCouchDocument + Send + Sync + 'static,
+        F: Fn(&mut T) -> bool + Send + Sync + 'static,
+    {
+        self.migrations.push(Migration {
+            version,
+            name: name.to_string(),
+            migrate: Box::new(TypedMigration { f, doc: PhantomData }),
+        });
+        self
+    }
+
+    /// The number of documents that are read and written at a time, 500 by default
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Keeps the version in another `_local` or design document, e.g. one per application, or a
+    /// design document to replicate the version with the documents. Other documents would be
+    /// migrated themselves.
+    pub fn state_id(mut self, id: &str) -> Self {
+        self.state_id = id.to_string();
+        self
+    }
+
+    pub fn on_progress(mut self, handler: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+        self.on_progress = Some(Box::new(handler));
+        self
+    }
+
+    /// The version of the database: that of the last migration that was applied, or 0
+    pub async fn version(&self) -> CouchResult<u64> {
+        Ok(self.load().await?.version)
+    }
+
+    /// Applies the migrations with a version higher than that of the database, in order
+    pub async fn run(&self) -> CouchResult<MigrationReport> {
+        let migrations = self.ordered()?;
+        let mut state = self.load().await?;
+        let mut report = MigrationReport {
+            from_version: state.version,
+            to_version: state.version,
+            ..MigrationReport::default()
+        };
+
+        for migration in migrations.into_iter().filter(|m| m.version > report.from_version) {
+            let running = match state.running.take() {
+                Some(running) if running.version == migration.version => running,
+                _ => Running::new(migration.version),
+            };
+            report.migrated += self.apply(migration, running, &mut state).await?;
+
+            state.version = migration.version;
+            state.running = None;
+            self.save(&state).await?;
+            report.applied.push(migration.version);
+            report.to_version = migration.version;
+        }
+        Ok(report)
+    }
+
+    /// The migrations in the order of their versions
+    fn ordered(&self) -> CouchResult<Vec<&Migration>> {
+        let mut migrations: Vec<&Migration> = self.migrations.iter().collect();
+        migrations.sort_by_key(|m| m.version);
+        if let Some(pair) = migrations.windows(2).find(|pair| pair[0].version == pair[1].version) {
+            return Err(CouchError::new(
+                format!("duplicate migration version {}", pair[0].version),
+                StatusCode::BAD_REQUEST,
+            ));
+        }
+        Ok(migrations)
+    }
+
+    /// Runs a migration from its position, storing the position after every batch. Returns the
+    /// number of documents that were written.
+    async fn apply(&self, migration: &Migration, mut running: Running, state: &mut State) -> CouchResult<u64> {
+        let mut query = ViewQuery::default();
+        if !running.last_id.is_empty() {
+            query = query.start_key(running.last_id.clone());
+        }
+        let mut docs = self.db.all_docs_stream::<Value>(query, self.batch_size as u64);
+        let mut batch = Vec::with_capacity(self.batch_size);
+        let mut migrated = 0;
+
+        loop {
+            let doc = docs.try_next().await?;
+            let done = doc.is_none();
+            if let Some(doc) = doc {
+                // the start key is the last document of the previous batch
+                if doc.get_id() != running.last_id {
+                    batch.push(doc);
+                }
+            }
+            if batch.len() >= self.batch_size || (done && !batch.is_empty()) {
+                let last_id = batch.last().map(|doc| doc.get_id().into_owned()).unwrap_or_default();
+                let processed = batch.len() as u64;
+                let written = migration.migrate.migrate(&self.db, std::mem::take(&mut batch)).await?;
+
+                running.last_id = last_id;
+                running.processed += processed;
+                running.migrated += written;
+                migrated += written;
+                state.running = Some(running.clone());
+                self.save(state).await?;
+
+                if let Some(on_progress) = &self.on_progress {
+                    on_progress(&Progress {
+                        version: migration.version,
+                        name: migration.name.clone(),
+                        processed: running.processed,
+                        migrated: running.migrated,
+                    });
+                }
+            }
+            if done {
+                return Ok(migrated);
+            }
+        }
+    }
+
+    async fn load(&self) -> CouchResult<State> {
+        match self.db.get::<Value>(&self.state_id).await {
+            Ok(doc) => Ok(serde_json::from_value(doc)?),
+            Err(err) if err.is_not_found() => Ok(State::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn save(&self, state: &State) -> CouchResult<()> {
+        let mut doc = serde_json::to_value(state)?;
+        doc["_id"] = json!(self.state_id);
+        self.db.upsert(&mut doc).await?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Migrator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let versions: Vec<u64> = self.migrations.iter().map(|m| m.version).collect();
+        f.debug_struct("Migrator")
+            .field("db", &self.db.name())
+            .field("state_id", &self.state_id)
+            .field("migrations", &versions)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Client;
+
+    fn unreachable_db() -> Database {
+        let client = Client::new("http://localhost:1", "admin", "password").unwrap();
+        Database::new(s!("migrations"), client)
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_versions() {
+        let migrator = Migrator::new(&unreachable_db())
+            .migration(2, "second", |_: &mut Value| false)
+            .migration(1, "first", |_: &mut Value| false)
+            .migration(2, "again", |_: &mut Value| false);
+        let err = migrator.run().await.unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::BAD_REQUEST));
+
+        let migrator = Migrator::new(&unreachable_db())
+            .migration(2, "second", |_: &mut Value| false)
+            .migration(1, "first", |_: &mut Value| false);
+        let versions: Vec<u64> = migrator.ordered().unwrap().iter().map(|m| m.version).collect();
+        assert_eq!(versions, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_batch_is_not_written() {
+        let migration = TypedMigration {
+            f: |doc: &mut Value| doc.get("status").is_none(),
+            doc: PhantomData,
+        };
+        let docs = vec![json!({"_id": "a", "_rev": "1-a", "status": "active"})];
+        assert_eq!(migration.migrate(&unreachable_db(), docs).await.unwrap(), 0);
+
+        let docs = vec![json!({"_id": "b", "_rev": "1-b"})];
+        assert!(migration.migrate(&unreachable_db(), docs).await.is_err());
+    }
+
+    #[test]
+    fn test_state() {
+        let state: State = serde_json::from_value(json!({"_id": "_local/migrations", "_rev": "0-1"})).unwrap();
+        assert_eq!(state, State::default());
+
+        let state = State {
+            version: 1,
+            running: Some(Running {
+                version: 2,
+                last_id: s!("a"),
+                processed: 10,
+                migrated: 4,
+            }),
+        };
+        let json = serde_json::to_value(&state).unwrap();
+        assert_eq!(
+            json,
+            json!({"version": 1, "running": {"version": 2, "last_id": "a", "processed": 10, "migrated": 4}})
+        );
+        assert_eq!(serde_json::from_value::<State>(json).unwrap(), state);
+    }
+}
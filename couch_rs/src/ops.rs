@@ -0,0 +1,164 @@
+use crate::database::Database;
+use crate::document::{DocumentCollection, TypedCouchDocument};
+use crate::error::CouchResult;
+use crate::types::document::{DocumentCreatedResult, DocumentId};
+use crate::types::find::FindQuery;
+use crate::types::query::QueryParams;
+use crate::types::view::ViewCollection;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+
+/// The core operations of a `Database`. Application code that depends on this trait, instead of
+/// on `Database`, can be unit tested with a fake or a mock (e.g. one generated by mockall)
+/// instead of a CouchDB server. The methods behave like those of `Database` with the same name.
+///
+/// Usage:
+/// ```
+/// use couch_rs::error::CouchResult;
+/// use couch_rs::ops::CouchOps;
+/// use serde_json::{json, Value};
+///
+/// const TEST_DB: &str = "test_db";
+///
+/// // works with a Database, and with a fake in the tests of the application
+/// async fn rename(db: &impl CouchOps, id: &str, name: &str) -> CouchResult<()> {
+///     let mut doc: Value = db.get(id).await?;
+///     doc["name"] = json!(name);
+///     db.save(&mut doc).await?;
+///     Ok(())
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::new_local_test()?;
+///     let db = client.db(TEST_DB).await?;
+///     db.upsert(&mut json!({"_id": "john", "name": "John"})).await?;
+///     rename(&db, "john", "Johnny").await
+/// }
+/// ```
+#[async_trait]
+pub trait CouchOps: Send + Sync {
+    /// The name of the database
+    fn name(&self) -> &str;
+
+    async fn exists(&self, id: &str) -> bool;
+
+    async fn get<T: TypedCouchDocument + Send>(&self, id: &str) -> CouchResult<T>;
+
+    async fn get_bulk<T: TypedCouchDocument + Send>(&self, ids: Vec<DocumentId>) -> CouchResult<DocumentCollection<T>>;
+
+    async fn get_all<T: TypedCouchDocument + Send>(&self) -> CouchResult<DocumentCollection<T>>;
+
+    async fn find<T: TypedCouchDocument + Send>(&self, query: &FindQuery) -> CouchResult<DocumentCollection<T>>;
+
+    async fn save<T: TypedCouchDocument + Send + Sync>(&self, doc: &mut T) -> DocumentCreatedResult;
+
+    async fn create<T: TypedCouchDocument + Send + Sync>(&self, doc: &mut T) -> DocumentCreatedResult;
+
+    async fn upsert<T: TypedCouchDocument + Send + Sync>(&self, doc: &mut T) -> DocumentCreatedResult;
+
+    async fn bulk_docs<T: TypedCouchDocument + Send + Sync>(
+        &self,
+        docs: &mut [T],
+    ) -> CouchResult<Vec<DocumentCreatedResult>>;
+
+    async fn remove<T: TypedCouchDocument + Send + Sync>(&self, doc: &T) -> bool;
+
+    async fn query<K, V, T>(
+        &self,
+        design_name: &str,
+        view_name: &str,
+        options: Option<QueryParams>,
+    ) -> CouchResult<ViewCollection<K, V, T>>
+    where
+        K: DeserializeOwned + Send,
+        V: DeserializeOwned + Send,
+        T: TypedCouchDocument + Send;
+}
+
+#[async_trait]
+impl CouchOps for Database {
+    fn name(&self) -> &str {
+        Database::name(self)
+    }
+
+    async fn exists(&self, id: &str) -> bool {
+        Database::exists(self, id).await
+    }
+
+    async fn get<T: TypedCouchDocument + Send>(&self, id: &str) -> CouchResult<T> {
+        Database::get(self, id).await
+    }
+
+    async fn get_bulk<T: TypedCouchDocument + Send>(&self, ids: Vec<DocumentId>) -> CouchResult<DocumentCollection<T>> {
+        Database::get_bulk(self, ids).await
+    }
+
+    async fn get_all<T: TypedCouchDocument + Send>(&self) -> CouchResult<DocumentCollection<T>> {
+        Database::get_all(self).await
+    }
+
+    async fn find<T: TypedCouchDocument + Send>(&self, query: &FindQuery) -> CouchResult<DocumentCollection<T>> {
+        Database::find(self, query).await
+    }
+
+    async fn save<T: TypedCouchDocument + Send + Sync>(&self, doc: &mut T) -> DocumentCreatedResult {
+        Database::save(self, doc).await
+    }
+
+    async fn create<T: TypedCouchDocument + Send + Sync>(&self, doc: &mut T) -> DocumentCreatedResult {
+        Database::create(self, doc).await
+    }
+
+    async fn upsert<T: TypedCouchDocument + Send + Sync>(&self, doc: &mut T) -> DocumentCreatedResult {
+        Database::upsert(self, doc).await
+    }
+
+    async fn bulk_docs<T: TypedCouchDocument + Send + Sync>(
+        &self,
+        docs: &mut [T],
+    ) -> CouchResult<Vec<DocumentCreatedResult>> {
+        Database::bulk_docs(self, docs).await
+    }
+
+    async fn remove<T: TypedCouchDocument + Send + Sync>(&self, doc: &T) -> bool {
+        Database::remove(self, doc).await
+    }
+
+    async fn query<K, V, T>(
+        &self,
+        design_name: &str,
+        view_name: &str,
+        options: Option<QueryParams>,
+    ) -> CouchResult<ViewCollection<K, V, T>>
+    where
+        K: DeserializeOwned + Send,
+        V: DeserializeOwned + Send,
+        T: TypedCouchDocument + Send,
+    {
+        Database::query(self, design_name, view_name, options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CouchError;
+    use crate::Client;
+    use serde_json::Value;
+
+    async fn first_name(db: &impl CouchOps, id: &str) -> CouchResult<Value> {
+        let doc: Value = db.get(id).await?;
+        Ok(doc["first_name"].clone())
+    }
+
+    #[tokio::test]
+    async fn test_database_ops() {
+        let client = Client::new("http://localhost:1", "admin", "password").unwrap();
+        let db = Database::new(s!("ops"), client);
+        assert_eq!(CouchOps::name(&db), "ops");
+        assert!(!CouchOps::exists(&db, "john").await);
+        let err = first_name(&db, "john").await.unwrap_err();
+        assert!(matches!(err, CouchError::Transport(_)));
+    }
+}
@@ -0,0 +1,330 @@
+use crate::database::Database;
+use crate::document::TypedCouchDocument;
+use crate::error::{CouchError, CouchResult};
+use crate::types::document::DocumentCreatedDetails;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// A write that is queued in an `Outbox`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Write {
+    /// Saves the document, with the `_rev` it had when it was queued
+    Save { doc: Value },
+    /// Deletes the revision of the document
+    Delete { id: String, rev: String },
+}
+
+impl Write {
+    /// The id of the document that is written
+    pub fn id(&self) -> &str {
+        match self {
+            Write::Save { doc } => doc.get("_id").and_then(Value::as_str).unwrap_or_default(),
+            Write::Delete { id, .. } => id,
+        }
+    }
+}
+
+/// What to do with a queued write that conflicts with the document in the database, because
+/// the document was changed while the write was queued
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    /// Drops the write, keeping the document in the database
+    Discard,
+    /// Applies the write on top of the current revision, overwriting (or deleting) it
+    Overwrite,
+    /// Saves this document on top of the current revision instead, e.g. a merge of both
+    Save(Value),
+}
+
+/// Decides what happens with a write that conflicts when the outbox is flushed. It gets the
+/// write, and the current revision of the document, which is `None` when the document was
+/// deleted.
+///
+/// A handler can be a closure:
+/// ```
+/// use couch_rs::outbox::{ConflictHandler, Resolution, Write};
+/// use serde_json::Value;
+///
+/// // the server wins, except for documents that were deleted in the meantime
+/// let handler = |_write: &Write, current: Option<&Value>| match current {
+///     Some(_) => Resolution::Discard,
+///     None => Resolution::Overwrite,
+/// };
+/// let write = Write::Delete { id: "a".to_string(), rev: "1-a".to_string() };
+/// assert_eq!(handler.resolve(&write, None), Resolution::Overwrite);
+/// ```
+pub trait ConflictHandler: Send + Sync {
+    fn resolve(&self, write: &Write, current: Option<&Value>) -> Resolution;
+}
+
+impl<F> ConflictHandler for F
+where
+    F: Fn(&Write, Option<&Value>) -> Resolution + Send + Sync,
+{
+    fn resolve(&self, write: &Write, current: Option<&Value>) -> Resolution {
+        self(write, current)
+    }
+}
+
+/// The outcome of a write through an `Outbox`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Delivery {
+    /// The write was applied, with the new revision of the document
+    Written(DocumentCreatedDetails),
+    /// The write was queued, because CouchDB is unreachable or earlier writes are still queued
+    Queued,
+}
+
+/// The outcome of `Outbox::flush`
+#[derive(Debug, Default)]
+pub struct FlushReport {
+    /// The writes that were applied, with the new revisions of the documents
+    pub written: Vec<DocumentCreatedDetails>,
+    /// The writes that conflicted, and were discarded by the conflict handler
+    pub discarded: Vec<Write>,
+    /// The writes that were rejected, e.g. because the user may not write the document
+    pub failed: Vec<(Write, CouchError)>,
+    /// The number of writes that are still queued, because CouchDB became unreachable
+    pub remaining: usize,
+}
+
+/// Queues the saves and deletes of documents while CouchDB is unreachable, for clients that are
+/// only connected now and then, like edge devices. A write is applied directly when the outbox
+/// is empty and CouchDB can be reached, and queued when it fails with a retryable error (see
+/// `CouchError::is_retryable`). `flush` applies the queued writes in order, and stops at the
+/// first write that fails with a retryable error again.
+///
+/// Writes that conflict when they are flushed are passed to the `ConflictHandler`; without a
+/// handler they are discarded. The queue is kept in memory; use `pending` and `with_pending` to
+/// persist it over restarts.
+///
+/// Usage:
+/// ```
+/// use couch_rs::error::CouchResult;
+/// use couch_rs::outbox::{Delivery, Outbox, Resolution};
+/// use serde_json::json;
+///
+/// const TEST_DB: &str = "test_db";
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::new_local_test()?;
+///     let db = client.db(TEST_DB).await?;
+///     let outbox = Outbox::new(&db).on_conflict(|_write: &_, _current: Option<&_>| Resolution::Overwrite);
+///
+///     let mut reading = json!({ "_id": "sensor-1", "temperature": 21.5 });
+///     if outbox.save(&mut reading).await? == Delivery::Queued {
+///         println!("{} writes waiting for CouchDB", outbox.len().await);
+///     }
+///
+///     // later, e.g. on a timer
+///     let report = outbox.flush().await;
+///     println!("{} written, {} still queued", report.written.len(), report.remaining);
+///     Ok(())
+/// }
+/// ```
+pub struct Outbox {
+    db: Database,
+    // held while writing, so that the writes are applied in the order in which they were made
+    queue: Mutex<VecDeque<Write>>,
+    on_conflict: Option<Arc<dyn ConflictHandler>>,
+}
+
+impl Outbox {
+    pub fn new(db: &Database) -> Self {
+        Self::with_pending(db, vec![])
+    }
+
+    /// An outbox with the writes that were pending in an earlier one, see `pending`
+    pub fn with_pending(db: &Database, writes: Vec<Write>) -> Self {
+        Outbox {
+            db: db.clone(),
+            queue: Mutex::new(writes.into()),
+            on_conflict: None,
+        }
+    }
+
+    pub fn on_conflict(mut self, handler: impl ConflictHandler + 'static) -> Self {
+        self.on_conflict = Some(Arc::new(handler));
+        self
+    }
+
+    /// The queued writes, in order
+    pub async fn pending(&self) -> Vec<Write> {
+        self.queue.lock().await.iter().cloned().collect()
+    }
+
+    /// The number of queued writes
+    pub async fn len(&self) -> usize {
+        self.queue.lock().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    /// Saves a document, or queues the save. When the document is written, its `_id` and `_rev`
+    /// are updated; a queued document is left unchanged.
+    pub async fn save<T: TypedCouchDocument>(&self, doc: &mut T) -> CouchResult<Delivery> {
+        let write = Write::Save {
+            doc: serde_json::to_value(&*doc)?,
+        };
+        let delivery = self.write(write).await?;
+        if let Delivery::Written(details) = &delivery {
+            doc.set_id(&details.id);
+            doc.set_rev(&details.rev);
+        }
+        Ok(delivery)
+    }
+
+    /// Deletes a revision of a document, or queues the delete
+    pub async fn delete(&self, id: &str, rev: &str) -> CouchResult<Delivery> {
+        let write = Write::Delete {
+            id: id.to_string(),
+            rev: rev.to_string(),
+        };
+        self.write(write).await
+    }
+
+    async fn write(&self, write: Write) -> CouchResult<Delivery> {
+        let mut queue = self.queue.lock().await;
+        if !queue.is_empty() {
+            queue.push_back(write);
+            return Ok(Delivery::Queued);
+        }
+        match self.apply(&write).await {
+            Ok(details) => Ok(Delivery::Written(details)),
+            Err(err) if err.is_retryable() => {
+                queue.push_back(write);
+                Ok(Delivery::Queued)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Applies the queued writes in order, until CouchDB can't be reached
+    pub async fn flush(&self) -> FlushReport {
+        let mut queue = self.queue.lock().await;
+        let mut report = FlushReport::default();
+        while let Some(write) = queue.pop_front() {
+            match self.apply_resolved(&write).await {
+                Ok(Some(details)) => report.written.push(details),
+                Ok(None) => report.discarded.push(write),
+                Err(err) if err.is_retryable() => {
+                    queue.push_front(write);
+                    break;
+                }
+                Err(err) => report.failed.push((write, err)),
+            }
+        }
+        report.remaining = queue.len();
+        report
+    }
+
+    /// Applies a write, resolving a conflict with the conflict handler. Returns `None` when the
+    /// write is discarded.
+    async fn apply_resolved(&self, write: &Write) -> CouchResult<Option<DocumentCreatedDetails>> {
+        match self.apply(write).await {
+            Err(err) if err.is_conflict() => {}
+            outcome => return outcome.map(Some),
+        }
+
+        let current = match self.db.get::<Value>(write.id()).await {
+            Ok(current) => Some(current),
+            Err(err) if err.is_not_found() => None,
+            Err(err) => return Err(err),
+        };
+        let resolution = match &self.on_conflict {
+            Some(handler) => handler.resolve(write, current.as_ref()),
+            None => Resolution::Discard,
+        };
+        let current_rev = current.as_ref().map(|doc| doc.get_rev().into_owned());
+        let rebased = match (resolution, write) {
+            (Resolution::Discard, _) => return Ok(None),
+            (Resolution::Overwrite, Write::Save { doc }) => rebase(doc.clone(), current_rev),
+            (Resolution::Save(doc), _) => rebase(doc, current_rev),
+            (Resolution::Overwrite, Write::Delete { id, .. }) => match current_rev {
+                Some(rev) => Write::Delete { id: id.clone(), rev },
+                // already deleted
+                None => return Ok(None),
+            },
+        };
+        self.apply(&rebased).await.map(Some)
+    }
+
+    async fn apply(&self, write: &Write) -> CouchResult<DocumentCreatedDetails> {
+        match write {
+            Write::Save { doc } => self.db.save(&mut doc.clone()).await,
+            Write::Delete { id, rev } => self.db.delete_doc(id, rev).await,
+        }
+    }
+}
+
+/// A save of the document on top of the current revision, or as a new document
+fn rebase(mut doc: Value, current_rev: Option<String>) -> Write {
+    match current_rev {
+        Some(rev) => doc.set_rev(&rev),
+        None => {
+            if let Some(doc) = doc.as_object_mut() {
+                doc.remove("_rev");
+            }
+        }
+    }
+    Write::Save { doc }
+}
+
+impl fmt::Debug for Outbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Outbox").field("db", &self.db.name()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Client;
+    use serde_json::json;
+
+    #[test]
+    fn test_write() {
+        let write = Write::Save {
+            doc: json!({ "_id": "a", "_rev": "1-a" }),
+        };
+        assert_eq!(write.id(), "a");
+        let json = serde_json::to_value(&write).unwrap();
+        assert_eq!(json, json!({ "type": "save", "doc": { "_id": "a", "_rev": "1-a" } }));
+        assert_eq!(serde_json::from_value::<Write>(json).unwrap(), write);
+
+        let rebased = rebase(json!({ "_id": "a", "_rev": "1-a" }), None);
+        assert_eq!(rebased.id(), "a");
+        assert_eq!(
+            rebased,
+            Write::Save {
+                doc: json!({ "_id": "a" })
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_queue_while_unreachable() {
+        let client = Client::new("http://localhost:1", "admin", "password").unwrap();
+        let outbox = Outbox::new(&Database::new(s!("edge"), client));
+
+        let mut doc = json!({ "_id": "a", "count": 1 });
+        assert_eq!(outbox.save(&mut doc).await.unwrap(), Delivery::Queued);
+        assert_eq!(outbox.delete("b", "1-b").await.unwrap(), Delivery::Queued);
+        assert_eq!(outbox.len().await, 2);
+
+        let report = outbox.flush().await;
+        assert!(report.written.is_empty());
+        assert_eq!(report.remaining, 2);
+        let pending = outbox.pending().await;
+        assert_eq!(pending[0].id(), "a");
+        assert_eq!(pending[1].id(), "b");
+    }
+}
@@ -0,0 +1,119 @@
+use crate::database::Database;
+use crate::document::{DocumentCollection, TypedCouchDocument};
+use crate::error::{CouchError, CouchResult};
+use crate::types::find::{FindQuery, FindResult};
+use crate::types::query::{QueryParams, ViewQuery};
+use crate::types::system::PartitionInfo;
+use crate::types::view::ViewCollection;
+use serde::de::DeserializeOwned;
+
+/// Operations on a single partition of a partitioned database. Queries on a partition only read
+/// the shard that holds the partition, which makes them much cheaper than global queries.
+/// See [partitioned databases](https://docs.couchdb.org/en/stable/partitioned-dbs/index.html)
+/// for details.
+///
+/// Usage:
+/// ```
+/// use couch_rs::error::CouchResult;
+/// use couch_rs::types::find::SelectorBuilder;
+/// use couch_rs::types::system::CreateDatabaseOptions;
+/// use serde_json::{json, Value};
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::new_local_test()?;
+///     let db = client
+///         .db_with_options("sensor_readings", CreateDatabaseOptions::default().partitioned(true))
+///         .await?;
+///     db.create(&mut json!({"_id": "sensor-1:reading-1", "temperature": 21})).await?;
+///
+///     let partition = db.partition("sensor-1");
+///     let all = partition.all_docs::<Value>(None).await?;
+///     let warm = partition
+///         .find::<Value>(&SelectorBuilder::new().gt("temperature", 20).into_query())
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Partition {
+    db: Database,
+    key: String,
+}
+
+impl Partition {
+    pub fn new(db: Database, key: &str) -> Self {
+        Partition {
+            db,
+            key: key.to_string(),
+        }
+    }
+
+    /// The partition key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Gets information about this partition, see [Database::partition_info]
+    pub async fn info(&self) -> CouchResult<PartitionInfo> {
+        self.db.partition_info(&self.key).await
+    }
+
+    fn create_raw_path(&self, path: &str) -> String {
+        let key = &self.key;
+        let encoded_key = url_encode!(key);
+        format!("{}/_partition/{}/{}", self.db.name(), encoded_key, path)
+    }
+
+    /// Gets all the documents in this partition, with applied parameters
+    pub async fn all_docs<T: TypedCouchDocument>(
+        &self,
+        params: Option<QueryParams>,
+    ) -> CouchResult<DocumentCollection<T>> {
+        let mut options = params.unwrap_or_default();
+        options.include_docs = Some(true);
+
+        let response = self
+            .db
+            .client()
+            .post(&self.create_raw_path("_all_docs"), js!(&options))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(DocumentCollection::new(response.json().await?))
+    }
+
+    /// Finds documents in this partition through a Mango query
+    pub async fn find<T: TypedCouchDocument>(&self, query: &FindQuery) -> CouchResult<DocumentCollection<T>> {
+        let response = self
+            .db
+            .client()
+            .post(&self.create_raw_path("_find"), js!(query))
+            .send()
+            .await?;
+        let status = response.status();
+        let data: FindResult<T> = response.json().await?;
+        Database::find_collection(status, data)
+    }
+
+    /// Queries a view, limited to the rows emitted for the documents in this partition. The view
+    /// must be defined in a partitioned design document.
+    pub async fn query_view<K: DeserializeOwned, V: DeserializeOwned, T: TypedCouchDocument>(
+        &self,
+        design_name: &str,
+        view_name: &str,
+        query: &ViewQuery,
+    ) -> CouchResult<ViewCollection<K, V, T>> {
+        let path = format!("_design/{}/_view/{}", url_encode!(design_name), url_encode!(view_name));
+        self.db
+            .client()
+            .post(&self.create_raw_path(&path), js!(query))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .map_err(CouchError::from)
+    }
+}
@@ -0,0 +1,309 @@
+use crate::error::{CouchError, CouchResult};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// An operation of a [JSON Patch](https://datatracker.ietf.org/doc/html/rfc6902). Paths are
+/// JSON Pointers, e.g. `/address/city` or `/tags/0` (`/tags/-` to append to an array).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    /// Adds a member to an object, or inserts an element in an array.
+    Add { path: String, value: Value },
+    /// Removes an existing value.
+    Remove { path: String },
+    /// Replaces an existing value.
+    Replace { path: String, value: Value },
+    /// Removes the value at `from` and adds it at `path`.
+    Move { from: String, path: String },
+    /// Adds a copy of the value at `from` at `path`.
+    Copy { from: String, path: String },
+    /// Checks that the value at `path` equals `value`, failing the whole patch otherwise.
+    Test { path: String, value: Value },
+}
+
+/// Applies the operations of a [JSON Patch](https://datatracker.ietf.org/doc/html/rfc6902) to
+/// `target`, in order. The patch is atomic: when an operation fails, `target` is left unchanged
+/// and an error is returned, with status 412 (Precondition Failed) for a failed `test` and 400
+/// (Bad Request) for an operation that cannot be applied, e.g. because its path does not exist.
+/// ```
+/// use couch_rs::patch::{apply_patch, PatchOperation};
+/// use serde_json::json;
+///
+/// let mut doc = json!({ "name": "John", "tags": ["a"] });
+/// let ops: Vec<PatchOperation> = serde_json::from_value(json!([
+///     { "op": "test", "path": "/name", "value": "John" },
+///     { "op": "add", "path": "/tags/-", "value": "b" }
+/// ])).unwrap();
+/// apply_patch(&mut doc, &ops).unwrap();
+/// assert_eq!(doc, json!({ "name": "John", "tags": ["a", "b"] }));
+/// ```
+pub fn apply_patch(target: &mut Value, ops: &[PatchOperation]) -> CouchResult<()> {
+    let mut patched = target.clone();
+    for op in ops {
+        apply_operation(&mut patched, op)?;
+    }
+    *target = patched;
+    Ok(())
+}
+
+fn apply_operation(target: &mut Value, op: &PatchOperation) -> CouchResult<()> {
+    match op {
+        PatchOperation::Add { path, value } => add(target, path, value.clone()),
+        PatchOperation::Remove { path } => remove(target, path).map(|_| ()),
+        PatchOperation::Replace { path, value } => {
+            *lookup(target, path)? = value.clone();
+            Ok(())
+        }
+        PatchOperation::Move { from, path } => {
+            if path.starts_with(&format!("{}/", from)) {
+                return Err(invalid(format!("cannot move {} into one of its children", from)));
+            }
+            let value = remove(target, from)?;
+            add(target, path, value)
+        }
+        PatchOperation::Copy { from, path } => {
+            let value = lookup(target, from)?.clone();
+            add(target, path, value)
+        }
+        PatchOperation::Test { path, value } => {
+            if lookup(target, path)? == value {
+                Ok(())
+            } else {
+                Err(CouchError::new(
+                    format!("test failed: {} is not {}", path, value),
+                    StatusCode::PRECONDITION_FAILED,
+                ))
+            }
+        }
+    }
+}
+
+fn invalid(message: String) -> CouchError {
+    CouchError::new(message, StatusCode::BAD_REQUEST)
+}
+
+fn lookup<'a>(target: &'a mut Value, path: &str) -> CouchResult<&'a mut Value> {
+    target
+        .pointer_mut(path)
+        .ok_or_else(|| invalid(format!("path {} does not exist", path)))
+}
+
+/// Splits a JSON Pointer in the pointer to the parent, and the (unescaped) last token
+fn split_path(path: &str) -> CouchResult<(&str, String)> {
+    match path.rfind('/') {
+        Some(index) => Ok((&path[..index], path[index + 1..].replace("~1", "/").replace("~0", "~"))),
+        None => Err(invalid(format!("invalid path {}", path))),
+    }
+}
+
+fn array_index(token: &str, len: usize) -> CouchResult<usize> {
+    match token.parse::<usize>() {
+        Ok(index) if index <= len && (token == "0" || !token.starts_with('0')) => Ok(index),
+        _ => Err(invalid(format!("invalid array index {}", token))),
+    }
+}
+
+fn add(target: &mut Value, path: &str, value: Value) -> CouchResult<()> {
+    if path.is_empty() {
+        *target = value;
+        return Ok(());
+    }
+    let (parent, token) = split_path(path)?;
+    match lookup(target, parent)? {
+        Value::Object(members) => {
+            members.insert(token, value);
+            Ok(())
+        }
+        Value::Array(elements) => {
+            let index = if token == "-" {
+                elements.len()
+            } else {
+                array_index(&token, elements.len())?
+            };
+            elements.insert(index, value);
+            Ok(())
+        }
+        _ => Err(invalid(format!("path {} does not exist", path))),
+    }
+}
+
+fn remove(target: &mut Value, path: &str) -> CouchResult<Value> {
+    let (parent, token) = split_path(path)?;
+    let removed = match lookup(target, parent)? {
+        Value::Object(members) => members.remove(&token),
+        Value::Array(elements) => match array_index(&token, elements.len()) {
+            Ok(index) if index < elements.len() => Some(elements.remove(index)),
+            _ => None,
+        },
+        _ => None,
+    };
+    removed.ok_or_else(|| invalid(format!("path {} does not exist", path)))
+}
+
+/// Applies a [JSON Merge Patch](https://datatracker.ietf.org/doc/html/rfc7396) to `target`:
+/// the members of an object in the patch replace those of the target, recursively, and `null`
+/// members are removed from the target. A patch that is not an object replaces the target.
+/// ```
+/// use couch_rs::patch::merge_patch;
+/// use serde_json::json;
+///
+/// let mut doc = json!({ "name": "John", "address": { "city": "Paris", "zip": "75001" } });
+/// merge_patch(&mut doc, &json!({ "address": { "city": "Lyon", "zip": null } }));
+/// assert_eq!(doc, json!({ "name": "John", "address": { "city": "Lyon" } }));
+/// ```
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    match patch {
+        Value::Object(members) => {
+            if !target.is_object() {
+                *target = Value::Object(serde_json::Map::new());
+            }
+            if let Value::Object(target_members) = target {
+                for (key, value) in members {
+                    if value.is_null() {
+                        target_members.remove(key);
+                    } else {
+                        merge_patch(target_members.entry(key.clone()).or_insert(Value::Null), value);
+                    }
+                }
+            }
+        }
+        _ => *target = patch.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_patch() {
+        // the examples of RFC 7396, appendix A
+        let cases = vec![
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "b"}), json!({"b": "c"}), json!({"a": "b", "b": "c"})),
+            (json!({"a": "b"}), json!({"a": null}), json!({})),
+            (json!({"a": "b", "b": "c"}), json!({"a": null}), json!({"b": "c"})),
+            (json!({"a": ["b"]}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "c"}), json!({"a": ["b"]}), json!({"a": ["b"]})),
+            (
+                json!({"a": {"b": "c"}}),
+                json!({"a": {"b": "d", "c": null}}),
+                json!({"a": {"b": "d"}}),
+            ),
+            (json!({"a": [{"b": "c"}]}), json!({"a": [1]}), json!({"a": [1]})),
+            (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+            (json!({"a": "b"}), json!(["c"]), json!(["c"])),
+            (json!({"a": "foo"}), json!(null), json!(null)),
+            (json!({"a": "foo"}), json!("bar"), json!("bar")),
+            (json!({"e": null}), json!({"a": 1}), json!({"e": null, "a": 1})),
+            (json!([1, 2]), json!({"a": "b", "c": null}), json!({"a": "b"})),
+            (json!({}), json!({"a": {"bb": {"ccc": null}}}), json!({"a": {"bb": {}}})),
+        ];
+        for (mut target, patch, expected) in cases {
+            merge_patch(&mut target, &patch);
+            assert_eq!(target, expected, "patch {}", patch);
+        }
+    }
+
+    fn patched(mut target: Value, ops: Value) -> CouchResult<Value> {
+        let ops: Vec<PatchOperation> = serde_json::from_value(ops).unwrap();
+        apply_patch(&mut target, &ops)?;
+        Ok(target)
+    }
+
+    #[test]
+    fn test_apply_patch() {
+        // examples of RFC 6902, appendix A
+        let cases = vec![
+            (
+                json!({"foo": "bar"}),
+                json!([{"op": "add", "path": "/baz", "value": "qux"}]),
+                json!({"baz": "qux", "foo": "bar"}),
+            ),
+            (
+                json!({"foo": ["bar", "baz"]}),
+                json!([{"op": "add", "path": "/foo/1", "value": "qux"}]),
+                json!({"foo": ["bar", "qux", "baz"]}),
+            ),
+            (
+                json!({"baz": "qux", "foo": "bar"}),
+                json!([{"op": "remove", "path": "/baz"}]),
+                json!({"foo": "bar"}),
+            ),
+            (
+                json!({"foo": ["bar", "qux", "baz"]}),
+                json!([{"op": "remove", "path": "/foo/1"}]),
+                json!({"foo": ["bar", "baz"]}),
+            ),
+            (
+                json!({"baz": "qux", "foo": "bar"}),
+                json!([{"op": "replace", "path": "/baz", "value": "boo"}]),
+                json!({"baz": "boo", "foo": "bar"}),
+            ),
+            (
+                json!({"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}),
+                json!([{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]),
+                json!({"foo": {"bar": "baz"}, "qux": {"corge": "grault", "thud": "fred"}}),
+            ),
+            (
+                json!({"foo": ["all", "grass", "cows", "eat"]}),
+                json!([{"op": "move", "from": "/foo/1", "path": "/foo/3"}]),
+                json!({"foo": ["all", "cows", "eat", "grass"]}),
+            ),
+            (
+                json!({"foo": ["bar"]}),
+                json!([{"op": "add", "path": "/foo/-", "value": ["abc", "def"]}]),
+                json!({"foo": ["bar", ["abc", "def"]]}),
+            ),
+            (
+                json!({"a/b": 1, "m~n": 2}),
+                json!([
+                    {"op": "copy", "from": "/a~1b", "path": "/c"},
+                    {"op": "test", "path": "/m~0n", "value": 2}
+                ]),
+                json!({"a/b": 1, "m~n": 2, "c": 1}),
+            ),
+        ];
+        for (target, ops, expected) in cases {
+            assert_eq!(patched(target, ops.clone()).unwrap(), expected, "patch {}", ops);
+        }
+    }
+
+    #[test]
+    fn test_apply_patch_errors() {
+        let doc = json!({"foo": "bar", "list": [1]});
+        let failed_test = patched(
+            doc.clone(),
+            json!([
+                {"op": "add", "path": "/baz", "value": "qux"},
+                {"op": "test", "path": "/foo", "value": "qux"}
+            ]),
+        );
+        assert_eq!(failed_test.unwrap_err().status(), Some(StatusCode::PRECONDITION_FAILED));
+
+        let invalid_ops = vec![
+            json!([{"op": "add", "path": "/baz/bat", "value": "qux"}]),
+            json!([{"op": "remove", "path": "/baz"}]),
+            json!([{"op": "replace", "path": "/baz", "value": 1}]),
+            json!([{"op": "add", "path": "/list/2", "value": 2}]),
+            json!([{"op": "add", "path": "/list/01", "value": 2}]),
+            json!([{"op": "move", "from": "/list", "path": "/list/0"}]),
+            json!([{"op": "add", "path": "foo", "value": 2}]),
+        ];
+        for ops in invalid_ops {
+            let err = patched(doc.clone(), ops.clone()).unwrap_err();
+            assert_eq!(err.status(), Some(StatusCode::BAD_REQUEST), "patch {}", ops);
+        }
+
+        // patches are atomic
+        let mut target = doc.clone();
+        let ops = vec![
+            PatchOperation::Remove { path: s!("/foo") },
+            PatchOperation::Remove { path: s!("/missing") },
+        ];
+        assert!(apply_patch(&mut target, &ops).is_err());
+        assert_eq!(target, doc);
+    }
+}
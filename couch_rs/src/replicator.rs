@@ -0,0 +1,84 @@
+use crate::client::Client;
+use crate::database::Database;
+use crate::document::DocumentCollection;
+use crate::error::CouchResult;
+use crate::types::document::DocumentCreatedResult;
+use crate::types::replication::ReplicationDocument;
+
+/// The name of the CouchDB database that holds the persistent replications
+const REPLICATOR_DB: &str = "_replicator";
+
+/// Manages persistent replications, by creating, updating and deleting documents in the
+/// `_replicator` database. CouchDB starts a replication when its document is created, and stops
+/// it when the document is deleted. Use [Client::scheduler_docs](crate::Client::scheduler_docs)
+/// to monitor the state of the replications.
+///
+/// Usage:
+/// ```
+/// use couch_rs::error::CouchResult;
+/// use couch_rs::types::replication::{ReplicateOptions, ReplicationDocument, ReplicationEndpoint};
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::new_local_test()?;
+///     let replicator = client.replicator();
+///
+///     let source = ReplicationEndpoint::new("http://localhost:5984/source_db").basic_auth("admin", "password");
+///     let target = ReplicationEndpoint::new("https://backup.example.com/source_db").basic_auth("backup", "secret");
+///     let mut doc = ReplicationDocument::new(
+///         "backup_source_db",
+///         source,
+///         target,
+///         ReplicateOptions::default().continuous(true).create_target(true),
+///     );
+///
+///     replicator.create(&mut doc).await?;
+///
+///     // stop the replication again
+///     replicator.delete(&doc).await;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Replicator {
+    db: Database,
+}
+
+impl Replicator {
+    pub fn new(client: &Client) -> Self {
+        // the _replicator database is never prefixed
+        Replicator {
+            db: Database::new(REPLICATOR_DB.to_string(), client.clone()),
+        }
+    }
+
+    /// Creates a persistent replication. When the document has no `_id`, CouchDB generates one.
+    pub async fn create(&self, doc: &mut ReplicationDocument) -> DocumentCreatedResult {
+        if doc._id.is_empty() {
+            self.db.create(doc).await
+        } else {
+            self.db.save(doc).await
+        }
+    }
+
+    /// Gets the replication document with the given id
+    pub async fn get(&self, id: &str) -> CouchResult<ReplicationDocument> {
+        self.db.get(id).await
+    }
+
+    /// Lists all replication documents
+    pub async fn list(&self) -> CouchResult<DocumentCollection<ReplicationDocument>> {
+        self.db.get_all().await
+    }
+
+    /// Updates a replication document; CouchDB restarts the replication with the new settings.
+    /// The document must include its current `_rev`.
+    pub async fn update(&self, doc: &mut ReplicationDocument) -> DocumentCreatedResult {
+        self.db.save(doc).await
+    }
+
+    /// Deletes a replication document, which cancels the replication. Returns success in a `bool`
+    pub async fn delete(&self, doc: &ReplicationDocument) -> bool {
+        self.db.remove(doc).await
+    }
+}
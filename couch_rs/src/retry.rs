@@ -0,0 +1,265 @@
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, Response, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// When and how often a `Client` retries failed requests, with exponential backoff. By default,
+/// only reads (`GET` and `HEAD` requests) are retried, when they fail with a connection error, a
+/// timeout, or one of the retryable status codes (429, 500, 502, 503 and 504). Queries that are
+/// sent as `POST`, like `_find`, are retried only when writes are retried.
+///
+/// When a 429 (Too Many Requests) or 503 (Service Unavailable) response has a `Retry-After`
+/// header, the request is retried after the delay the server asks for instead of the backoff, or
+/// not at all when that delay exceeds `max_retry_after`.
+///
+/// Usage:
+/// ```
+/// use couch_rs::error::CouchResult;
+/// use couch_rs::retry::RetryPolicy;
+/// use std::time::Duration;
+///
+/// fn main() -> CouchResult<()> {
+///     let policy = RetryPolicy::default()
+///         .max_attempts(5)
+///         .backoff(Duration::from_millis(200), Duration::from_secs(10));
+///     let client = couch_rs::Client::builder("http://localhost:5984").retry_policy(policy).build()?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts of a request, including the first one
+    pub max_attempts: u32,
+    /// The delay before the first retry, which doubles for every next retry
+    pub initial_backoff: Duration,
+    /// The maximum delay between two attempts
+    pub max_backoff: Duration,
+    /// Whether to wait a random part (up to half) less than the backoff, so that clients that
+    /// failed at the same time do not retry at the same time
+    pub jitter: bool,
+    /// The status codes of responses that are retried
+    pub retry_statuses: Vec<StatusCode>,
+    /// Whether to retry requests that failed to connect, or timed out
+    pub retry_transport_errors: bool,
+    /// The longest `Retry-After` delay to wait for
+    pub max_retry_after: Duration,
+    /// Whether to retry `POST`, `PUT` and `DELETE` requests too. Only enable this when your
+    /// writes are idempotent, e.g. because documents have their own ids, as a write that timed
+    /// out may have been applied anyway.
+    pub retry_writes: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            jitter: true,
+            retry_statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            retry_transport_errors: true,
+            max_retry_after: Duration::from_secs(60),
+            retry_writes: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn retry_statuses(mut self, statuses: Vec<StatusCode>) -> Self {
+        self.retry_statuses = statuses;
+        self
+    }
+
+    pub fn retry_transport_errors(mut self, retry: bool) -> Self {
+        self.retry_transport_errors = retry;
+        self
+    }
+
+    pub fn max_retry_after(mut self, max: Duration) -> Self {
+        self.max_retry_after = max;
+        self
+    }
+
+    pub fn retry_writes(mut self, retry: bool) -> Self {
+        self.retry_writes = retry;
+        self
+    }
+
+    /// Whether requests with this method are retried
+    pub(crate) fn retries_method(&self, method: &Method) -> bool {
+        matches!(*method, Method::GET | Method::HEAD) || self.retry_writes
+    }
+
+    /// Whether the outcome of an attempt is worth retrying
+    pub(crate) fn retries_outcome(&self, outcome: &reqwest::Result<Response>) -> bool {
+        match outcome {
+            Ok(response) => self.retry_statuses.contains(&response.status()),
+            Err(err) => self.retry_transport_errors && (err.is_connect() || err.is_timeout()),
+        }
+    }
+
+    /// How long to wait before retrying after the given (failed) attempt, starting at 1, if the
+    /// outcome is worth retrying at all
+    pub(crate) fn delay_after(&self, attempt: u32, outcome: &reqwest::Result<Response>) -> Option<Duration> {
+        if !self.retries_outcome(outcome) {
+            return None;
+        }
+        match outcome {
+            Ok(response)
+                if matches!(
+                    response.status(),
+                    StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+                ) =>
+            {
+                match retry_after(response.headers(), SystemTime::now()) {
+                    Some(delay) if delay > self.max_retry_after => None,
+                    Some(delay) => Some(delay),
+                    None => Some(self.backoff_after(attempt)),
+                }
+            }
+            _ => Some(self.backoff_after(attempt)),
+        }
+    }
+
+    /// The delay after the given (failed) attempt, starting at 1
+    pub(crate) fn backoff_after(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let backoff = self.initial_backoff.saturating_mul(factor).min(self.max_backoff);
+        if self.jitter {
+            let random = RandomState::new().build_hasher().finish();
+            backoff.mul_f64(1.0 - (random % 1000) as f64 / 2000.0)
+        } else {
+            backoff
+        }
+    }
+}
+
+/// The delay of a `Retry-After` header, in seconds or as an HTTP date
+pub(crate) fn retry_after(headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        // a date in the past means: retry now
+        Err(_) => parse_http_date(value).map(|date| date.duration_since(now).unwrap_or_default()),
+    }
+}
+
+/// Parses an HTTP date in the preferred format, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    if parts.len() != 6 || parts[5] != "GMT" {
+        return None;
+    }
+    let day: u64 = parts[1].parse().ok()?;
+    let month = MONTHS.iter().position(|month| *month == parts[2])? as u64 + 1;
+    let year: u64 = parts[3].parse().ok()?;
+    let time: Vec<u64> = parts[4]
+        .split(':')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    if time.len() != 3 || year < 1970 || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // days since the epoch, see http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year / 400;
+    let day_of_era =
+        (year - era * 400) * 365 + (year - era * 400) / 4 - (year - era * 400) / 100 + (153 * month + 2) / 5 + day - 1;
+    let days = (era * 146_097 + day_of_era).checked_sub(719_468)?;
+
+    let seconds = days * 86_400 + time[0] * 3_600 + time[1] * 60 + time[2];
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default()
+            .backoff(Duration::from_millis(100), Duration::from_millis(500))
+            .jitter(false);
+        assert_eq!(policy.backoff_after(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff_after(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff_after(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff_after(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff_after(100), Duration::from_millis(500));
+
+        let policy = policy.jitter(true);
+        for _ in 0..100 {
+            let backoff = policy.backoff_after(2);
+            assert!(backoff > Duration::from_millis(100) && backoff <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let now = UNIX_EPOCH + Duration::from_secs(784_111_757);
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers, now), None);
+
+        headers.insert(RETRY_AFTER, "120".parse().unwrap());
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(120)));
+
+        headers.insert(RETRY_AFTER, "Sun, 06 Nov 1994 08:49:37 GMT".parse().unwrap());
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(20)));
+        let later = now + Duration::from_secs(60);
+        assert_eq!(retry_after(&headers, later), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(retry_after(&headers, now), None);
+    }
+
+    #[test]
+    fn test_parse_http_date() {
+        let date = parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT").unwrap();
+        assert_eq!(date, UNIX_EPOCH);
+        let date = parse_http_date("Tue, 29 Feb 2000 12:30:15 GMT").unwrap();
+        assert_eq!(date, UNIX_EPOCH + Duration::from_secs(951_827_415));
+        assert!(parse_http_date("Tue, 29 Feb 2000 12:30:15 CET").is_none());
+        assert!(parse_http_date("Tue, 29 Foo 2000 12:30:15 GMT").is_none());
+    }
+
+    #[test]
+    fn test_retries_method() {
+        let policy = RetryPolicy::default();
+        assert!(policy.retries_method(&Method::GET));
+        assert!(policy.retries_method(&Method::HEAD));
+        assert!(!policy.retries_method(&Method::POST));
+        assert!(!policy.retries_method(&Method::PUT));
+        assert!(policy.retry_writes(true).retries_method(&Method::DELETE));
+    }
+}
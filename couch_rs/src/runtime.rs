@@ -0,0 +1,107 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A future that is boxed, to pass it to a `Runtime`
+pub type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// The timers and background tasks of a `Client`: the delays between retries, the rate limit of
+/// `max_requests_per_second`, the polling of `wait_until_available`, the reconnects of the
+/// changes feeds, and the renewal of a session. The default is `Tokio`; set another runtime with
+/// `ClientBuilder::runtime`, e.g. for async-std or smol.
+///
+/// The HTTP requests themselves are sent by reqwest, which needs a tokio reactor, e.g. the
+/// `tokio1` feature of async-std, or `async-compat` for smol.
+///
+/// Usage, for async-std it would call `async_std::task::sleep` and `async_std::task::spawn`:
+/// ```
+/// use couch_rs::error::CouchResult;
+/// use couch_rs::runtime::{BoxFuture, Runtime};
+/// use std::time::Duration;
+/// use tokio::runtime::Handle;
+///
+/// // runs the background tasks on a dedicated runtime
+/// struct Dedicated(Handle);
+///
+/// impl Runtime for Dedicated {
+///     fn sleep(&self, duration: Duration) -> BoxFuture {
+///         Box::pin(tokio::time::sleep(duration))
+///     }
+///
+///     fn spawn(&self, task: BoxFuture) {
+///         self.0.spawn(task);
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::builder("http://localhost:5984")
+///         .runtime(Dedicated(Handle::current()))
+///         .build()?;
+///     Ok(())
+/// }
+/// ```
+pub trait Runtime: Send + Sync {
+    /// A future that completes after the duration
+    fn sleep(&self, duration: Duration) -> BoxFuture;
+
+    /// Runs a task in the background
+    fn spawn(&self, task: BoxFuture);
+}
+
+/// The tokio runtime, which must be running when the client is used
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tokio;
+
+impl Runtime for Tokio {
+    fn sleep(&self, duration: Duration) -> BoxFuture {
+        Box::pin(tokio::time::sleep(duration))
+    }
+
+    fn spawn(&self, task: BoxFuture) {
+        tokio::spawn(task);
+    }
+}
+
+/// The runtime of a `Client`, shared by its clones
+#[derive(Clone)]
+pub(crate) struct SharedRuntime(pub(crate) Arc<dyn Runtime>);
+
+impl Default for SharedRuntime {
+    fn default() -> Self {
+        SharedRuntime(Arc::new(Tokio))
+    }
+}
+
+impl fmt::Debug for SharedRuntime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedRuntime")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_tokio() {
+        let runtime = SharedRuntime::default();
+        let started = Instant::now();
+        runtime.0.sleep(Duration::from_millis(20)).await;
+        assert!(started.elapsed() >= Duration::from_millis(20));
+
+        let counter = Arc::new(AtomicUsize::new(0));
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let task_counter = counter.clone();
+        runtime.0.spawn(Box::pin(async move {
+            task_counter.fetch_add(1, Ordering::SeqCst);
+            let _ = sender.send(());
+        }));
+        receiver.await.unwrap();
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+}
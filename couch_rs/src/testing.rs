@@ -0,0 +1,699 @@
+use crate::document::{from_typed_value, DocumentCollection, Typed, TypedCouchDocument};
+use crate::error::{CouchError, CouchResult};
+use crate::ids::{IdGenerator, UuidV7};
+use crate::ops::CouchOps;
+use crate::types::document::{DocumentCreatedDetails, DocumentCreatedResult, DocumentId};
+use crate::types::find::{FindQuery, SortDirection, SortSpec};
+use crate::types::query::QueryParams;
+use crate::types::view::ViewCollection;
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// A revision of a document, as stored by an `InMemoryDatabase`
+#[derive(Debug, Clone)]
+struct Stored {
+    rev: String,
+    /// The document, including its `_id` and `_rev`
+    doc: Value,
+    deleted: bool,
+}
+
+/// A fake database, that keeps the documents in memory, for unit tests of code that depends on
+/// [CouchOps] instead of `Database`. Clones share their documents, like clones of a `Database`.
+///
+/// It checks the revisions of writes like CouchDB does, so updating a document with a stale
+/// `_rev` fails with `CouchError::Conflict`, and reading a document that doesn't exist with
+/// `CouchError::NotFound`. `find` supports the common selector operators (`$eq`, `$ne`, `$gt`,
+/// `$gte`, `$lt`, `$lte`, `$in`, `$nin`, `$exists`, `$type`, `$size`, `$all`, `$elemMatch`,
+/// `$allMatch`, `$mod`, `$and`, `$or`, `$nor` and `$not`), `sort`, `skip`, `limit` and `fields`.
+/// Strings are compared by their bytes, not with the Unicode collation of CouchDB. Views are not
+/// supported, and only the winning revision of a document is kept, so there are no conflicts.
+///
+/// Usage:
+/// ```
+/// use couch_rs::error::CouchResult;
+/// use couch_rs::ops::CouchOps;
+/// use couch_rs::testing::InMemoryDatabase;
+/// use couch_rs::types::find::FindQuery;
+/// use serde_json::{json, Value};
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let db = InMemoryDatabase::new("test_db");
+///     db.save(&mut json!({"_id": "john", "age": 42})).await?;
+///     db.save(&mut json!({"_id": "jane", "age": 37})).await?;
+///
+///     let adults = db.find::<Value>(&FindQuery::new(json!({"age": {"$gt": 40}}))).await?;
+///     assert_eq!(adults.rows[0]["_id"], "john");
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct InMemoryDatabase {
+    name: String,
+    docs: Arc<Mutex<BTreeMap<String, Stored>>>,
+}
+
+impl InMemoryDatabase {
+    pub fn new(name: &str) -> Self {
+        InMemoryDatabase {
+            name: name.to_string(),
+            docs: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// Gets the documents in the order of their ids, like `Database::get_all_params`. Supports
+    /// `keys`, `start_key`, `end_key`, `inclusive_end`, `descending`, `skip` and `limit`.
+    pub fn get_all_params<T: TypedCouchDocument>(
+        &self,
+        params: Option<QueryParams>,
+    ) -> CouchResult<DocumentCollection<T>> {
+        let params = params.unwrap_or_default();
+        let docs = self.docs.lock().unwrap();
+        let mut found: Vec<&Stored> = match params.keys.is_empty() {
+            true => docs.values().collect(),
+            false => params.keys.iter().filter_map(|key| docs.get(key)).collect(),
+        };
+        found.retain(|stored| !stored.deleted && !stored.doc.get_id().starts_with('_'));
+        if params.keys.is_empty() {
+            if params.descending == Some(true) {
+                found.reverse();
+            }
+            let id_of = |stored: &Stored| stored.doc.get_id().into_owned();
+            let before = |a: &str, b: &str| match params.descending == Some(true) {
+                true => a > b,
+                false => a < b,
+            };
+            if let Some(start_key) = &params.start_key {
+                found.retain(|stored| !before(&id_of(stored), start_key));
+            }
+            if let Some(end_key) = &params.end_key {
+                let inclusive = params.inclusive_end != Some(false);
+                found.retain(|stored| {
+                    let id = id_of(stored);
+                    before(&id, end_key) || (inclusive && &id == end_key)
+                });
+            }
+        }
+        let rows = page(found, params.skip, params.limit)
+            .into_iter()
+            .map(|stored| from_typed_value(&stored.doc))
+            .collect::<CouchResult<Vec<T>>>()?;
+        Ok(DocumentCollection::new_from_documents(rows, None))
+    }
+
+    /// Writes a document, checking its revision
+    fn write(&self, id: &str, rev: &str, mut doc: Value) -> CouchResult<DocumentCreatedDetails> {
+        let mut docs = self.docs.lock().unwrap();
+        let current = docs.get(id);
+        let conflict = match current {
+            Some(stored) if !stored.deleted => rev != stored.rev,
+            Some(stored) => !rev.is_empty() && rev != stored.rev,
+            None => !rev.is_empty(),
+        };
+        if conflict {
+            return Err(CouchError::write_failure(
+                Some(id.to_string()),
+                Some(s!("conflict")),
+                Some(s!("Document update conflict.")),
+                StatusCode::CONFLICT,
+            ));
+        }
+
+        let generation = current.map_or(0, |stored| rev_generation(&stored.rev)) + 1;
+        let rev = format!(
+            "{}-{}",
+            generation,
+            rev_hash(&doc, current.map(|stored| stored.rev.as_str()))
+        );
+        let deleted = doc.get("_deleted") == Some(&json!(true));
+        doc.set_id(id);
+        doc.set_rev(&rev);
+        docs.insert(
+            id.to_string(),
+            Stored {
+                rev: rev.clone(),
+                doc,
+                deleted,
+            },
+        );
+        Ok(DocumentCreatedDetails {
+            id: id.to_string(),
+            rev,
+        })
+    }
+
+    async fn save_doc<T: TypedCouchDocument>(&self, doc: &mut T, create: bool) -> DocumentCreatedResult {
+        doc.set_timestamps(SystemTime::now());
+        if doc.get_id().is_empty() {
+            doc.set_id(&UuidV7.generate().await?);
+        }
+        let id = doc.get_id().into_owned();
+        let rev = match create {
+            // a new document can be created on top of a deleted one
+            true => match self.docs.lock().unwrap().get(&id) {
+                Some(stored) if stored.deleted => stored.rev.clone(),
+                _ => String::new(),
+            },
+            false => doc.get_rev().into_owned(),
+        };
+        let details = self.write(&id, &rev, serde_json::to_value(Typed(&*doc))?)?;
+        doc.set_rev(&details.rev);
+        Ok(details)
+    }
+}
+
+#[async_trait]
+impl CouchOps for InMemoryDatabase {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn exists(&self, id: &str) -> bool {
+        let docs = self.docs.lock().unwrap();
+        docs.get(id).is_some_and(|stored| !stored.deleted)
+    }
+
+    async fn get<T: TypedCouchDocument + Send>(&self, id: &str) -> CouchResult<T> {
+        let docs = self.docs.lock().unwrap();
+        match docs.get(id) {
+            Some(stored) if !stored.deleted => from_typed_value(&stored.doc),
+            Some(_) => Err(CouchError::new_with_id(
+                Some(s!(id)),
+                s!("deleted"),
+                StatusCode::NOT_FOUND,
+            )),
+            None => Err(CouchError::new_with_id(
+                Some(s!(id)),
+                s!("missing"),
+                StatusCode::NOT_FOUND,
+            )),
+        }
+    }
+
+    async fn get_bulk<T: TypedCouchDocument + Send>(&self, ids: Vec<DocumentId>) -> CouchResult<DocumentCollection<T>> {
+        self.get_all_params(Some(QueryParams::from_keys(ids)))
+    }
+
+    async fn get_all<T: TypedCouchDocument + Send>(&self) -> CouchResult<DocumentCollection<T>> {
+        self.get_all_params(None)
+    }
+
+    async fn find<T: TypedCouchDocument + Send>(&self, query: &FindQuery) -> CouchResult<DocumentCollection<T>> {
+        let docs = self.docs.lock().unwrap();
+        let mut found = vec![];
+        for stored in docs.values() {
+            if !stored.deleted && !stored.doc.get_id().starts_with('_') && matches(&stored.doc, &query.selector)? {
+                found.push(&stored.doc);
+            }
+        }
+        let sort = sort_fields(&query.sort);
+        found.sort_by(|a, b| {
+            sort.iter()
+                .map(|(field, direction)| {
+                    let ordering = collate(field_value(a, field), field_value(b, field));
+                    match direction {
+                        SortDirection::Asc => ordering,
+                        SortDirection::Desc => ordering.reverse(),
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        let rows = page(found, query.skip, query.limit.or(Some(25)))
+            .into_iter()
+            .map(|doc| match &query.fields {
+                Some(fields) => from_typed_value(&project(doc, fields)),
+                None => from_typed_value(doc),
+            })
+            .collect::<CouchResult<Vec<T>>>()?;
+        Ok(DocumentCollection::new_from_documents(rows, None))
+    }
+
+    async fn save<T: TypedCouchDocument + Send + Sync>(&self, doc: &mut T) -> DocumentCreatedResult {
+        self.save_doc(doc, false).await
+    }
+
+    async fn create<T: TypedCouchDocument + Send + Sync>(&self, doc: &mut T) -> DocumentCreatedResult {
+        self.save_doc(doc, true).await
+    }
+
+    async fn upsert<T: TypedCouchDocument + Send + Sync>(&self, doc: &mut T) -> DocumentCreatedResult {
+        let current = self.docs.lock().unwrap().get(doc.get_id().as_ref()).cloned();
+        match current {
+            Some(stored) if !stored.deleted => doc.set_rev(&stored.rev),
+            _ => doc.set_rev(""),
+        }
+        self.save_doc(doc, false).await
+    }
+
+    async fn bulk_docs<T: TypedCouchDocument + Send + Sync>(
+        &self,
+        docs: &mut [T],
+    ) -> CouchResult<Vec<DocumentCreatedResult>> {
+        let mut results = Vec::with_capacity(docs.len());
+        for doc in docs.iter_mut() {
+            results.push(self.save_doc(doc, false).await);
+        }
+        Ok(results)
+    }
+
+    async fn remove<T: TypedCouchDocument + Send + Sync>(&self, doc: &T) -> bool {
+        let id = doc.get_id();
+        let deleted = json!({ "_id": id, "_deleted": true });
+        self.write(&id, &doc.get_rev(), deleted).is_ok()
+    }
+
+    async fn query<K, V, T>(
+        &self,
+        _design_name: &str,
+        _view_name: &str,
+        _options: Option<QueryParams>,
+    ) -> CouchResult<ViewCollection<K, V, T>>
+    where
+        K: DeserializeOwned + Send,
+        V: DeserializeOwned + Send,
+        T: TypedCouchDocument + Send,
+    {
+        Err(CouchError::new(
+            s!("views are not supported by the in-memory database"),
+            StatusCode::NOT_IMPLEMENTED,
+        ))
+    }
+}
+
+/// The generation of a revision, e.g. 2 for `2-7051cbe5c8faecd085a3fa619e6e6337`
+fn rev_generation(rev: &str) -> u64 {
+    rev.split('-').next().and_then(|n| n.parse().ok()).unwrap_or(0)
+}
+
+/// A hash of a revision, of its document and the previous revision
+fn rev_hash(doc: &Value, previous: Option<&str>) -> String {
+    let mut hashes = [0u64; 2];
+    for (seed, hash) in hashes.iter_mut().enumerate() {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        previous.hash(&mut hasher);
+        doc.to_string().hash(&mut hasher);
+        *hash = hasher.finish();
+    }
+    format!("{:016x}{:016x}", hashes[0], hashes[1])
+}
+
+fn page<T>(items: Vec<T>, skip: Option<u64>, limit: Option<u64>) -> Vec<T> {
+    let skip = skip.unwrap_or(0) as usize;
+    let limit = limit.map_or(usize::MAX, |limit| limit as usize);
+    items.into_iter().skip(skip).take(limit).collect()
+}
+
+fn sort_fields(sort: &[SortSpec]) -> Vec<(String, SortDirection)> {
+    sort.iter()
+        .flat_map(|spec| match spec {
+            SortSpec::Simple(field) => vec![(field.clone(), SortDirection::Asc)],
+            SortSpec::Complex(fields) => fields
+                .iter()
+                .map(|(field, direction)| (field.clone(), direction.clone()))
+                .collect(),
+        })
+        .collect()
+}
+
+/// The value of a field, where the names of nested fields are separated by dots
+fn field_value<'a>(doc: &'a Value, field: &str) -> Option<&'a Value> {
+    field.split('.').try_fold(doc, |value, name| value.get(name))
+}
+
+/// The document with only the given fields
+fn project(doc: &Value, fields: &[String]) -> Value {
+    let mut projected = json!({});
+    for field in fields {
+        if let Some(value) = field_value(doc, field) {
+            let mut target = &mut projected;
+            let mut names = field.split('.').peekable();
+            while let Some(name) = names.next() {
+                let object = target.as_object_mut().expect("projected fields are objects");
+                target = match names.peek() {
+                    Some(_) => object.entry(name).or_insert_with(|| json!({})),
+                    None => object.entry(name).or_insert_with(|| value.clone()),
+                };
+            }
+        }
+    }
+    projected
+}
+
+/// Orders values like CouchDB: null, booleans, numbers, strings, arrays and objects, and missing
+/// fields before all of those
+fn collate(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+    let (a, b) = match (a, b) {
+        (None, None) => return Ordering::Equal,
+        (None, Some(_)) => return Ordering::Less,
+        (Some(_), None) => return Ordering::Greater,
+        (Some(a), Some(b)) => (a, b),
+    };
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap_or_default(), b.as_f64().unwrap_or_default());
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(a), Value::Array(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| collate(Some(a), Some(b)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Object(a), Value::Object(b)) => a
+            .iter()
+            .zip(b)
+            .map(|((ka, va), (kb, vb))| ka.cmp(kb).then_with(|| collate(Some(va), Some(vb))))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (a, b) => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn unsupported(operator: &str) -> CouchError {
+    CouchError::new(
+        format!("unsupported operator {} in the in-memory database", operator),
+        StatusCode::BAD_REQUEST,
+    )
+}
+
+fn invalid(operator: &str, argument: &Value) -> CouchError {
+    CouchError::new(
+        format!("invalid argument for {}: {}", operator, argument),
+        StatusCode::BAD_REQUEST,
+    )
+}
+
+fn selectors<'a>(operator: &str, argument: &'a Value) -> CouchResult<&'a Vec<Value>> {
+    argument.as_array().ok_or_else(|| invalid(operator, argument))
+}
+
+/// Whether a document, or the value of a field, matches a Mango selector
+fn matches(value: &Value, selector: &Value) -> CouchResult<bool> {
+    let Some(selector) = selector.as_object() else {
+        return Ok(value == selector);
+    };
+    for (key, argument) in selector {
+        let matched = match key.as_str() {
+            "$and" => all(value, selectors(key, argument)?)?,
+            "$or" => any(value, selectors(key, argument)?)?,
+            "$nor" => !any(value, selectors(key, argument)?)?,
+            "$not" => !matches(value, argument)?,
+            operator if operator.starts_with('$') => return Err(unsupported(operator)),
+            field => matches_field(field_value(value, field), argument)?,
+        };
+        if !matched {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn all(value: &Value, selectors: &[Value]) -> CouchResult<bool> {
+    for selector in selectors {
+        if !matches(value, selector)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn any(value: &Value, selectors: &[Value]) -> CouchResult<bool> {
+    for selector in selectors {
+        if matches(value, selector)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Whether the value of a field matches a condition: a value, operators, or a nested selector
+fn matches_field(value: Option<&Value>, condition: &Value) -> CouchResult<bool> {
+    let operators = match condition.as_object() {
+        Some(object) if object.keys().all(|key| key.starts_with('$')) => object,
+        Some(_) => return value.map_or(Ok(false), |value| matches(value, condition)),
+        None => return Ok(value == Some(condition)),
+    };
+    for (operator, argument) in operators {
+        if !matches_operator(value, operator, argument)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn matches_operator(value: Option<&Value>, operator: &str, argument: &Value) -> CouchResult<bool> {
+    if operator == "$exists" {
+        return Ok(value.is_some() == argument.as_bool().ok_or_else(|| invalid(operator, argument))?);
+    }
+    if operator == "$not" {
+        return Ok(!matches_field(value, argument)?);
+    }
+    let Some(value) = value else {
+        // only $exists and $not match missing fields
+        return Ok(false);
+    };
+    let ordering = collate(Some(value), Some(argument));
+    let matched = match operator {
+        "$eq" => value == argument,
+        "$ne" => value != argument,
+        "$gt" => ordering.is_gt(),
+        "$gte" => ordering.is_ge(),
+        "$lt" => ordering.is_lt(),
+        "$lte" => ordering.is_le(),
+        "$in" => selectors(operator, argument)?.contains(value),
+        "$nin" => !selectors(operator, argument)?.contains(value),
+        "$type" => type_name(value) == argument.as_str().ok_or_else(|| invalid(operator, argument))?,
+        "$size" => value.as_array().map(Vec::len).map(|len| len as u64) == argument.as_u64(),
+        "$all" => {
+            let wanted = selectors(operator, argument)?;
+            value
+                .as_array()
+                .is_some_and(|values| wanted.iter().all(|w| values.contains(w)))
+        }
+        "$elemMatch" | "$allMatch" => {
+            let Some(values) = value.as_array() else {
+                return Ok(false);
+            };
+            let mut results = vec![];
+            for element in values {
+                results.push(matches_element(element, argument)?);
+            }
+            match operator {
+                "$elemMatch" => results.contains(&true),
+                _ => !results.is_empty() && !results.contains(&false),
+            }
+        }
+        "$mod" => match (value.as_i64(), argument.as_array().map(Vec::as_slice)) {
+            (Some(value), Some([divisor, remainder])) => match (divisor.as_i64(), remainder.as_i64()) {
+                (Some(divisor), Some(remainder)) if divisor != 0 => value % divisor == remainder,
+                _ => return Err(invalid(operator, argument)),
+            },
+            (None, Some([_, _])) => false,
+            _ => return Err(invalid(operator, argument)),
+        },
+        operator => return Err(unsupported(operator)),
+    };
+    Ok(matched)
+}
+
+/// Whether an element of an array matches the selector of `$elemMatch` or `$allMatch`, which
+/// applies to the element itself when it only holds operators
+fn matches_element(element: &Value, selector: &Value) -> CouchResult<bool> {
+    match selector.as_object() {
+        Some(object) if object.keys().all(|key| key.starts_with('$')) => matches_field(Some(element), selector),
+        _ => matches(element, selector),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selector_matches(doc: Value, selector: Value) -> bool {
+        matches(&doc, &selector).unwrap()
+    }
+
+    #[test]
+    fn test_selectors() {
+        let doc = json!({
+            "name": "john",
+            "age": 42,
+            "address": {"city": "Amsterdam"},
+            "tags": ["admin", "user"],
+            "scores": [3, 8],
+        });
+        assert!(selector_matches(doc.clone(), json!({"name": "john", "age": 42})));
+        assert!(!selector_matches(doc.clone(), json!({"name": "jane"})));
+        assert!(selector_matches(doc.clone(), json!({"age": {"$gt": 40, "$lte": 42}})));
+        assert!(!selector_matches(doc.clone(), json!({"age": {"$lt": 42}})));
+        assert!(selector_matches(doc.clone(), json!({"address.city": "Amsterdam"})));
+        assert!(selector_matches(doc.clone(), json!({"address": {"city": "Amsterdam"}})));
+        assert!(selector_matches(
+            doc.clone(),
+            json!({"name": {"$in": ["jane", "john"]}})
+        ));
+        assert!(selector_matches(doc.clone(), json!({"name": {"$nin": ["jane"]}})));
+        assert!(selector_matches(doc.clone(), json!({"email": {"$exists": false}})));
+        assert!(!selector_matches(doc.clone(), json!({"email": {"$ne": "x"}})));
+        assert!(selector_matches(
+            doc.clone(),
+            json!({"tags": {"$all": ["user"], "$size": 2}})
+        ));
+        assert!(selector_matches(
+            doc.clone(),
+            json!({"scores": {"$elemMatch": {"$gt": 5}}})
+        ));
+        assert!(!selector_matches(
+            doc.clone(),
+            json!({"scores": {"$allMatch": {"$gt": 5}}})
+        ));
+        assert!(selector_matches(
+            doc.clone(),
+            json!({"age": {"$mod": [10, 2]}, "name": {"$type": "string"}})
+        ));
+        assert!(selector_matches(
+            doc.clone(),
+            json!({"$or": [{"name": "jane"}, {"age": {"$gte": 18}}], "$not": {"name": "jane"}})
+        ));
+        assert!(!selector_matches(doc.clone(), json!({"$nor": [{"name": "john"}]})));
+        assert!(selector_matches(doc.clone(), json!({"name": {"$gt": 12}})));
+
+        assert!(matches(&doc, &json!({"name": {"$regex": "^j"}})).is_err());
+        assert!(matches(&doc, &json!({"$and": {"name": "john"}})).is_err());
+    }
+
+    #[test]
+    fn test_collate_and_project() {
+        let values = [
+            json!(null),
+            json!(false),
+            json!(true),
+            json!(1),
+            json!("a"),
+            json!([1]),
+            json!({}),
+        ];
+        for pair in values.windows(2) {
+            assert_eq!(collate(Some(&pair[0]), Some(&pair[1])), Ordering::Less);
+        }
+        assert_eq!(collate(None, Some(&json!(null))), Ordering::Less);
+        assert_eq!(collate(Some(&json!([1, 2])), Some(&json!([1]))), Ordering::Greater);
+
+        let doc = json!({"_id": "a", "name": "john", "address": {"city": "Amsterdam", "zip": "1000"}});
+        let fields = vec![s!("_id"), s!("address.city"), s!("missing")];
+        assert_eq!(
+            project(&doc, &fields),
+            json!({"_id": "a", "address": {"city": "Amsterdam"}})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revisions() {
+        let db = InMemoryDatabase::new("test");
+        let mut doc = json!({"_id": "a", "count": 1});
+        let created = db.save(&mut doc).await.unwrap();
+        assert!(created.rev.starts_with("1-"));
+        assert_eq!(doc["_rev"], json!(created.rev));
+
+        let mut stale = doc.clone();
+        doc["count"] = json!(2);
+        let updated = db.save(&mut doc).await.unwrap();
+        assert!(updated.rev.starts_with("2-"));
+        stale["count"] = json!(3);
+        assert!(db.save(&mut stale).await.unwrap_err().is_conflict());
+        assert!(db.create(&mut json!({"_id": "a"})).await.unwrap_err().is_conflict());
+
+        let stored: Value = db.get("a").await.unwrap();
+        assert_eq!(stored["count"], json!(2));
+        assert!(!db.remove(&stale).await);
+        assert!(db.remove(&stored).await);
+        assert!(!db.exists("a").await);
+        assert!(db.get::<Value>("a").await.unwrap_err().is_not_found());
+
+        // a deleted document can be created again
+        let recreated = db.create(&mut json!({"_id": "a"})).await.unwrap();
+        assert!(recreated.rev.starts_with("4-"));
+        let mut upserted = json!({"_id": "a", "count": 5});
+        assert!(db.upsert(&mut upserted).await.unwrap().rev.starts_with("5-"));
+
+        let mut generated = json!({"count": 6});
+        let details = db.create(&mut generated).await.unwrap();
+        assert_eq!(generated["_id"], json!(details.id));
+    }
+
+    #[tokio::test]
+    async fn test_find_and_all_docs() {
+        let db = InMemoryDatabase::new("test");
+        let mut docs: Vec<Value> = (0..5)
+            .map(|idx| json!({"_id": format!("doc_{}", idx), "group": idx % 2, "idx": idx}))
+            .collect();
+        docs.push(json!({"_id": "_design/app", "views": {}}));
+        assert!(db.bulk_docs(&mut docs).await.unwrap().iter().all(Result::is_ok));
+
+        let query = FindQuery::new(json!({"group": 0}))
+            .sort(vec![SortSpec::Complex([(s!("idx"), SortDirection::Desc)].into())])
+            .fields(vec![s!("idx")])
+            .limit(2);
+        let found = db.find::<Value>(&query).await.unwrap();
+        assert_eq!(found.rows, vec![json!({"idx": 4}), json!({"idx": 2})]);
+
+        let all = db.get_all::<Value>().await.unwrap();
+        assert_eq!(all.rows.len(), 5);
+        let ids = vec![s!("doc_3"), s!("missing"), s!("doc_1")];
+        let bulk = db.get_bulk::<Value>(ids).await.unwrap();
+        assert_eq!(bulk.rows.len(), 2);
+        assert_eq!(bulk.rows[0]["_id"], json!("doc_3"));
+
+        let params = QueryParams::default()
+            .start_key("doc_1")
+            .end_key("doc_3")
+            .inclusive_end(false);
+        let range = db.get_all_params::<Value>(Some(params)).unwrap();
+        let ids: Vec<&Value> = range.rows.iter().map(|doc| &doc["_id"]).collect();
+        assert_eq!(ids, vec!["doc_1", "doc_2"]);
+        let params = QueryParams::default().descending(true).skip(1).limit(2);
+        let range = db.get_all_params::<Value>(Some(params)).unwrap();
+        let ids: Vec<&Value> = range.rows.iter().map(|doc| &doc["_id"]).collect();
+        assert_eq!(ids, vec!["doc_3", "doc_2"]);
+
+        let err = db
+            .query::<Value, Value, Value>("app", "by_group", None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::NOT_IMPLEMENTED));
+    }
+}
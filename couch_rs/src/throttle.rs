@@ -0,0 +1,73 @@
+use crate::runtime::Runtime;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, Semaphore, SemaphorePermit};
+
+/// Limits the number of concurrent requests of a `Client` and all its clones, and the rate at
+/// which they are sent
+#[derive(Debug)]
+pub(crate) struct Throttle {
+    concurrency: Option<Semaphore>,
+    interval: Option<Duration>,
+    next_slot: Mutex<Instant>,
+}
+
+impl Throttle {
+    pub(crate) fn new(max_concurrent: Option<usize>, max_per_second: Option<u32>) -> Self {
+        Throttle {
+            concurrency: max_concurrent.map(|max| Semaphore::new(max.max(1))),
+            interval: max_per_second.map(|max| Duration::from_secs(1) / max.max(1)),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits until a request may be sent. The request counts as in flight until the permit is
+    /// dropped.
+    pub(crate) async fn acquire(&self, runtime: &dyn Runtime) -> Option<SemaphorePermit<'_>> {
+        if let Some(interval) = self.interval {
+            let slot = {
+                let mut next_slot = self.next_slot.lock().await;
+                let slot = (*next_slot).max(Instant::now());
+                *next_slot = slot + interval;
+                slot
+            };
+            if let Some(delay) = slot.checked_duration_since(Instant::now()) {
+                runtime.sleep(delay).await;
+            }
+        }
+
+        match &self.concurrency {
+            // the semaphore is never closed
+            Some(semaphore) => semaphore.acquire().await.ok(),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Tokio;
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let throttle = Throttle::new(None, Some(20));
+        let started = Instant::now();
+        for _ in 0..5 {
+            throttle.acquire(&Tokio).await;
+        }
+        // the first request is sent immediately, the next ones every 50 ms
+        assert!(started.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit() {
+        let throttle = Throttle::new(Some(2), None);
+        let first = throttle.acquire(&Tokio).await;
+        let _second = throttle.acquire(&Tokio).await;
+        assert!(first.is_some());
+        assert_eq!(throttle.concurrency.as_ref().unwrap().available_permits(), 0);
+
+        drop(first);
+        assert!(throttle.acquire(&Tokio).await.is_some());
+    }
+}
@@ -0,0 +1,80 @@
+use std::time::SystemTime;
+
+/// The type of the `created_at` and `updated_at` fields of a document with
+/// `#[couch(timestamps)]`. The `Database` sets `created_at` when the document is saved for the
+/// first time, i.e. when it doesn't have a `_rev`, and `updated_at` on every save.
+///
+/// Implemented for `SystemTime`, for `chrono::DateTime<Utc>` with the `chrono` feature, for
+/// `time::OffsetDateTime` with the `time` feature, and for an `Option` of those.
+///
+/// Usage:
+/// ```
+/// use couch_rs::CouchDocument;
+/// use couch_rs::document::TypedCouchDocument;
+/// use serde::{Deserialize, Serialize};
+/// use std::time::SystemTime;
+///
+/// #[derive(Serialize, Deserialize, CouchDocument)]
+/// #[couch(timestamps)]
+/// pub struct Note {
+///     #[serde(skip_serializing_if = "String::is_empty")]
+///     pub _id: String,
+///     #[serde(skip_serializing_if = "String::is_empty")]
+///     pub _rev: String,
+///     pub created_at: Option<SystemTime>,
+///     pub updated_at: Option<SystemTime>,
+///     pub text: String,
+/// }
+/// ```
+pub trait Timestamp {
+    fn from_system_time(time: SystemTime) -> Self;
+}
+
+impl Timestamp for SystemTime {
+    fn from_system_time(time: SystemTime) -> Self {
+        time
+    }
+}
+
+impl<T: Timestamp> Timestamp for Option<T> {
+    fn from_system_time(time: SystemTime) -> Self {
+        Some(T::from_system_time(time))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Timestamp for chrono::DateTime<chrono::Utc> {
+    fn from_system_time(time: SystemTime) -> Self {
+        time.into()
+    }
+}
+
+#[cfg(feature = "time")]
+impl Timestamp for time::OffsetDateTime {
+    fn from_system_time(time: SystemTime) -> Self {
+        time.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_from_system_time() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(SystemTime::from_system_time(now), now);
+        assert_eq!(Option::<SystemTime>::from_system_time(now), Some(now));
+        #[cfg(feature = "chrono")]
+        assert_eq!(
+            chrono::DateTime::<chrono::Utc>::from_system_time(now).timestamp(),
+            1_700_000_000
+        );
+        #[cfg(feature = "time")]
+        assert_eq!(
+            time::OffsetDateTime::from_system_time(now).unix_timestamp(),
+            1_700_000_000
+        );
+    }
+}
@@ -1,4 +1,12 @@
+use crate::document::{from_typed_value, TypedCouchDocument};
+use crate::error::CouchResult;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Sequence identifier of a change in the `_changes` feed. CouchDB 2.0 and up use opaque strings,
+/// CouchDB 1.x uses numbers.
+pub type Seq = serde_json::Value;
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 #[serde(untagged)]
@@ -9,7 +17,7 @@ pub enum Event {
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct ChangeEvent {
-    pub seq: serde_json::Value,
+    pub seq: Seq,
     pub id: String,
     pub changes: Vec<Change>,
 
@@ -20,6 +28,37 @@ pub struct ChangeEvent {
     pub doc: Option<serde_json::Value>,
 }
 
+/// A change with its document deserialized, returned by a
+/// [TypedChangesStream](crate::changes::TypedChangesStream).
+#[derive(Debug)]
+pub struct TypedChangeEvent<T> {
+    pub seq: Seq,
+    pub id: String,
+    pub changes: Vec<Change>,
+    pub deleted: bool,
+    /// The document, or the error deserializing it. `None` for a deleted document, or when the
+    /// feed doesn't include the documents.
+    pub doc: Option<CouchResult<T>>,
+}
+
+impl<T: TypedCouchDocument> From<ChangeEvent> for TypedChangeEvent<T> {
+    fn from(event: ChangeEvent) -> Self {
+        let doc = match event.deleted {
+            true => None,
+            false => event
+                .doc
+                .map(|doc| from_typed_value(&doc).map_err(|err| err.with_id(&event.id))),
+        };
+        TypedChangeEvent {
+            seq: event.seq,
+            id: event.id,
+            changes: event.changes,
+            deleted: event.deleted,
+            doc,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct Change {
     pub rev: String,
@@ -27,6 +66,266 @@ pub struct Change {
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct FinishedEvent {
-    pub last_seq: serde_json::Value,
+    pub last_seq: Seq,
+    pub pending: Option<u64>, // not available on CouchDB 1.0
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(untagged)]
+pub enum DbUpdatesEvent {
+    Update(DbUpdateEvent),
+    Finished(FinishedEvent),
+}
+
+/// The kind of update reported by the `_db_updates` feed
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum DbUpdateType {
+    Created,
+    Updated,
+    Deleted,
+    #[serde(other)]
+    Other,
+}
+
+/// A database update, as reported by the `_db_updates` feed
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct DbUpdateEvent {
+    pub db_name: String,
+    #[serde(rename = "type")]
+    pub update_type: DbUpdateType,
+    pub seq: Seq,
+}
+
+/// Filter to apply to the changes feed. See
+/// [filtering](https://docs.couchdb.org/en/stable/api/database/changes.html#filtering) for details.
+#[derive(PartialEq, Debug, Clone)]
+pub enum ChangesFilter {
+    /// A filter function in a design document, in the form `ddoc/name`
+    Function(String),
+    /// Only the changes of the documents with the given ids
+    DocIds(Vec<String>),
+    /// Only the changes of the documents matching a Mango selector
+    Selector(Value),
+    /// Only the changes of the documents that emit a row in the map function of the given view,
+    /// in the form `ddoc/view`
+    View(String),
+}
+
+/// Which revisions to return for every change
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
+pub enum ChangesStyle {
+    /// Only the winning revision
+    #[serde(rename = "main_only")]
+    MainOnly,
+    /// All leaf revisions, including conflicts and deleted former conflicts
+    #[serde(rename = "all_docs")]
+    AllDocs,
+}
+
+impl ChangesStyle {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ChangesStyle::MainOnly => "main_only",
+            ChangesStyle::AllDocs => "all_docs",
+        }
+    }
+}
+
+/// Changes request parameters. You can use the builder paradigm to construct these parameters easily:
+/// ```
+/// use couch_rs::types::changes::{ChangesRequest, ChangesStyle};
+/// let _request = ChangesRequest::default()
+///     .filter("app/important")
+///     .param("level", "high")
+///     .style(ChangesStyle::AllDocs);
+/// ```
+#[derive(Default, PartialEq, Debug, Clone)]
+pub struct ChangesRequest {
+    pub filter: Option<ChangesFilter>,
+    pub style: Option<ChangesStyle>,
+    pub include_docs: Option<bool>,
+    /// Additional query parameters, e.g. the arguments for a filter function
+    pub params: HashMap<String, String>,
+}
+
+impl ChangesRequest {
+    /// Use a filter function in a design document, in the form `ddoc/name`
+    pub fn filter(mut self, filter: &str) -> Self {
+        self.filter = Some(ChangesFilter::Function(filter.to_string()));
+        self
+    }
+
+    pub fn doc_ids(mut self, doc_ids: Vec<String>) -> Self {
+        self.filter = Some(ChangesFilter::DocIds(doc_ids));
+        self
+    }
+
+    pub fn selector(mut self, selector: Value) -> Self {
+        self.filter = Some(ChangesFilter::Selector(selector));
+        self
+    }
+
+    /// Use the map function of a view as filter, in the form `ddoc/view`
+    pub fn view(mut self, view: &str) -> Self {
+        self.filter = Some(ChangesFilter::View(view.to_string()));
+        self
+    }
+
+    pub fn style(mut self, style: ChangesStyle) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    pub fn include_docs(mut self, include_docs: bool) -> Self {
+        self.include_docs = Some(include_docs);
+        self
+    }
+
+    /// Add a query parameter, e.g. an argument for the filter function
+    pub fn param(mut self, key: &str, value: &str) -> Self {
+        self.params.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// The query parameters for this request
+    pub fn query_params(&self) -> HashMap<String, String> {
+        let mut params = self.params.clone();
+
+        match &self.filter {
+            Some(ChangesFilter::Function(filter)) => {
+                params.insert("filter".to_string(), filter.clone());
+            }
+            Some(ChangesFilter::DocIds(_)) => {
+                params.insert("filter".to_string(), "_doc_ids".to_string());
+            }
+            Some(ChangesFilter::Selector(_)) => {
+                params.insert("filter".to_string(), "_selector".to_string());
+            }
+            Some(ChangesFilter::View(view)) => {
+                params.insert("filter".to_string(), "_view".to_string());
+                params.insert("view".to_string(), view.clone());
+            }
+            None => {}
+        }
+
+        if let Some(style) = &self.style {
+            params.insert("style".to_string(), style.as_str().to_string());
+        }
+
+        if let Some(include_docs) = self.include_docs {
+            params.insert("include_docs".to_string(), include_docs.to_string());
+        }
+
+        params
+    }
+
+    /// The body to POST for this request; only the `doc_ids` and `selector` filters require one
+    pub fn body(&self) -> Option<Value> {
+        match &self.filter {
+            Some(ChangesFilter::DocIds(doc_ids)) => Some(json!({ "doc_ids": doc_ids })),
+            Some(ChangesFilter::Selector(selector)) => Some(json!({ "selector": selector })),
+            _ => None,
+        }
+    }
+}
+
+/// Changes response abstraction, returned by the `normal` and `longpoll` feeds
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ChangesResponse {
+    pub results: Vec<ChangeEvent>,
+    /// Resume the feed from here, by passing it as `since` in the next request
+    pub last_seq: Seq,
     pub pending: Option<u64>, // not available on CouchDB 1.0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as couch_rs;
+    use couch_rs_derive::CouchDocument;
+
+    #[test]
+    fn test_typed_change_event() {
+        #[derive(Serialize, Deserialize, CouchDocument, Debug)]
+        struct Thing {
+            _id: String,
+            _rev: String,
+            thing: bool,
+        }
+
+        let event = |doc: Value, deleted: bool| ChangeEvent {
+            seq: json!("1-g1AAAAFR"),
+            id: "a".to_string(),
+            changes: vec![Change {
+                rev: "1-abc".to_string(),
+            }],
+            deleted,
+            doc: Some(doc),
+        };
+
+        let typed: TypedChangeEvent<Thing> = event(json!({"_id": "a", "_rev": "1-abc", "thing": true}), false).into();
+        assert_eq!(typed.id, "a");
+        assert!(typed.doc.unwrap().unwrap().thing);
+
+        let typed: TypedChangeEvent<Thing> = event(json!({"_id": "a", "_rev": "1-abc", "thing": 1}), false).into();
+        let err = typed.doc.unwrap().unwrap_err();
+        assert_eq!(err.id(), Some("a"));
+
+        let typed: TypedChangeEvent<Thing> = event(json!({"_id": "a", "_rev": "2-abc", "_deleted": true}), true).into();
+        assert!(typed.deleted);
+        assert!(typed.doc.is_none());
+    }
+
+    #[test]
+    fn test_parse_db_updates_event() {
+        let event: DbUpdatesEvent =
+            serde_json::from_str(r#"{"db_name":"mailbox","type":"created","seq":"1-g1AAAAFR"}"#).unwrap();
+        match event {
+            DbUpdatesEvent::Update(update) => {
+                assert_eq!(update.db_name, "mailbox");
+                assert_eq!(update.update_type, DbUpdateType::Created);
+                assert_eq!(update.seq, json!("1-g1AAAAFR"));
+            }
+            _ => panic!("expected an update"),
+        }
+
+        let event: DbUpdateEvent = serde_json::from_str(r#"{"db_name":"a","type":"ddoc_updated","seq":1}"#).unwrap();
+        assert_eq!(event.update_type, DbUpdateType::Other);
+
+        let event: DbUpdatesEvent = serde_json::from_str(r#"{"last_seq":"3-g1AAAAFR","pending":0}"#).unwrap();
+        assert!(matches!(event, DbUpdatesEvent::Finished(_)));
+    }
+
+    #[test]
+    fn test_changes_request_filter_function() {
+        let request = ChangesRequest::default()
+            .filter("app/important")
+            .param("level", "high")
+            .style(ChangesStyle::AllDocs)
+            .include_docs(true);
+        let params = request.query_params();
+        assert_eq!(params.get("filter").unwrap(), "app/important");
+        assert_eq!(params.get("level").unwrap(), "high");
+        assert_eq!(params.get("style").unwrap(), "all_docs");
+        assert_eq!(params.get("include_docs").unwrap(), "true");
+        assert!(request.body().is_none());
+    }
+
+    #[test]
+    fn test_changes_request_with_body() {
+        let request = ChangesRequest::default().doc_ids(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(request.query_params().get("filter").unwrap(), "_doc_ids");
+        assert_eq!(request.body().unwrap(), json!({"doc_ids": ["a", "b"]}));
+
+        let request = ChangesRequest::default().selector(json!({"type": "invoice"}));
+        assert_eq!(request.query_params().get("filter").unwrap(), "_selector");
+        assert_eq!(request.body().unwrap(), json!({"selector": {"type": "invoice"}}));
+
+        let request = ChangesRequest::default().view("app/by_type");
+        let params = request.query_params();
+        assert_eq!(params.get("filter").unwrap(), "_view");
+        assert_eq!(params.get("view").unwrap(), "app/by_type");
+        assert!(request.body().is_none());
+    }
+}
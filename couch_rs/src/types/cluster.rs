@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+/// The setup state of a node or cluster, see
+/// [cluster setup](https://docs.couchdb.org/en/stable/setup/cluster.html) for details.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ClusterSetupState {
+    ClusterDisabled,
+    SingleNodeDisabled,
+    SingleNodeEnabled,
+    ClusterEnabled,
+    ClusterFinished,
+    #[serde(other)]
+    Other,
+}
+
+/// The response of `GET /_cluster_setup`
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct ClusterSetupStatus {
+    pub state: ClusterSetupState,
+}
+
+/// An action of the cluster setup wizard, posted to `/_cluster_setup`. A cluster is set up by
+/// enabling the cluster on each node, adding the nodes on the coordinating node, and finishing
+/// the cluster.
+/// ```
+/// use couch_rs::types::cluster::{AddNode, ClusterSetupAction, EnableCluster};
+/// let _actions = vec![
+///     ClusterSetupAction::EnableCluster(EnableCluster::new("0.0.0.0", "admin", "password").node_count(2)),
+///     ClusterSetupAction::AddNode(AddNode::new("10.0.0.2", "admin", "password")),
+///     ClusterSetupAction::FinishCluster,
+/// ];
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClusterSetupAction {
+    EnableSingleNode(EnableCluster),
+    EnableCluster(EnableCluster),
+    AddNode(AddNode),
+    FinishCluster,
+}
+
+/// The payload of the `enable_cluster` and `enable_single_node` actions
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct EnableCluster {
+    pub bind_address: String,
+    /// The name of the admin that is created
+    pub username: String,
+    pub password: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_count: Option<u32>,
+    /// Enables the cluster on a remote node, instead of the node that handles the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_node: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_current_user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_current_password: Option<String>,
+}
+
+impl EnableCluster {
+    pub fn new(bind_address: &str, username: &str, password: &str) -> Self {
+        EnableCluster {
+            bind_address: bind_address.to_string(),
+            username: username.to_string(),
+            password: password.to_string(),
+            port: None,
+            node_count: None,
+            remote_node: None,
+            remote_current_user: None,
+            remote_current_password: None,
+        }
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    pub fn node_count(mut self, node_count: u32) -> Self {
+        self.node_count = Some(node_count);
+        self
+    }
+
+    /// Enables the cluster on `node`, with the current admin credentials of that node
+    pub fn remote(mut self, node: &str, current_user: &str, current_password: &str) -> Self {
+        self.remote_node = Some(node.to_string());
+        self.remote_current_user = Some(current_user.to_string());
+        self.remote_current_password = Some(current_password.to_string());
+        self
+    }
+}
+
+/// The payload of the `add_node` action
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct AddNode {
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    pub username: String,
+    pub password: String,
+}
+
+impl AddNode {
+    pub fn new(host: &str, username: &str, password: &str) -> Self {
+        AddNode {
+            host: host.to_string(),
+            port: None,
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+}
+
+/// The nodes of a cluster, see
+/// [_membership](https://docs.couchdb.org/en/stable/api/server/common.html#membership)
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct Membership {
+    /// The nodes this node knows about and is connected to
+    pub all_nodes: Vec<String>,
+    /// The nodes that are configured as members of the cluster
+    pub cluster_nodes: Vec<String>,
+}
+
+impl Membership {
+    /// The members of the cluster that this node is not connected to, e.g. because they are down
+    pub fn unreachable_nodes(&self) -> Vec<&str> {
+        self.cluster_nodes
+            .iter()
+            .filter(|node| !self.all_nodes.contains(node))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{from_value, json, to_value};
+
+    #[test]
+    fn test_cluster_setup_action() {
+        let action = ClusterSetupAction::AddNode(AddNode::new("10.0.0.2", "admin", "password").port(5984));
+        assert_eq!(
+            to_value(&action).unwrap(),
+            json!({
+                "action": "add_node",
+                "host": "10.0.0.2",
+                "port": 5984,
+                "username": "admin",
+                "password": "password"
+            })
+        );
+        assert_eq!(
+            to_value(&ClusterSetupAction::FinishCluster).unwrap(),
+            json!({ "action": "finish_cluster" })
+        );
+
+        let status: ClusterSetupStatus = from_value(json!({ "state": "cluster_finished" })).unwrap();
+        assert_eq!(status.state, ClusterSetupState::ClusterFinished);
+    }
+
+    #[test]
+    fn test_membership() {
+        let membership: Membership = from_value(json!({
+            "all_nodes": ["couchdb@node1", "couchdb@node2"],
+            "cluster_nodes": ["couchdb@node1", "couchdb@node2", "couchdb@node3"]
+        }))
+        .unwrap();
+        assert_eq!(membership.unreachable_nodes(), vec!["couchdb@node3"]);
+    }
+}
@@ -1,4 +1,12 @@
+use crate::document::TypedCouchDocument;
+use crate::types::document::DocumentId;
+use crate::types::search::SearchIndex;
+use crate::types::view::CouchFunc;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+const DESIGN_PREFIX: &str = "_design/";
 
 /// Design document created abstraction
 #[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
@@ -9,3 +17,345 @@ pub struct DesignCreated {
     pub error: Option<String>,
     pub reason: Option<String>,
 }
+
+/// A design document, holding the views, search indexes, filters, update functions and validation
+/// of a database. Define the design documents of an application in code, and deploy them with
+/// `Database::sync_design_doc`. See [design documents](https://docs.couchdb.org/en/stable/ddocs/index.html)
+/// for details.
+/// ```
+/// use couch_rs::types::design::DesignDocument;
+/// use couch_rs::types::search::SearchIndex;
+/// use couch_rs::types::view::CouchFunc;
+/// let _ddoc = DesignDocument::new("users")
+///     .view("by_name", CouchFunc::new("function(doc) { emit(doc.name, null); }", None))
+///     .search_index("by_text", SearchIndex::new("function(doc) { index('default', doc.bio); }"))
+///     .filter("active", "function(doc, req) { return doc.active; }")
+///     .validate_doc_update("function(newDoc, oldDoc, userCtx) { if (!newDoc.name) { throw({forbidden: 'name is required'}); } }");
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct DesignDocument {
+    pub _id: DocumentId,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub _rev: String,
+    #[serde(default = "default_language")]
+    pub language: String,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub views: HashMap<String, CouchFunc>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub indexes: HashMap<String, SearchIndex>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub filters: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub updates: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub shows: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub lists: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validate_doc_update: Option<String>,
+}
+
+fn default_language() -> String {
+    "javascript".to_string()
+}
+
+impl DesignDocument {
+    /// Creates an empty design document. The name may include the `_design/` prefix.
+    pub fn new(name: &str) -> Self {
+        let name = name.strip_prefix(DESIGN_PREFIX).unwrap_or(name);
+        DesignDocument {
+            _id: format!("{}{}", DESIGN_PREFIX, name),
+            _rev: String::new(),
+            language: default_language(),
+            views: HashMap::new(),
+            indexes: HashMap::new(),
+            filters: HashMap::new(),
+            updates: HashMap::new(),
+            shows: HashMap::new(),
+            lists: HashMap::new(),
+            validate_doc_update: None,
+        }
+    }
+
+    /// The name of the design document, without the `_design/` prefix
+    pub fn name(&self) -> &str {
+        self._id.strip_prefix(DESIGN_PREFIX).unwrap_or(&self._id)
+    }
+
+    pub fn language(mut self, language: &str) -> Self {
+        self.language = language.to_string();
+        self
+    }
+
+    pub fn view(mut self, name: &str, func: CouchFunc) -> Self {
+        self.views.insert(name.to_string(), func);
+        self
+    }
+
+    /// Adds a search index, to query with `Database::search`
+    pub fn search_index(mut self, name: &str, index: SearchIndex) -> Self {
+        self.indexes.insert(name.to_string(), index);
+        self
+    }
+
+    pub fn filter(mut self, name: &str, func: &str) -> Self {
+        self.filters.insert(name.to_string(), func.to_string());
+        self
+    }
+
+    pub fn update(mut self, name: &str, func: &str) -> Self {
+        self.updates.insert(name.to_string(), func.to_string());
+        self
+    }
+
+    pub fn show(mut self, name: &str, func: &str) -> Self {
+        self.shows.insert(name.to_string(), func.to_string());
+        self
+    }
+
+    pub fn list(mut self, name: &str, func: &str) -> Self {
+        self.lists.insert(name.to_string(), func.to_string());
+        self
+    }
+
+    pub fn validate_doc_update(mut self, func: &str) -> Self {
+        self.validate_doc_update = Some(func.to_string());
+        self
+    }
+
+    /// Whether the content of both design documents is the same, regardless of their revisions
+    pub fn same_content(&self, other: &DesignDocument) -> bool {
+        self._id == other._id && self.diff(Some(other)).is_empty()
+    }
+
+    /// The changes needed to turn `current` (the design document in the database, if any) into
+    /// this design document
+    pub fn diff(&self, current: Option<&DesignDocument>) -> DesignDocDiff {
+        let empty = DesignDocument::new(self.name());
+        let current_doc = current.unwrap_or(&empty);
+        DesignDocDiff {
+            exists: current.is_some(),
+            language: self.language != current_doc.language,
+            views: diff_functions(&self.views, &current_doc.views),
+            indexes: diff_functions(&self.indexes, &current_doc.indexes),
+            filters: diff_functions(&self.filters, &current_doc.filters),
+            updates: diff_functions(&self.updates, &current_doc.updates),
+            shows: diff_functions(&self.shows, &current_doc.shows),
+            lists: diff_functions(&self.lists, &current_doc.lists),
+            validate_doc_update: diff_function(
+                self.validate_doc_update.as_ref(),
+                current_doc.validate_doc_update.as_ref(),
+            ),
+        }
+    }
+}
+
+/// How a function of a design document changes
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A change to a named function (e.g. a view or filter) of a design document
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct FunctionChange {
+    pub name: String,
+    pub kind: ChangeKind,
+}
+
+/// The differences between a design document in code and in the database, see
+/// `Database::diff_design_doc`
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct DesignDocDiff {
+    /// Whether the design document exists in the database
+    pub exists: bool,
+    pub language: bool,
+    pub views: Vec<FunctionChange>,
+    pub indexes: Vec<FunctionChange>,
+    pub filters: Vec<FunctionChange>,
+    pub updates: Vec<FunctionChange>,
+    pub shows: Vec<FunctionChange>,
+    pub lists: Vec<FunctionChange>,
+    pub validate_doc_update: Option<ChangeKind>,
+}
+
+impl DesignDocDiff {
+    /// Whether the design documents have the same content
+    pub fn is_empty(&self) -> bool {
+        !self.language
+            && self.views.is_empty()
+            && self.indexes.is_empty()
+            && self.filters.is_empty()
+            && self.updates.is_empty()
+            && self.shows.is_empty()
+            && self.lists.is_empty()
+            && self.validate_doc_update.is_none()
+    }
+}
+
+fn diff_function<T: PartialEq>(new: Option<&T>, current: Option<&T>) -> Option<ChangeKind> {
+    match (new, current) {
+        (Some(_), None) => Some(ChangeKind::Added),
+        (None, Some(_)) => Some(ChangeKind::Removed),
+        (Some(new), Some(current)) if new != current => Some(ChangeKind::Modified),
+        _ => None,
+    }
+}
+
+fn diff_functions<T: PartialEq>(new: &HashMap<String, T>, current: &HashMap<String, T>) -> Vec<FunctionChange> {
+    let mut names: Vec<&String> = new
+        .keys()
+        .chain(current.keys().filter(|k| !new.contains_key(*k)))
+        .collect();
+    names.sort();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            diff_function(new.get(name), current.get(name)).map(|kind| FunctionChange {
+                name: name.clone(),
+                kind,
+            })
+        })
+        .collect()
+}
+
+impl TypedCouchDocument for DesignDocument {
+    fn get_id(&self) -> Cow<'_, str> {
+        Cow::from(&self._id)
+    }
+
+    fn get_rev(&self) -> Cow<'_, str> {
+        Cow::from(&self._rev)
+    }
+
+    fn set_rev(&mut self, rev: &str) {
+        self._rev = rev.to_string();
+    }
+
+    fn set_id(&mut self, id: &str) {
+        self._id = id.to_string();
+    }
+
+    fn merge_ids(&mut self, other: &Self) {
+        self.set_id(&other.get_id());
+        self.set_rev(&other.get_rev());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_design_document() {
+        let ddoc = DesignDocument::new("_design/users")
+            .view(
+                "by_name",
+                CouchFunc::new("function(doc) { emit(doc.name); }", Some("_count")),
+            )
+            .filter("active", "function(doc) { return doc.active; }");
+        assert_eq!(ddoc._id, "_design/users");
+        assert_eq!(ddoc.name(), "users");
+        assert_eq!(
+            serde_json::to_value(&ddoc).unwrap(),
+            json!({
+                "_id": "_design/users",
+                "language": "javascript",
+                "views": {"by_name": {"map": "function(doc) { emit(doc.name); }", "reduce": "_count"}},
+                "filters": {"active": "function(doc) { return doc.active; }"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_same_content_ignores_revision() {
+        let ddoc = DesignDocument::new("users").update("touch", "function(doc, req) { return [doc, 'ok']; }");
+        let mut stored: DesignDocument = serde_json::from_value(json!({
+            "_id": "_design/users",
+            "_rev": "1-abc",
+            "updates": {"touch": "function(doc, req) { return [doc, 'ok']; }"}
+        }))
+        .unwrap();
+        assert!(ddoc.same_content(&stored));
+
+        stored.validate_doc_update = Some("function() {}".to_string());
+        assert!(!ddoc.same_content(&stored));
+    }
+
+    #[test]
+    fn test_diff() {
+        let ddoc = DesignDocument::new("users")
+            .view("by_name", CouchFunc::new("function(doc) { emit(doc.name); }", None))
+            .view("by_age", CouchFunc::new("function(doc) { emit(doc.age); }", None))
+            .filter("active", "function(doc) { return doc.active; }");
+        let current = DesignDocument::new("users")
+            .view(
+                "by_name",
+                CouchFunc::new("function(doc) { emit(doc.name, null); }", None),
+            )
+            .view("by_email", CouchFunc::new("function(doc) { emit(doc.email); }", None))
+            .filter("active", "function(doc) { return doc.active; }")
+            .validate_doc_update("function() {}");
+
+        let diff = ddoc.diff(Some(&current));
+        assert!(diff.exists);
+        assert!(!diff.is_empty());
+        assert_eq!(
+            diff.views,
+            vec![
+                FunctionChange {
+                    name: "by_age".to_string(),
+                    kind: ChangeKind::Added
+                },
+                FunctionChange {
+                    name: "by_email".to_string(),
+                    kind: ChangeKind::Removed
+                },
+                FunctionChange {
+                    name: "by_name".to_string(),
+                    kind: ChangeKind::Modified
+                },
+            ]
+        );
+        assert!(diff.indexes.is_empty());
+        assert!(diff.filters.is_empty());
+        assert_eq!(diff.validate_doc_update, Some(ChangeKind::Removed));
+
+        let new = ddoc.diff(None);
+        assert!(!new.exists);
+        assert_eq!(new.views.len(), 2);
+        assert!(ddoc.diff(Some(&ddoc)).is_empty());
+    }
+
+    #[test]
+    fn test_search_index() {
+        let ddoc = DesignDocument::new("books").search_index(
+            "by_title",
+            SearchIndex::new("function(doc) { index('title', doc.title); }").analyzer("english"),
+        );
+        assert_eq!(
+            serde_json::to_value(&ddoc).unwrap(),
+            json!({
+                "_id": "_design/books",
+                "language": "javascript",
+                "indexes": {"by_title": {"analyzer": "english", "index": "function(doc) { index('title', doc.title); }"}}
+            })
+        );
+
+        let current = DesignDocument::new("books").search_index(
+            "by_title",
+            SearchIndex::new("function(doc) { index('title', doc.title); }"),
+        );
+        assert_eq!(
+            ddoc.diff(Some(&current)).indexes,
+            vec![FunctionChange {
+                name: "by_title".to_string(),
+                kind: ChangeKind::Modified
+            }]
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use crate::error::{CouchError, CouchResult};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// String that represents a Document ID in CouchDB
 pub type DocumentId = String;
@@ -13,6 +14,204 @@ pub enum DocumentRef<T> {
     Populated(T),
 }
 
+/// The `_attachments` of a document, by attachment name
+pub type Attachments = HashMap<String, Attachment>;
+
+/// An attachment of a document: either a stub with just the metadata, or an inline attachment
+/// with its (base64 encoded) data. CouchDB returns stubs, unless the document is read with
+/// `ReadOptions::attachments`. Stubs can be sent back unchanged to keep an attachment as it is.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(untagged)]
+pub enum Attachment {
+    Inline(InlineAttachment),
+    Stub(AttachmentStub),
+}
+
+impl Attachment {
+    /// Creates an inline attachment from its raw content
+    pub fn inline(content_type: &str, data: &[u8]) -> Self {
+        Attachment::Inline(InlineAttachment {
+            content_type: content_type.to_string(),
+            data: base64::encode(data),
+            digest: None,
+            revpos: None,
+        })
+    }
+
+    pub fn content_type(&self) -> &str {
+        match self {
+            Attachment::Inline(inline) => &inline.content_type,
+            Attachment::Stub(stub) => &stub.content_type,
+        }
+    }
+
+    /// The decoded content of an inline attachment, `None` for stubs or invalid data
+    pub fn data(&self) -> Option<Vec<u8>> {
+        match self {
+            Attachment::Inline(inline) => base64::decode(&inline.data).ok(),
+            Attachment::Stub(_) => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct InlineAttachment {
+    pub content_type: String,
+    /// The base64 encoded content
+    pub data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revpos: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct AttachmentStub {
+    pub content_type: String,
+    pub digest: String,
+    pub length: u64,
+    pub revpos: u64,
+    #[serde(default = "stub_default")]
+    pub stub: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoded_length: Option<u64>,
+}
+
+fn stub_default() -> bool {
+    true
+}
+
+/// Options to read a single document
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct ReadOptions {
+    /// A specific revision of the document, instead of the winning revision
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    /// Include the data of the attachments, instead of stubs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<bool>,
+    /// Include the encoding information in the attachment stubs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub att_encoding_info: Option<bool>,
+    /// Include the revisions that are in conflict with the winning revision in `_conflicts`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflicts: Option<bool>,
+    /// Include the deleted revisions that were in conflict in `_deleted_conflicts`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_conflicts: Option<bool>,
+    /// The read quorum: the number of replicas that must return the document before the response
+    /// is sent. A higher quorum reads recent writes more reliably, at the cost of latency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r: Option<u32>,
+}
+
+impl ReadOptions {
+    pub fn rev(mut self, rev: &str) -> Self {
+        self.rev = Some(rev.to_string());
+        self
+    }
+
+    pub fn attachments(mut self, attachments: bool) -> Self {
+        self.attachments = Some(attachments);
+        self
+    }
+
+    pub fn att_encoding_info(mut self, att_encoding_info: bool) -> Self {
+        self.att_encoding_info = Some(att_encoding_info);
+        self
+    }
+
+    pub fn conflicts(mut self, conflicts: bool) -> Self {
+        self.conflicts = Some(conflicts);
+        self
+    }
+
+    pub fn deleted_conflicts(mut self, deleted_conflicts: bool) -> Self {
+        self.deleted_conflicts = Some(deleted_conflicts);
+        self
+    }
+
+    pub fn r(mut self, r: u32) -> Self {
+        self.r = Some(r);
+        self
+    }
+
+    /// The options as query string parameters
+    pub(crate) fn query_params(&self) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        if let Some(rev) = &self.rev {
+            params.insert(s!("rev"), rev.clone());
+        }
+        if let Some(attachments) = self.attachments {
+            params.insert(s!("attachments"), attachments.to_string());
+        }
+        if let Some(att_encoding_info) = self.att_encoding_info {
+            params.insert(s!("att_encoding_info"), att_encoding_info.to_string());
+        }
+        if let Some(conflicts) = self.conflicts {
+            params.insert(s!("conflicts"), conflicts.to_string());
+        }
+        if let Some(deleted_conflicts) = self.deleted_conflicts {
+            params.insert(s!("deleted_conflicts"), deleted_conflicts.to_string());
+        }
+        if let Some(r) = self.r {
+            params.insert(s!("r"), r.to_string());
+        }
+        params
+    }
+}
+
+/// The revisions to read with `Database::get_open_revs`
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum OpenRevs {
+    /// All leaf revisions, i.e. the winning revision and the revisions in conflict with it
+    All,
+    /// Specific revisions
+    Revs(Vec<String>),
+}
+
+impl OpenRevs {
+    /// The value of the `open_revs` query string parameter
+    pub(crate) fn query_value(&self) -> CouchResult<String> {
+        match self {
+            OpenRevs::All => Ok(s!("all")),
+            OpenRevs::Revs(revs) => Ok(serde_json::to_string(revs)?),
+        }
+    }
+}
+
+/// A revision in the response of an `open_revs` request
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum OpenRevResponse<T> {
+    Ok(T),
+    Missing(serde::de::IgnoredAny),
+}
+
+/// Options to write documents in bulk
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct BulkDocsOptions {
+    /// When `false`, the documents are written with their own `_rev` instead of a new revision
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_edits: Option<bool>,
+}
+
+impl BulkDocsOptions {
+    pub fn new_edits(mut self, new_edits: bool) -> Self {
+        self.new_edits = Some(new_edits);
+        self
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct BulkDocsRequest<'a, T: Serialize> {
+    pub docs: &'a [T],
+    #[serde(flatten)]
+    pub options: &'a BulkDocsOptions,
+}
+
 /// Abstracted document creation response
 #[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
 pub(crate) struct DocumentCreatedResponse {
@@ -37,15 +236,21 @@ pub struct DocumentCreatedDetails {
     pub rev: String,
 }
 
+/// The status code of a per-document error in a bulk response
+fn error_status(error: &str) -> StatusCode {
+    match error {
+        "forbidden" => StatusCode::FORBIDDEN,
+        "unauthorized" => StatusCode::UNAUTHORIZED,
+        "conflict" => StatusCode::CONFLICT,
+        "not_found" => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
 impl From<DocumentCreatedResponse> for DocumentCreatedResult {
     fn from(response: DocumentCreatedResponse) -> Self {
         if let Some(error) = response.error {
-            let status_code = match error.as_str() {
-                "forbidden" => StatusCode::FORBIDDEN,
-                "unauthorized" => StatusCode::UNAUTHORIZED,
-                "conflict" => StatusCode::CONFLICT,
-                _ => StatusCode::INTERNAL_SERVER_ERROR,
-            };
+            let status_code = error_status(&error);
 
             Err(CouchError::new_with_id(
                 response.id,
@@ -65,3 +270,280 @@ impl From<DocumentCreatedResponse> for DocumentCreatedResult {
 }
 
 pub type DocumentCreatedResult = CouchResult<DocumentCreatedDetails>;
+
+/// Options to save a single document
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct SaveOptions {
+    /// Write in batch mode: CouchDB acknowledges the write before it's stored, and stores the
+    /// document later together with other writes. A batched write may be lost, e.g. when the
+    /// server crashes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch: Option<bool>,
+    /// The write quorum: the number of replicas that must store the document before the response
+    /// is sent. CouchDB responds with 202 (Accepted) when fewer than `w` replicas confirmed the
+    /// write.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub w: Option<u32>,
+}
+
+impl SaveOptions {
+    pub fn batch(mut self, batch: bool) -> Self {
+        self.batch = Some(batch);
+        self
+    }
+
+    pub fn w(mut self, w: u32) -> Self {
+        self.w = Some(w);
+        self
+    }
+
+    /// The options as query string parameters
+    pub(crate) fn query_params(&self) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        if let Some(true) = self.batch {
+            params.insert(s!("batch"), s!("ok"));
+        }
+        if let Some(w) = self.w {
+            params.insert(s!("w"), w.to_string());
+        }
+        params
+    }
+}
+
+/// The options of a write, with the write quorum `w`: the counterpart of `ReadOptions`
+pub type WriteOptions = SaveOptions;
+
+/// The result of saving a document with `SaveOptions`
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
+pub enum SaveResult {
+    /// The document is stored, with the new revision
+    Created(DocumentCreatedDetails),
+    /// The document is accepted in batch mode, but not stored yet; its revision is unknown
+    Accepted { id: String },
+}
+
+impl SaveResult {
+    pub fn id(&self) -> &str {
+        match self {
+            SaveResult::Created(details) => &details.id,
+            SaveResult::Accepted { id } => id,
+        }
+    }
+
+    /// Whether the document is stored, which is not yet the case for batched writes
+    pub fn is_durable(&self) -> bool {
+        matches!(self, SaveResult::Created(_))
+    }
+}
+
+/// A document to read with `Database::bulk_get`, optionally a specific revision of it
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct BulkGetItem {
+    pub id: DocumentId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+}
+
+impl BulkGetItem {
+    pub fn new(id: &str) -> Self {
+        BulkGetItem {
+            id: id.to_string(),
+            rev: None,
+        }
+    }
+
+    pub fn rev(mut self, rev: &str) -> Self {
+        self.rev = Some(rev.to_string());
+        self
+    }
+}
+
+impl From<&str> for BulkGetItem {
+    fn from(id: &str) -> Self {
+        BulkGetItem::new(id)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct BulkGetResponse<T> {
+    pub results: Vec<BulkGetResult<T>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct BulkGetResult<T> {
+    pub docs: Vec<BulkGetDoc<T>>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum BulkGetDoc<T> {
+    Ok(T),
+    Error(BulkGetError),
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct BulkGetError {
+    pub id: String,
+    pub error: String,
+    pub reason: Option<String>,
+}
+
+impl<T> From<BulkGetDoc<T>> for CouchResult<T> {
+    fn from(doc: BulkGetDoc<T>) -> Self {
+        match doc {
+            BulkGetDoc::Ok(doc) => Ok(doc),
+            BulkGetDoc::Error(err) => {
+                let status = error_status(&err.error);
+                Err(CouchError::new_with_id(
+                    Some(err.id),
+                    err.reason.unwrap_or(err.error),
+                    status,
+                ))
+            }
+        }
+    }
+}
+
+/// The revisions of a document that are missing in a database, see `Database::revs_diff`
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct RevsDiff {
+    pub missing: Vec<String>,
+    /// Revisions in the database that may be ancestors of the missing revisions
+    #[serde(default)]
+    pub possible_ancestors: Vec<String>,
+}
+
+/// The result of purging documents
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct PurgeResult {
+    pub purge_seq: Option<serde_json::Value>,
+    /// The purged revisions, by document id
+    pub purged: HashMap<String, Vec<String>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_attachments() {
+        let attachments: Attachments = serde_json::from_value(json!({
+            "stub.txt": {
+                "content_type": "text/plain",
+                "digest": "md5-XrY7u+Ae7tCTyyK7j1rNww==",
+                "length": 11,
+                "revpos": 2,
+                "stub": true
+            },
+            "inline.txt": {
+                "content_type": "text/plain",
+                "data": "aGVsbG8gd29ybGQ=",
+                "digest": "md5-XrY7u+Ae7tCTyyK7j1rNww==",
+                "revpos": 2
+            }
+        }))
+        .unwrap();
+
+        assert!(matches!(attachments["stub.txt"], Attachment::Stub(_)));
+        assert_eq!(attachments["stub.txt"].data(), None);
+        assert_eq!(attachments["inline.txt"].data().unwrap(), b"hello world");
+        assert_eq!(attachments["inline.txt"].content_type(), "text/plain");
+    }
+
+    #[test]
+    fn test_inline_attachment() {
+        let attachment = Attachment::inline("text/plain", b"hello world");
+        assert_eq!(
+            serde_json::to_value(&attachment).unwrap(),
+            json!({"content_type": "text/plain", "data": "aGVsbG8gd29ybGQ="})
+        );
+    }
+
+    #[test]
+    fn test_read_options() {
+        let params = ReadOptions::default().attachments(true).query_params();
+        assert_eq!(params.get("attachments").map(String::as_str), Some("true"));
+        assert!(!params.contains_key("att_encoding_info"));
+
+        let params = ReadOptions::default()
+            .conflicts(true)
+            .deleted_conflicts(true)
+            .query_params();
+        assert_eq!(params.get("conflicts").map(String::as_str), Some("true"));
+        assert_eq!(params.get("deleted_conflicts").map(String::as_str), Some("true"));
+
+        let params = ReadOptions::default().r(2).query_params();
+        assert_eq!(params.get("r").map(String::as_str), Some("2"));
+
+        let params = ReadOptions::default().rev("1-abc").query_params();
+        assert_eq!(params.get("rev").map(String::as_str), Some("1-abc"));
+    }
+
+    #[test]
+    fn test_document_created_result() {
+        let response: DocumentCreatedResponse = serde_json::from_value(json!({
+            "id": "1",
+            "error": "conflict",
+            "reason": "Document update conflict."
+        }))
+        .unwrap();
+        let err = DocumentCreatedResult::from(response).unwrap_err();
+        assert!(err.is_conflict());
+        assert_eq!(err.id(), Some("1"));
+
+        let response: DocumentCreatedResponse =
+            serde_json::from_value(json!({ "ok": true, "id": "2", "rev": "1-abc" })).unwrap();
+        assert_eq!(DocumentCreatedResult::from(response).unwrap().rev, "1-abc");
+    }
+
+    #[test]
+    fn test_bulk_get_response() {
+        let response: BulkGetResponse<serde_json::Value> = serde_json::from_value(json!({
+            "results": [
+                { "id": "1", "docs": [{ "ok": { "_id": "1", "_rev": "1-abc" } }] },
+                { "id": "2", "docs": [{ "error": { "id": "2", "rev": "undefined", "error": "not_found", "reason": "missing" } }] }
+            ]
+        }))
+        .unwrap();
+        let docs: Vec<CouchResult<serde_json::Value>> = response
+            .results
+            .into_iter()
+            .flat_map(|result| result.docs)
+            .map(CouchResult::from)
+            .collect();
+        assert_eq!(docs[0].as_ref().unwrap()["_rev"], "1-abc");
+        let err = docs[1].as_ref().unwrap_err();
+        assert!(err.is_not_found());
+        assert_eq!(err.id(), Some("2"));
+    }
+
+    #[test]
+    fn test_save_options() {
+        assert_eq!(
+            SaveOptions::default()
+                .batch(true)
+                .query_params()
+                .get("batch")
+                .map(String::as_str),
+            Some("ok")
+        );
+        assert!(SaveOptions::default().batch(false).query_params().is_empty());
+        assert_eq!(
+            SaveOptions::default().w(3).query_params().get("w").map(String::as_str),
+            Some("3")
+        );
+    }
+
+    #[test]
+    fn test_open_revs() {
+        assert_eq!(OpenRevs::All.query_value().unwrap(), "all");
+        let revs = OpenRevs::Revs(vec![s!("1-abc"), s!("2-def")]);
+        assert_eq!(revs.query_value().unwrap(), r#"["1-abc","2-def"]"#);
+
+        let response: Vec<OpenRevResponse<serde_json::Value>> =
+            serde_json::from_value(json!([{"ok": {"_id": "1", "_rev": "1-abc"}}, {"missing": "2-def"}])).unwrap();
+        assert!(matches!(&response[0], OpenRevResponse::Ok(doc) if doc["_rev"] == "1-abc"));
+        assert!(matches!(&response[1], OpenRevResponse::Missing(_)));
+    }
+}
@@ -1,6 +1,8 @@
 use crate::document::TypedCouchDocument;
+use crate::types::index::Index;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 
@@ -83,8 +85,8 @@ pub struct FindQuery {
 
 /// Find result abstraction
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
-#[serde(bound(deserialize = "T: TypedCouchDocument"))]
-pub struct FindResult<T: TypedCouchDocument> {
+#[serde(bound(deserialize = "T: DeserializeOwned"))]
+pub struct FindResult<T> {
     pub docs: Option<Vec<T>>,
     pub warning: Option<String>,
     pub error: Option<String>,
@@ -94,17 +96,52 @@ pub struct FindResult<T: TypedCouchDocument> {
 
 //todo: include status on structs
 
-/// Explain result abstraction
+/// A struct with some of the fields of a type of documents, which `Database::find_fields` asks
+/// CouchDB for, instead of the whole documents. Derive it with `#[derive(Projection)]`, so the
+/// fields always match the struct.
+pub trait Projection: DeserializeOwned {
+    /// The fields of the documents, as they are named in CouchDB
+    fn fields() -> &'static [&'static str];
+}
+
+/// The documents of `Database::find_fields`, with the fields of the projection only
+#[derive(PartialEq, Debug, Clone)]
+pub struct Projected<P> {
+    pub docs: Vec<P>,
+    /// The bookmark of the next page, if any
+    pub bookmark: Option<String>,
+}
+
+/// Explain result abstraction: how CouchDB would execute a Mango query.
+/// See [/db/_explain](https://docs.couchdb.org/en/latest/api/database/find.html#db-explain)
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct ExplainResult {
     pub dbname: String,
-    pub index: IndexSpec,
+    /// The index chosen to execute the query
+    pub index: Index,
+    #[serde(default)]
+    pub partitioned: Option<bool>,
     pub selector: Value,
     pub opts: Value,
-    pub limit: u32,
+    pub limit: u64,
     pub skip: u64,
-    pub fields: Vec<String>,
-    pub range: Value,
+    /// The requested fields, or `"all_fields"`
+    pub fields: Value,
+    #[serde(default)]
+    pub range: Option<Value>,
+    /// The arguments passed to the underlying view query
+    #[serde(default)]
+    pub mrargs: Option<Value>,
+    /// Whether the index covers all fields of the query, so no documents have to be read (CouchDB 3.4+)
+    #[serde(default)]
+    pub covering: Option<bool>,
+}
+
+impl ExplainResult {
+    /// Whether the query requires a full scan of the database, because no suitable index exists
+    pub fn is_full_scan(&self) -> bool {
+        self.index.index_type == "special"
+    }
 }
 
 /// $ne operation
@@ -139,7 +176,7 @@ impl SelectAll {
 
 impl From<&SelectAll> for serde_json::Value {
     fn from(s: &SelectAll) -> Self {
-        serde_json::to_value(&s).expect("can not convert into json")
+        serde_json::to_value(s).expect("can not convert into json")
     }
 }
 
@@ -190,6 +227,15 @@ impl FindQuery {
         Self::new(SelectAll::default().as_value())
     }
 
+    /// Finds the documents of type `T`, by the `type` field of `TypedCouchDocument::type_name`;
+    /// all documents when `T` doesn't have a type name
+    pub fn for_type<T: TypedCouchDocument>() -> Self {
+        match T::type_name() {
+            Some(type_name) => Self::new(serde_json::json!({ "type": type_name })),
+            None => Self::find_all(),
+        }
+    }
+
     pub fn as_value(&self) -> Value {
         self.into()
     }
@@ -229,11 +275,15 @@ impl FindQuery {
         self
     }
 
+    /// Whether to update the index before responding. `false` skips the index rebuild for
+    /// latency-sensitive reads, and may return stale results. Defaults to `true`, like CouchDB.
     pub fn update(mut self, update: bool) -> Self {
         self.update = Some(update);
         self
     }
 
+    /// Whether to answer from the same set of shard replicas for every request. Defaults to
+    /// `false`, like CouchDB.
     pub fn stable(mut self, stable: bool) -> Self {
         self.stable = Some(stable);
         self
@@ -248,6 +298,58 @@ impl FindQuery {
         self.execution_stats = Some(execution_stats);
         self
     }
+
+    /// The fields an index needs to serve this query: the sort fields first, followed by the
+    /// fields of the selector. Fields under `$or`, `$nor` and `$not` are left out, because an
+    /// index on them can not be used for the query.
+    pub(crate) fn index_fields(&self) -> Vec<String> {
+        let mut fields = vec![];
+        for spec in &self.sort {
+            match spec {
+                SortSpec::Simple(field) => push_unique(&mut fields, field),
+                SortSpec::Complex(content) => content.keys().for_each(|field| push_unique(&mut fields, field)),
+            }
+        }
+        selector_fields(&self.selector, "", &mut fields);
+        fields
+    }
+}
+
+fn push_unique(fields: &mut Vec<String>, field: &str) {
+    if !fields.iter().any(|f| f == field) {
+        fields.push(field.to_string());
+    }
+}
+
+fn is_operator(key: &str) -> bool {
+    key.starts_with('$')
+}
+
+fn selector_fields(selector: &Value, prefix: &str, fields: &mut Vec<String>) {
+    let Some(selector) = selector.as_object() else {
+        return;
+    };
+
+    for (key, value) in selector {
+        if key == "$and" {
+            for sub in value.as_array().into_iter().flatten() {
+                selector_fields(sub, prefix, fields);
+            }
+        } else if !is_operator(key) {
+            let path = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            match value.as_object() {
+                // a nested object selector, e.g. {"address": {"city": "Amsterdam"}}
+                Some(nested) if !nested.is_empty() && !nested.keys().any(|k| is_operator(k)) => {
+                    selector_fields(value, &path, fields)
+                }
+                _ => push_unique(fields, &path),
+            }
+        }
+    }
 }
 
 impl From<FindQuery> for serde_json::Value {
@@ -258,7 +360,7 @@ impl From<FindQuery> for serde_json::Value {
 
 impl From<&FindQuery> for serde_json::Value {
     fn from(q: &FindQuery) -> Self {
-        serde_json::to_value(&q).expect("can not convert into json")
+        serde_json::to_value(q).expect("can not convert into json")
     }
 }
 
@@ -275,11 +377,204 @@ impl Display for FindQuery {
     }
 }
 
+/// Fluent builder for [Mango selectors](https://docs.couchdb.org/en/latest/api/database/find.html#find-selectors).
+/// Conditions on the same field are combined, conditions on different fields must all match.
+/// Builders can be cloned and nested, to reuse parts of a selector across queries:
+/// ```
+/// use couch_rs::types::find::{FindQuery, SelectorBuilder};
+/// use serde_json::json;
+///
+/// let adults = SelectorBuilder::new().gte("age", 18);
+/// let _query: FindQuery = adults
+///     .clone()
+///     .or(vec![
+///         SelectorBuilder::new().eq("city", "Amsterdam"),
+///         SelectorBuilder::new().is_in("country", vec!["NL", "BE"]),
+///     ])
+///     .into_query()
+///     .limit(10);
+/// assert_eq!(adults.build(), json!({"age": {"$gte": 18}}));
+/// ```
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct SelectorBuilder {
+    selector: Map<String, Value>,
+}
+
+impl SelectorBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the condition `{field: {operator: value}}`, merged with the other conditions on the field
+    pub fn condition(mut self, field: &str, operator: &str, value: impl Into<Value>) -> Self {
+        let entry = self
+            .selector
+            .entry(field.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !entry.is_object() {
+            // an implicit equality, e.g. added through `field`
+            *entry = serde_json::json!({ "$eq": entry.take() });
+        }
+        if let Some(conditions) = entry.as_object_mut() {
+            conditions.insert(operator.to_string(), value.into());
+        }
+        self
+    }
+
+    /// Sets the raw selector for a field, replacing any conditions on that field
+    pub fn field(mut self, field: &str, selector: impl Into<Value>) -> Self {
+        self.selector.insert(field.to_string(), selector.into());
+        self
+    }
+
+    pub fn eq(self, field: &str, value: impl Into<Value>) -> Self {
+        self.condition(field, "$eq", value)
+    }
+
+    pub fn ne(self, field: &str, value: impl Into<Value>) -> Self {
+        self.condition(field, "$ne", value)
+    }
+
+    pub fn gt(self, field: &str, value: impl Into<Value>) -> Self {
+        self.condition(field, "$gt", value)
+    }
+
+    pub fn gte(self, field: &str, value: impl Into<Value>) -> Self {
+        self.condition(field, "$gte", value)
+    }
+
+    pub fn lt(self, field: &str, value: impl Into<Value>) -> Self {
+        self.condition(field, "$lt", value)
+    }
+
+    pub fn lte(self, field: &str, value: impl Into<Value>) -> Self {
+        self.condition(field, "$lte", value)
+    }
+
+    /// `$in`: the field must equal one of the values
+    pub fn is_in<V: Into<Value>>(self, field: &str, values: Vec<V>) -> Self {
+        self.condition(field, "$in", values)
+    }
+
+    /// `$nin`: the field must not equal any of the values
+    pub fn not_in<V: Into<Value>>(self, field: &str, values: Vec<V>) -> Self {
+        self.condition(field, "$nin", values)
+    }
+
+    pub fn exists(self, field: &str, exists: bool) -> Self {
+        self.condition(field, "$exists", exists)
+    }
+
+    /// `$type`: one of "null", "boolean", "number", "string", "array" or "object"
+    pub fn type_of(self, field: &str, json_type: &str) -> Self {
+        self.condition(field, "$type", json_type)
+    }
+
+    /// `$regex`: the field must match the (Erlang) regular expression
+    pub fn regex(self, field: &str, regex: &str) -> Self {
+        self.condition(field, "$regex", regex)
+    }
+
+    /// `$size`: the array field must have the given length
+    pub fn size(self, field: &str, size: u64) -> Self {
+        self.condition(field, "$size", size)
+    }
+
+    /// `$all`: the array field must contain all the values
+    pub fn all<V: Into<Value>>(self, field: &str, values: Vec<V>) -> Self {
+        self.condition(field, "$all", values)
+    }
+
+    /// `$elemMatch`: at least one element of the array field must match the selector
+    pub fn elem_match(self, field: &str, selector: SelectorBuilder) -> Self {
+        self.condition(field, "$elemMatch", selector)
+    }
+
+    /// `$allMatch`: all elements of the array field must match the selector
+    pub fn all_match(self, field: &str, selector: SelectorBuilder) -> Self {
+        self.condition(field, "$allMatch", selector)
+    }
+
+    /// `$and`: all selectors must match
+    pub fn and(self, selectors: Vec<SelectorBuilder>) -> Self {
+        self.combination("$and", selectors)
+    }
+
+    /// `$or`: at least one of the selectors must match
+    pub fn or(self, selectors: Vec<SelectorBuilder>) -> Self {
+        self.combination("$or", selectors)
+    }
+
+    /// `$nor`: none of the selectors may match
+    pub fn nor(self, selectors: Vec<SelectorBuilder>) -> Self {
+        self.combination("$nor", selectors)
+    }
+
+    /// `$not`: the selector may not match
+    pub fn not(mut self, selector: SelectorBuilder) -> Self {
+        self.selector.insert("$not".to_string(), selector.into());
+        self
+    }
+
+    fn combination(mut self, operator: &str, selectors: Vec<SelectorBuilder>) -> Self {
+        let entry = self
+            .selector
+            .entry(operator.to_string())
+            .or_insert_with(|| Value::Array(vec![]));
+        if let Some(existing) = entry.as_array_mut() {
+            existing.extend(selectors.into_iter().map(Value::from));
+        }
+        self
+    }
+
+    /// The selector as a JSON value
+    pub fn build(&self) -> Value {
+        Value::Object(self.selector.clone())
+    }
+
+    /// A `FindQuery` with this selector
+    pub fn into_query(self) -> FindQuery {
+        self.into()
+    }
+}
+
+impl From<SelectorBuilder> for Value {
+    fn from(builder: SelectorBuilder) -> Self {
+        Value::Object(builder.selector)
+    }
+}
+
+impl From<SelectorBuilder> for FindQuery {
+    fn from(builder: SelectorBuilder) -> Self {
+        FindQuery::new(builder.into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate as couch_rs;
     use serde_json::json;
 
+    #[derive(Deserialize, couch_rs_derive::Projection)]
+    #[serde(rename_all = "camelCase")]
+    #[allow(dead_code)]
+    struct UserName {
+        #[serde(rename = "_id")]
+        id: String,
+        first_name: String,
+        #[serde(rename(deserialize = "surname"))]
+        last_name: String,
+        #[serde(skip)]
+        display: String,
+        r#type: String,
+    }
+
+    #[test]
+    fn test_derive_projection() {
+        assert_eq!(UserName::fields(), ["_id", "firstName", "surname", "type"]);
+    }
+
     #[test]
     fn test_convert_to_value() {
         let mut sort = HashMap::new();
@@ -318,7 +613,7 @@ mod tests {
         assert_eq!(selector, r#"{"thing":true}"#);
         assert_eq!(query.limit, Some(1));
         assert_eq!(query.sort.len(), 1);
-        let first_sort = query.sort.get(0).unwrap();
+        let first_sort = query.sort.first().unwrap();
         if let SortSpec::Complex(spec) = first_sort {
             assert!(spec.contains_key("thing"));
             let direction = spec.get("thing").unwrap();
@@ -327,4 +622,104 @@ mod tests {
             panic!("unexpected sort spec");
         }
     }
+
+    #[test]
+    fn test_stable_and_update() {
+        let query = FindQuery::find_all();
+        assert_eq!(query.update, None);
+        assert_eq!(query.stable, None);
+
+        let json = FindQuery::new(json!({})).stable(true).update(false).as_value();
+        assert_eq!(json, json!({"selector": {}, "stable": true, "update": false}));
+    }
+
+    #[test]
+    fn test_selector_builder() {
+        let selector = SelectorBuilder::new()
+            .gt("age", 18)
+            .lt("age", 65)
+            .regex("name", "^J")
+            .is_in("country", vec!["NL", "BE"])
+            .elem_match("tags", SelectorBuilder::new().eq("name", "rust"))
+            .or(vec![
+                SelectorBuilder::new().exists("email", true),
+                SelectorBuilder::new().ne("phone", Value::Null),
+            ])
+            .build();
+
+        assert_eq!(
+            selector,
+            json!({
+                "age": {"$gt": 18, "$lt": 65},
+                "name": {"$regex": "^J"},
+                "country": {"$in": ["NL", "BE"]},
+                "tags": {"$elemMatch": {"name": {"$eq": "rust"}}},
+                "$or": [{"email": {"$exists": true}}, {"phone": {"$ne": null}}]
+            })
+        );
+    }
+
+    #[test]
+    fn test_selector_builder_combinations() {
+        let base = SelectorBuilder::new().field("type", "user");
+        let selector = base
+            .clone()
+            .gte("type", "a")
+            .and(vec![SelectorBuilder::new().eq("a", 1)])
+            .and(vec![SelectorBuilder::new().eq("b", 2)])
+            .not(SelectorBuilder::new().eq("deleted", true));
+
+        assert_eq!(base.build(), json!({"type": "user"}));
+        assert_eq!(
+            selector.build(),
+            json!({
+                "type": {"$eq": "user", "$gte": "a"},
+                "$and": [{"a": {"$eq": 1}}, {"b": {"$eq": 2}}],
+                "$not": {"deleted": {"$eq": true}}
+            })
+        );
+
+        let query = selector.into_query().limit(5);
+        assert_eq!(query.limit, Some(5));
+        assert_eq!(query.selector["type"]["$eq"], json!("user"));
+    }
+
+    #[test]
+    fn test_parse_explain_result() {
+        let result: ExplainResult = serde_json::from_value(json!({
+            "dbname": "movies",
+            "index": {"ddoc": null, "name": "_all_docs", "type": "special", "def": {"fields": [{"_id": "asc"}]}},
+            "partitioned": false,
+            "selector": {"year": {"$gt": 2010}},
+            "opts": {"use_index": [], "bookmark": "nil", "limit": 2, "skip": 0, "sort": {}, "fields": "all_fields"},
+            "limit": 2,
+            "skip": 0,
+            "fields": "all_fields",
+            "mrargs": {"include_docs": true, "view_type": "map", "reduce": false},
+            "covering": false
+        }))
+        .unwrap();
+
+        assert!(result.is_full_scan());
+        assert_eq!(result.index.name, "_all_docs");
+        assert_eq!(result.fields, json!("all_fields"));
+        assert_eq!(result.covering, Some(false));
+    }
+
+    #[test]
+    fn test_index_fields() {
+        let query = FindQuery::new(json!({
+            "year": {"$gt": 2010},
+            "address": {"city": "Amsterdam"},
+            "$and": [{"genre": "drama"}, {"year": {"$lt": 2020}}],
+            "$or": [{"director": "Lynch"}, {"director": "Kubrick"}]
+        }))
+        .sort(vec![SortSpec::Simple("title".to_string())]);
+
+        let mut fields = query.index_fields();
+        assert_eq!(fields.remove(0), "title");
+        fields.sort();
+        assert_eq!(fields, vec!["address.city", "genre", "year"]);
+        assert!(FindQuery::new(json!({"$or": [{"a": 1}]})).index_fields().is_empty());
+    }
 }
@@ -2,16 +2,28 @@ use super::*;
 use document::DocumentId;
 use find::SortSpec;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 /// Index fields abstraction
 #[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
 pub struct IndexFields {
     pub fields: Vec<SortSpec>,
+    /// Only documents matching this selector are included in the index
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partial_filter_selector: Option<Value>,
 }
 
 impl IndexFields {
     pub fn new(fields: Vec<SortSpec>) -> IndexFields {
-        IndexFields { fields }
+        IndexFields {
+            fields,
+            partial_filter_selector: None,
+        }
+    }
+
+    pub fn partial_filter_selector(mut self, selector: Value) -> IndexFields {
+        self.partial_filter_selector = Some(selector);
+        self
     }
 }
 
@@ -23,11 +35,129 @@ pub struct Index {
     #[serde(rename = "type")]
     pub index_type: String,
     pub def: IndexFields,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partitioned: Option<bool>,
 }
 
-/// Database index list abstraction
+/// Index list abstraction
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
-pub struct DatabaseIndexList {
+pub struct IndexList {
     pub total_rows: u32,
     pub indexes: Vec<Index>,
 }
+
+/// The former name of `IndexList`, kept for backwards compatibility
+pub type DatabaseIndexList = IndexList;
+
+/// Type of a Mango index
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexType {
+    Json,
+    /// Requires the search plugin
+    Text,
+}
+
+impl IndexType {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            IndexType::Json => "json",
+            IndexType::Text => "text",
+        }
+    }
+}
+
+/// Options to create a Mango index. You can use the builder paradigm to construct these
+/// parameters easily:
+/// ```
+/// use couch_rs::types::index::{IndexOptions, IndexType};
+/// let _options = IndexOptions::default().ddoc("user-indexes").index_type(IndexType::Json);
+/// ```
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, Default)]
+pub struct IndexOptions {
+    /// The design document to create the index in; by default each index gets its own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ddoc: Option<DocumentId>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub index_type: Option<IndexType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partitioned: Option<bool>,
+}
+
+impl IndexOptions {
+    pub fn ddoc(mut self, ddoc: &str) -> Self {
+        self.ddoc = Some(ddoc.to_string());
+        self
+    }
+
+    pub fn index_type(mut self, index_type: IndexType) -> Self {
+        self.index_type = Some(index_type);
+        self
+    }
+
+    pub fn partitioned(mut self, partitioned: bool) -> Self {
+        self.partitioned = Some(partitioned);
+        self
+    }
+}
+
+/// Body of an index creation request
+#[derive(Serialize, Debug)]
+pub(crate) struct IndexRequest<'a> {
+    pub name: &'a str,
+    pub index: &'a IndexFields,
+    #[serde(flatten)]
+    pub options: &'a IndexOptions,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_index_request() {
+        let spec = IndexFields::new(vec![SortSpec::Simple("year".to_string())])
+            .partial_filter_selector(json!({"type": {"$eq": "movie"}}));
+        let options = IndexOptions::default().ddoc("movies").index_type(IndexType::Json);
+        let request = IndexRequest {
+            name: "year-index",
+            index: &spec,
+            options: &options,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "name": "year-index",
+                "index": {"fields": ["year"], "partial_filter_selector": {"type": {"$eq": "movie"}}},
+                "ddoc": "movies",
+                "type": "json"
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_index_list() {
+        let list: IndexList = serde_json::from_value(json!({
+            "total_rows": 2,
+            "indexes": [
+                {"ddoc": null, "name": "_all_docs", "type": "special", "def": {"fields": [{"_id": "asc"}]}},
+                {
+                    "ddoc": "_design/movies",
+                    "name": "year-index",
+                    "type": "json",
+                    "partitioned": false,
+                    "def": {"fields": [{"year": "asc"}], "partial_filter_selector": {"type": "movie"}}
+                }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(list.indexes.len(), 2);
+        assert_eq!(
+            list.indexes[1].def.partial_filter_selector,
+            Some(json!({"type": "movie"}))
+        );
+    }
+}
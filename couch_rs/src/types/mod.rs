@@ -1,8 +1,16 @@
 pub mod changes;
+pub mod cluster;
 pub mod design;
 pub mod document;
 pub mod find;
 pub mod index;
 pub mod query;
+pub mod replication;
+pub mod scheduler;
+pub mod search;
+pub mod security;
+pub mod stats;
 pub mod system;
+pub mod tasks;
+pub mod user;
 pub mod view;
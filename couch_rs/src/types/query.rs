@@ -2,6 +2,7 @@ use crate::document::TypedCouchDocument;
 use crate::types::view::ViewCollection;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct QueriesParams {
@@ -20,9 +21,11 @@ pub struct QueriesCollection<K: DeserializeOwned, V: DeserializeOwned, T: TypedC
     pub results: Vec<ViewCollection<K, V, T>>,
 }
 
-/// Whether or not the view in question should be updated prior to responding to the user
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
+/// Whether or not the view in question should be updated prior to responding to the user.
+/// CouchDB updates the view first by default (`True`).
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, Default)]
 pub enum UpdateView {
+    #[default]
     #[serde(rename = "true")]
     True,
     #[serde(rename = "false")]
@@ -37,7 +40,7 @@ pub enum UpdateView {
 /// use couch_rs::types::query::QueryParams;
 /// let _qp = QueryParams::default().group(true).conflicts(false).start_key("1");
 /// ```
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct QueryParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub conflicts: Option<bool>,
@@ -106,35 +109,6 @@ pub struct QueryParams {
     pub update_seq: Option<bool>,
 }
 
-impl Default for QueryParams {
-    fn default() -> Self {
-        QueryParams {
-            conflicts: None,
-            descending: None,
-            end_key: None,
-            end_key_doc_id: None,
-            group: None,
-            group_level: None,
-            include_docs: None,
-            attachments: None,
-            att_encoding_info: None,
-            inclusive_end: None,
-            key: None,
-            keys: vec![],
-            limit: None,
-            reduce: None,
-            skip: None,
-            sorted: None,
-            stable: None,
-            stale: None,
-            start_key: None,
-            start_key_doc_id: None,
-            update: None,
-            update_seq: None,
-        }
-    }
-}
-
 impl QueryParams {
     pub fn from_keys(keys: Vec<String>) -> Self {
         QueryParams {
@@ -218,6 +192,8 @@ impl QueryParams {
         self
     }
 
+    /// Whether to answer from the same set of shard replicas for every request. Defaults to
+    /// `false`, like CouchDB.
     pub fn stable(mut self, stable: bool) -> Self {
         self.stable = Some(stable);
         self
@@ -233,6 +209,9 @@ impl QueryParams {
         self
     }
 
+    /// Whether to update the view before responding: `False` skips the index rebuild for
+    /// latency-sensitive reads and returns possibly stale results, `Lazy` also starts an update
+    /// after the response. Defaults to `UpdateView::True`, like CouchDB.
     pub fn update(mut self, update: UpdateView) -> Self {
         self.update = Some(update);
         self
@@ -244,9 +223,233 @@ impl QueryParams {
     }
 }
 
+/// View query parameters, with keys as JSON values, so views with numeric or compound (array)
+/// keys can be queried. You can use the builder paradigm to construct these parameters easily:
+/// [views.html](https://docs.couchdb.org/en/stable/api/ddoc/views.html)
+/// ```
+/// use couch_rs::types::query::ViewQuery;
+/// use serde_json::json;
+/// let _query = ViewQuery::default()
+///     .start_key(json!([2021, 1]))
+///     .end_key(json!([2021, 12, {}]))
+///     .descending(false)
+///     .limit(100);
+/// ```
+#[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ViewQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflicts: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub descending: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_key: Option<Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_key_doc_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_level: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_docs: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub att_encoding_info: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inclusive_end: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keys: Option<Vec<Value>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reduce: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skip: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sorted: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stable: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_key: Option<Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_key_doc_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update: Option<UpdateView>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_seq: Option<bool>,
+}
+
+impl ViewQuery {
+    pub fn conflicts(mut self, conflicts: bool) -> Self {
+        self.conflicts = Some(conflicts);
+        self
+    }
+
+    pub fn descending(mut self, descending: bool) -> Self {
+        self.descending = Some(descending);
+        self
+    }
+
+    pub fn end_key(mut self, end_key: impl Into<Value>) -> Self {
+        self.end_key = Some(end_key.into());
+        self
+    }
+
+    pub fn end_key_doc_id(mut self, end_key_doc_id: &str) -> Self {
+        self.end_key_doc_id = Some(end_key_doc_id.to_string());
+        self
+    }
+
+    pub fn group(mut self, group: bool) -> Self {
+        self.group = Some(group);
+        self
+    }
+
+    pub fn group_level(mut self, group_level: u32) -> Self {
+        self.group_level = Some(group_level);
+        self
+    }
+
+    pub fn include_docs(mut self, include_docs: bool) -> Self {
+        self.include_docs = Some(include_docs);
+        self
+    }
+
+    pub fn attachments(mut self, attachments: bool) -> Self {
+        self.attachments = Some(attachments);
+        self
+    }
+
+    pub fn att_encoding_info(mut self, att_encoding_info: bool) -> Self {
+        self.att_encoding_info = Some(att_encoding_info);
+        self
+    }
+
+    pub fn inclusive_end(mut self, inclusive_end: bool) -> Self {
+        self.inclusive_end = Some(inclusive_end);
+        self
+    }
+
+    pub fn key(mut self, key: impl Into<Value>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    pub fn keys<K: Into<Value>>(mut self, keys: Vec<K>) -> Self {
+        self.keys = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn reduce(mut self, reduce: bool) -> Self {
+        self.reduce = Some(reduce);
+        self
+    }
+
+    pub fn skip(mut self, skip: u64) -> Self {
+        self.skip = Some(skip);
+        self
+    }
+
+    pub fn sorted(mut self, sorted: bool) -> Self {
+        self.sorted = Some(sorted);
+        self
+    }
+
+    /// Whether to answer from the same set of shard replicas for every request. Defaults to
+    /// `false`, like CouchDB.
+    pub fn stable(mut self, stable: bool) -> Self {
+        self.stable = Some(stable);
+        self
+    }
+
+    pub fn start_key(mut self, start_key: impl Into<Value>) -> Self {
+        self.start_key = Some(start_key.into());
+        self
+    }
+
+    pub fn start_key_doc_id(mut self, start_key_doc_id: &str) -> Self {
+        self.start_key_doc_id = Some(start_key_doc_id.to_string());
+        self
+    }
+
+    /// Whether to update the view before responding: `False` skips the index rebuild for
+    /// latency-sensitive reads and returns possibly stale results, `Lazy` also starts an update
+    /// after the response. Defaults to `UpdateView::True`, like CouchDB.
+    pub fn update(mut self, update: UpdateView) -> Self {
+        self.update = Some(update);
+        self
+    }
+
+    pub fn update_seq(mut self, update_seq: bool) -> Self {
+        self.update_seq = Some(update_seq);
+        self
+    }
+}
+
+/// Converts `QueryParams`, where all keys are strings
+impl From<QueryParams> for ViewQuery {
+    fn from(params: QueryParams) -> Self {
+        ViewQuery {
+            conflicts: params.conflicts,
+            descending: params.descending,
+            end_key: params.end_key.map(Value::String),
+            end_key_doc_id: params.end_key_doc_id,
+            group: params.group,
+            group_level: params.group_level,
+            include_docs: params.include_docs,
+            attachments: params.attachments,
+            att_encoding_info: params.att_encoding_info,
+            inclusive_end: params.inclusive_end,
+            key: params.key.map(Value::String),
+            keys: if params.keys.is_empty() {
+                None
+            } else {
+                Some(params.keys.into_iter().map(Value::String).collect())
+            },
+            limit: params.limit,
+            reduce: params.reduce,
+            skip: params.skip,
+            sorted: params.sorted,
+            stable: params.stable,
+            start_key: params.start_key.map(Value::String),
+            start_key_doc_id: params.start_key_doc_id,
+            update: params.update,
+            update_seq: params.update_seq,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
 
     #[test]
     fn test_query_params_builder_paradigm() {
@@ -260,4 +463,48 @@ mod tests {
         let str_val = serde_json::to_string(&qp).expect("can not convert to string");
         assert!(str_val.contains(r#""update":"lazy""#))
     }
+
+    #[test]
+    fn test_stable_and_update() {
+        let json = serde_json::to_value(QueryParams::default()).unwrap();
+        assert_eq!(json, json!({}));
+        assert_eq!(UpdateView::default(), UpdateView::True);
+
+        let json = serde_json::to_value(ViewQuery::default().stable(true).update(UpdateView::False)).unwrap();
+        assert_eq!(json, json!({"stable": true, "update": "false"}));
+    }
+
+    #[test]
+    fn test_view_query_json_keys() {
+        let query = ViewQuery::default()
+            .start_key(json!([2021, 1]))
+            .end_key(json!([2021, 12, {}]))
+            .start_key_doc_id("a")
+            .inclusive_end(false)
+            .group_level(2)
+            .limit(10);
+        assert_eq!(
+            serde_json::to_value(&query).unwrap(),
+            json!({
+                "start_key": [2021, 1],
+                "end_key": [2021, 12, {}],
+                "start_key_doc_id": "a",
+                "inclusive_end": false,
+                "group_level": 2,
+                "limit": 10
+            })
+        );
+
+        let query = ViewQuery::default().key(42).keys(vec!["a", "b"]);
+        assert_eq!(query.key, Some(json!(42)));
+        assert_eq!(query.keys, Some(vec![json!("a"), json!("b")]));
+    }
+
+    #[test]
+    fn test_view_query_from_query_params() {
+        let query: ViewQuery = QueryParams::default().start_key("1").keys(vec!["a".to_string()]).into();
+        assert_eq!(query.start_key, Some(json!("1")));
+        assert_eq!(query.keys, Some(vec![json!("a")]));
+        assert_eq!(ViewQuery::from(QueryParams::default()), ViewQuery::default());
+    }
 }
@@ -0,0 +1,367 @@
+use crate::document::TypedCouchDocument;
+use crate::types::document::DocumentId;
+use base64::write::EncoderWriter as Base64Encoder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// The source or target of a replication: the name of a local database, or the URL of a
+/// (remote) database, optionally with credentials.
+/// ```
+/// use couch_rs::types::replication::ReplicationEndpoint;
+/// let _local = ReplicationEndpoint::from("local_db");
+/// let _remote = ReplicationEndpoint::new("https://example.com/remote_db").basic_auth("user", "secret");
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(from = "EndpointRepr", into = "EndpointRepr")]
+pub struct ReplicationEndpoint {
+    pub url: String,
+    /// Additional headers sent with every request to this endpoint, e.g. `Authorization`
+    pub headers: HashMap<String, String>,
+}
+
+impl ReplicationEndpoint {
+    pub fn new(url: &str) -> Self {
+        ReplicationEndpoint {
+            url: url.to_string(),
+            headers: HashMap::new(),
+        }
+    }
+
+    /// Authenticate to this endpoint with basic authentication. The credentials are sent
+    /// through the `Authorization` header, which is supported by all versions of CouchDB, and
+    /// keeps them out of the URL.
+    pub fn basic_auth(self, username: &str, password: &str) -> Self {
+        let mut header_value = b"Basic ".to_vec();
+        {
+            let mut encoder = Base64Encoder::new(&mut header_value, base64::STANDARD);
+            // The unwrap here is fine because Vec::write* is infallible.
+            write!(encoder, "{}:{}", username, password).unwrap();
+        }
+        // base64 output is always valid UTF-8
+        self.header("Authorization", &String::from_utf8(header_value).unwrap())
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_string(), value.to_string());
+        self
+    }
+}
+
+impl From<&str> for ReplicationEndpoint {
+    fn from(url: &str) -> Self {
+        ReplicationEndpoint::new(url)
+    }
+}
+
+impl From<String> for ReplicationEndpoint {
+    fn from(url: String) -> Self {
+        ReplicationEndpoint {
+            url,
+            headers: HashMap::new(),
+        }
+    }
+}
+
+/// CouchDB accepts (and returns) endpoints as plain URLs or as objects
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum EndpointRepr {
+    Url(String),
+    Object {
+        url: String,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        headers: HashMap<String, String>,
+    },
+}
+
+impl From<EndpointRepr> for ReplicationEndpoint {
+    fn from(repr: EndpointRepr) -> Self {
+        match repr {
+            EndpointRepr::Url(url) => url.into(),
+            EndpointRepr::Object { url, headers } => ReplicationEndpoint { url, headers },
+        }
+    }
+}
+
+impl From<ReplicationEndpoint> for EndpointRepr {
+    fn from(endpoint: ReplicationEndpoint) -> Self {
+        if endpoint.headers.is_empty() {
+            EndpointRepr::Url(endpoint.url)
+        } else {
+            EndpointRepr::Object {
+                url: endpoint.url,
+                headers: endpoint.headers,
+            }
+        }
+    }
+}
+
+/// Replication options. You can use the builder paradigm to construct these options easily:
+/// [replicate](https://docs.couchdb.org/en/stable/api/server/common.html#replicate)
+/// ```
+/// use couch_rs::types::replication::ReplicateOptions;
+/// let _options = ReplicateOptions::default().create_target(true).continuous(true);
+/// ```
+#[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ReplicateOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continuous: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub create_target: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc_ids: Option<Vec<String>>,
+
+    /// A filter function in a design document of the source database, in the form `ddoc/name`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+
+    /// Arguments for the filter function
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_params: Option<HashMap<String, String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since_seq: Option<Value>,
+}
+
+impl ReplicateOptions {
+    pub fn cancel(mut self, cancel: bool) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    pub fn continuous(mut self, continuous: bool) -> Self {
+        self.continuous = Some(continuous);
+        self
+    }
+
+    pub fn create_target(mut self, create_target: bool) -> Self {
+        self.create_target = Some(create_target);
+        self
+    }
+
+    pub fn doc_ids(mut self, doc_ids: Vec<String>) -> Self {
+        self.doc_ids = Some(doc_ids);
+        self
+    }
+
+    pub fn filter(mut self, filter: &str) -> Self {
+        self.filter = Some(filter.to_string());
+        self
+    }
+
+    pub fn query_params(mut self, query_params: HashMap<String, String>) -> Self {
+        self.query_params = Some(query_params);
+        self
+    }
+
+    pub fn selector(mut self, selector: Value) -> Self {
+        self.selector = Some(selector);
+        self
+    }
+
+    pub fn since_seq(mut self, since_seq: Value) -> Self {
+        self.since_seq = Some(since_seq);
+        self
+    }
+}
+
+/// Request body of the `_replicate` endpoint
+#[derive(Serialize, Debug)]
+pub(crate) struct ReplicateRequest<'a> {
+    pub source: ReplicationEndpoint,
+    pub target: ReplicationEndpoint,
+    #[serde(flatten)]
+    pub options: &'a ReplicateOptions,
+}
+
+/// Replication response abstraction
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ReplicationResponse {
+    pub ok: bool,
+    /// The history of the replication sessions; only returned by one-off replications
+    #[serde(default)]
+    pub history: Vec<ReplicationHistory>,
+    /// True when there was nothing to replicate
+    #[serde(default)]
+    pub no_changes: bool,
+    pub session_id: Option<String>,
+    pub source_last_seq: Option<Value>,
+    pub replication_id_version: Option<u32>,
+    /// The id of a continuous replication
+    #[serde(rename = "_local_id")]
+    pub local_id: Option<String>,
+}
+
+/// A persistent replication, stored as a document in the `_replicator` database. See
+/// [replicator database](https://docs.couchdb.org/en/stable/replication/replicator.html) for details.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ReplicationDocument {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub _id: DocumentId,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub _rev: String,
+    pub source: ReplicationEndpoint,
+    pub target: ReplicationEndpoint,
+    #[serde(flatten)]
+    pub options: ReplicateOptions,
+}
+
+impl ReplicationDocument {
+    /// Create a new replication document. Provide an `id` to be able to find the replication
+    /// back, or leave it empty to let CouchDB generate one.
+    pub fn new(
+        id: &str,
+        source: impl Into<ReplicationEndpoint>,
+        target: impl Into<ReplicationEndpoint>,
+        options: ReplicateOptions,
+    ) -> Self {
+        ReplicationDocument {
+            _id: id.to_string(),
+            _rev: String::new(),
+            source: source.into(),
+            target: target.into(),
+            options,
+        }
+    }
+}
+
+impl TypedCouchDocument for ReplicationDocument {
+    fn get_id(&self) -> Cow<'_, str> {
+        Cow::from(&self._id)
+    }
+
+    fn get_rev(&self) -> Cow<'_, str> {
+        Cow::from(&self._rev)
+    }
+
+    fn set_rev(&mut self, rev: &str) {
+        self._rev = rev.to_string();
+    }
+
+    fn set_id(&mut self, id: &str) {
+        self._id = id.to_string();
+    }
+
+    fn merge_ids(&mut self, other: &Self) {
+        self.set_id(&other.get_id());
+        self.set_rev(&other.get_rev());
+    }
+}
+
+/// Replication session history abstraction
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ReplicationHistory {
+    pub session_id: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub start_last_seq: Value,
+    pub end_last_seq: Value,
+    pub recorded_seq: Value,
+    #[serde(default)]
+    pub docs_read: u64,
+    #[serde(default)]
+    pub docs_written: u64,
+    #[serde(default)]
+    pub doc_write_failures: u64,
+    #[serde(default)]
+    pub missing_checked: u64,
+    #[serde(default)]
+    pub missing_found: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_replicate_request() {
+        let options = ReplicateOptions::default()
+            .create_target(true)
+            .doc_ids(vec!["a".to_string()]);
+        let request = ReplicateRequest {
+            source: "source_db".into(),
+            target: "http://localhost:5984/target_db".into(),
+            options: &options,
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "source": "source_db",
+                "target": "http://localhost:5984/target_db",
+                "create_target": true,
+                "doc_ids": ["a"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_replication_document() {
+        let doc = ReplicationDocument::new(
+            "my_replication",
+            "source_db",
+            ReplicationEndpoint::new("https://example.com/target_db").basic_auth("user", "secret"),
+            ReplicateOptions::default().continuous(true),
+        );
+        let value = serde_json::to_value(&doc).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "_id": "my_replication",
+                "source": "source_db",
+                "target": {
+                    "url": "https://example.com/target_db",
+                    "headers": {"Authorization": "Basic dXNlcjpzZWNyZXQ="}
+                },
+                "continuous": true
+            })
+        );
+
+        let parsed: ReplicationDocument = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, doc);
+    }
+
+    #[test]
+    fn test_parse_replication_response() {
+        let response: ReplicationResponse = serde_json::from_value(json!({
+            "history": [{
+                "doc_write_failures": 0,
+                "docs_read": 10,
+                "docs_written": 10,
+                "end_last_seq": 28,
+                "end_time": "Sun, 11 Aug 2013 20:38:50 GMT",
+                "missing_checked": 10,
+                "missing_found": 10,
+                "recorded_seq": 28,
+                "session_id": "142a35854a08e205c47174d91b1f9628",
+                "start_last_seq": 1,
+                "start_time": "Sun, 11 Aug 2013 20:38:50 GMT"
+            }],
+            "ok": true,
+            "replication_id_version": 3,
+            "session_id": "142a35854a08e205c47174d91b1f9628",
+            "source_last_seq": 28
+        }))
+        .unwrap();
+        assert!(response.ok);
+        assert_eq!(response.history.len(), 1);
+        assert_eq!(response.history[0].docs_written, 10);
+
+        let response: ReplicationResponse =
+            serde_json::from_value(json!({"ok": true, "_local_id": "0a81b645497e6270611ec3419767a584+continuous"}))
+                .unwrap();
+        assert!(response.history.is_empty());
+        assert!(response.local_id.is_some());
+    }
+}
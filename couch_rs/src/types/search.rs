@@ -0,0 +1,273 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// A full-text search index of a design document, for the search of CouchDB 3.x and Cloudant.
+/// The index function calls `index(name, value, options)` for the fields of a document. See
+/// [search](https://docs.couchdb.org/en/stable/ddocs/search.html) for details.
+/// ```
+/// use couch_rs::types::search::SearchIndex;
+/// let _index = SearchIndex::new("function(doc) { index('title', doc.title, {store: true}); }").analyzer("english");
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct SearchIndex {
+    /// The analyzer, e.g. `"standard"`, or an object for a `perfield` analyzer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analyzer: Option<Value>,
+    pub index: String,
+}
+
+impl SearchIndex {
+    pub fn new(index: &str) -> Self {
+        SearchIndex {
+            analyzer: None,
+            index: index.to_string(),
+        }
+    }
+
+    pub fn analyzer(mut self, analyzer: impl Into<Value>) -> Self {
+        self.analyzer = Some(analyzer.into());
+        self
+    }
+}
+
+/// Search query parameters, see `Database::search`. The query uses the Lucene syntax, e.g.
+/// `title:couch* AND year:[2010 TO 2020]`. You can use the builder paradigm to construct these
+/// parameters easily:
+/// ```
+/// use couch_rs::types::search::SearchQuery;
+/// let _query = SearchQuery::new("title:couch*")
+///     .limit(10)
+///     .sort("-year<number>")
+///     .counts(vec!["genre".to_string()])
+///     .highlight_fields(vec!["title".to_string()]);
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct SearchQuery {
+    pub query: String,
+
+    /// Continue from the bookmark of a previous result
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bookmark: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+
+    /// A field, or a list of fields, e.g. `"-year<number>"` to sort by year, descending
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_docs: Option<bool>,
+
+    /// The stored fields to return, instead of all of them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_fields: Option<Vec<String>>,
+
+    /// The fields to count the distinct values of, in `SearchResult::counts`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub counts: Option<Vec<String>>,
+
+    /// Ranges to count the values of numeric fields in, e.g.
+    /// `{"price": {"cheap": "[0 TO 100]", "expensive": "{100 TO Infinity}"}}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ranges: Option<Value>,
+
+    /// Restricts the results to documents with these field values, as `[field, value]` pairs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drilldown: Option<Vec<Vec<String>>>,
+
+    /// The fields to highlight the matches of, in `SearchRow::highlights`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_fields: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_pre_tag: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_post_tag: Option<String>,
+
+    /// The number of fragments per highlighted field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_number: Option<u32>,
+
+    /// The number of characters per fragment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_size: Option<u32>,
+}
+
+impl SearchQuery {
+    pub fn new(query: &str) -> Self {
+        SearchQuery {
+            query: query.to_string(),
+            ..SearchQuery::default()
+        }
+    }
+
+    pub fn bookmark(mut self, bookmark: &str) -> Self {
+        self.bookmark = Some(bookmark.to_string());
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn sort(mut self, sort: impl Into<Value>) -> Self {
+        self.sort = Some(sort.into());
+        self
+    }
+
+    pub fn include_docs(mut self, include_docs: bool) -> Self {
+        self.include_docs = Some(include_docs);
+        self
+    }
+
+    pub fn include_fields(mut self, fields: Vec<String>) -> Self {
+        self.include_fields = Some(fields);
+        self
+    }
+
+    pub fn counts(mut self, fields: Vec<String>) -> Self {
+        self.counts = Some(fields);
+        self
+    }
+
+    pub fn ranges(mut self, ranges: Value) -> Self {
+        self.ranges = Some(ranges);
+        self
+    }
+
+    /// Only return documents where `field` has `value`; multiple drilldowns are combined with AND
+    pub fn drilldown(mut self, field: &str, value: &str) -> Self {
+        self.drilldown
+            .get_or_insert_with(Vec::new)
+            .push(vec![field.to_string(), value.to_string()]);
+        self
+    }
+
+    pub fn highlight_fields(mut self, fields: Vec<String>) -> Self {
+        self.highlight_fields = Some(fields);
+        self
+    }
+
+    /// The tags around the highlighted matches, `<em>` and `</em>` by default
+    pub fn highlight_tags(mut self, pre_tag: &str, post_tag: &str) -> Self {
+        self.highlight_pre_tag = Some(pre_tag.to_string());
+        self.highlight_post_tag = Some(post_tag.to_string());
+        self
+    }
+
+    pub fn highlight_number(mut self, number: u32) -> Self {
+        self.highlight_number = Some(number);
+        self
+    }
+
+    pub fn highlight_size(mut self, size: u32) -> Self {
+        self.highlight_size = Some(size);
+        self
+    }
+}
+
+/// The result of `Database::search`
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(bound(deserialize = "T: DeserializeOwned"))]
+pub struct SearchResult<T = Value> {
+    /// The number of matching documents, which can be more than the rows of this page
+    pub total_rows: u64,
+    /// Pass this to `SearchQuery::bookmark` for the next page
+    #[serde(default)]
+    pub bookmark: Option<String>,
+    pub rows: Vec<SearchRow<T>>,
+    /// The number of documents per value of the fields of `SearchQuery::counts`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub counts: HashMap<String, HashMap<String, u64>>,
+    /// The number of documents per range of `SearchQuery::ranges`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub ranges: HashMap<String, HashMap<String, u64>>,
+}
+
+/// A document that matches a search
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(bound(deserialize = "T: DeserializeOwned"))]
+pub struct SearchRow<T = Value> {
+    pub id: String,
+    /// The values the row is sorted by, by default the relevance
+    #[serde(default)]
+    pub order: Vec<Value>,
+    /// The stored fields of the index
+    #[serde(default)]
+    pub fields: Map<String, Value>,
+    /// The document, when the query includes the documents
+    #[serde(default = "Option::default")]
+    pub doc: Option<T>,
+    /// The highlighted fragments per field of `SearchQuery::highlight_fields`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub highlights: HashMap<String, Vec<String>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_search_query() {
+        let query = SearchQuery::new("title:couch*")
+            .limit(10)
+            .sort(json!(["-year<number>", "title<string>"]))
+            .include_docs(true)
+            .counts(vec!["genre".to_string()])
+            .drilldown("genre", "database")
+            .highlight_fields(vec!["title".to_string()])
+            .highlight_tags("<b>", "</b>");
+        assert_eq!(
+            serde_json::to_value(&query).unwrap(),
+            json!({
+                "query": "title:couch*",
+                "limit": 10,
+                "sort": ["-year<number>", "title<string>"],
+                "include_docs": true,
+                "counts": ["genre"],
+                "drilldown": [["genre", "database"]],
+                "highlight_fields": ["title"],
+                "highlight_pre_tag": "<b>",
+                "highlight_post_tag": "</b>"
+            })
+        );
+    }
+
+    #[test]
+    fn test_search_result() {
+        let result: SearchResult = serde_json::from_value(json!({
+            "total_rows": 2,
+            "bookmark": "g1AAAAA",
+            "rows": [{
+                "id": "couchdb",
+                "order": [1.2, 0],
+                "fields": {"title": "CouchDB: The Definitive Guide"},
+                "doc": {"_id": "couchdb", "_rev": "1-a", "title": "CouchDB: The Definitive Guide"},
+                "highlights": {"title": ["<em>CouchDB</em>: The Definitive Guide"]}
+            }],
+            "counts": {"genre": {"database": 2}}
+        }))
+        .unwrap();
+        assert_eq!(result.total_rows, 2);
+        assert_eq!(result.bookmark.as_deref(), Some("g1AAAAA"));
+        assert_eq!(result.rows[0].fields["title"], json!("CouchDB: The Definitive Guide"));
+        assert_eq!(result.rows[0].doc.as_ref().unwrap()["_id"], json!("couchdb"));
+        assert_eq!(result.rows[0].highlights["title"].len(), 1);
+        assert_eq!(result.counts["genre"]["database"], 2);
+        assert!(result.ranges.is_empty());
+
+        let without_docs: SearchResult = serde_json::from_value(json!({
+            "total_rows": 0,
+            "rows": [{"id": "a", "order": [0.5]}]
+        }))
+        .unwrap();
+        assert!(without_docs.rows[0].doc.is_none());
+        assert!(without_docs.bookmark.is_none());
+    }
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+/// The security object of a database, defining who can administer and who can access it. A
+/// database without admins and members is public. See
+/// [security](https://docs.couchdb.org/en/stable/api/database/security.html) for details.
+/// ```
+/// use couch_rs::types::security::{SecurityMembers, SecurityObject};
+/// let _security = SecurityObject::default()
+///     .admins(SecurityMembers::default().role("ops"))
+///     .members(SecurityMembers::default().name("john").role("users"));
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct SecurityObject {
+    /// Users and roles that can administer the database, e.g. create design documents
+    #[serde(default)]
+    pub admins: SecurityMembers,
+    /// Users and roles that can read and write documents
+    #[serde(default)]
+    pub members: SecurityMembers,
+}
+
+impl SecurityObject {
+    pub fn admins(mut self, admins: SecurityMembers) -> Self {
+        self.admins = admins;
+        self
+    }
+
+    pub fn members(mut self, members: SecurityMembers) -> Self {
+        self.members = members;
+        self
+    }
+}
+
+/// The user names and roles of the admins or members of a database
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct SecurityMembers {
+    #[serde(default)]
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+impl SecurityMembers {
+    pub fn name(mut self, name: &str) -> Self {
+        self.names.push(name.to_string());
+        self
+    }
+
+    pub fn role(mut self, role: &str) -> Self {
+        self.roles.push(role.to_string());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{from_value, json, to_value};
+
+    #[test]
+    fn test_security_object() {
+        let security = SecurityObject::default().members(SecurityMembers::default().name("john").role("users"));
+        assert_eq!(
+            to_value(&security).unwrap(),
+            json!({
+                "admins": { "names": [], "roles": [] },
+                "members": { "names": ["john"], "roles": ["users"] }
+            })
+        );
+
+        // a new database has an empty security object
+        let empty: SecurityObject = from_value(json!({})).unwrap();
+        assert_eq!(empty, SecurityObject::default());
+    }
+}
@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The statistics of a node, grouped by subsystem, e.g. `couchdb` and `fabric`. See
+/// [_stats](https://docs.couchdb.org/en/stable/api/server/common.html#node-node-name-stats) for
+/// the available metrics.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct NodeStats {
+    #[serde(flatten)]
+    pub groups: HashMap<String, Stat>,
+}
+
+impl NodeStats {
+    /// Gets a metric by its path, e.g. `["couchdb", "httpd", "requests"]`
+    pub fn get(&self, path: &[&str]) -> Option<&Metric> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(self.groups.get(*first)?, |stat, name| match stat {
+                Stat::Group(group) => group.get(*name),
+                Stat::Metric(_) => None,
+            })
+            .and_then(|stat| match stat {
+                Stat::Metric(metric) => Some(metric),
+                Stat::Group(_) => None,
+            })
+    }
+
+    /// The number of HTTP requests handled by the node
+    pub fn requests(&self) -> Option<f64> {
+        self.get(&["couchdb", "httpd", "requests"])?.value.as_number()
+    }
+
+    /// The latency of the HTTP requests, in milliseconds
+    pub fn request_time(&self) -> Option<&Histogram> {
+        self.get(&["couchdb", "request_time"])?.value.as_histogram()
+    }
+}
+
+/// A metric, or a group of metrics
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(untagged)]
+pub enum Stat {
+    Metric(Metric),
+    Group(HashMap<String, Stat>),
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Metric {
+    pub value: MetricValue,
+    #[serde(rename = "type")]
+    pub metric_type: MetricType,
+    #[serde(default)]
+    pub desc: String,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricType {
+    Counter,
+    Gauge,
+    Histogram,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(untagged)]
+pub enum MetricValue {
+    Number(f64),
+    Histogram(Histogram),
+}
+
+impl MetricValue {
+    /// The value of a counter or gauge
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            MetricValue::Number(value) => Some(*value),
+            MetricValue::Histogram(_) => None,
+        }
+    }
+
+    pub fn as_histogram(&self) -> Option<&Histogram> {
+        match self {
+            MetricValue::Histogram(histogram) => Some(histogram),
+            MetricValue::Number(_) => None,
+        }
+    }
+}
+
+/// The value of a histogram metric, over the sample period of the node
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Histogram {
+    pub n: u64,
+    pub min: f64,
+    pub max: f64,
+    pub arithmetic_mean: f64,
+    pub geometric_mean: f64,
+    pub harmonic_mean: f64,
+    pub median: f64,
+    pub variance: f64,
+    pub standard_deviation: f64,
+    pub skewness: f64,
+    pub kurtosis: f64,
+    /// Pairs of percentile and value, e.g. `(99.0, 12.5)`
+    pub percentile: Vec<(f64, f64)>,
+    /// Pairs of bin and count
+    pub histogram: Vec<(f64, u64)>,
+}
+
+impl Histogram {
+    /// The value at the given percentile, e.g. `99.0`, when reported by the node
+    pub fn percentile(&self, percentile: f64) -> Option<f64> {
+        self.percentile
+            .iter()
+            .find(|(p, _)| (*p - percentile).abs() < f64::EPSILON)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// The memory and process statistics of the Erlang VM of a node. See
+/// [_system](https://docs.couchdb.org/en/stable/api/server/common.html#node-node-name-system)
+/// for details.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SystemStats {
+    /// Uptime in seconds
+    pub uptime: u64,
+    pub memory: MemoryStats,
+    pub run_queue: u64,
+    pub ets_table_count: u64,
+    pub context_switches: u64,
+    pub reductions: u64,
+    pub garbage_collection_count: u64,
+    pub words_reclaimed: u64,
+    pub io_input: u64,
+    pub io_output: u64,
+    pub os_proc_count: u64,
+    pub stale_proc_count: u64,
+    pub process_count: u64,
+    pub process_limit: u64,
+    pub internal_replication_jobs: u64,
+    pub message_queues: HashMap<String, Value>,
+    pub distribution: HashMap<String, Value>,
+}
+
+/// Memory usage of the Erlang VM, in bytes
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+#[serde(default)]
+pub struct MemoryStats {
+    pub other: u64,
+    pub atom: u64,
+    pub atom_used: u64,
+    pub processes: u64,
+    pub processes_used: u64,
+    pub binary: u64,
+    pub code: u64,
+    pub ets: u64,
+}
+
+impl MemoryStats {
+    pub fn total(&self) -> u64 {
+        self.other + self.atom + self.processes + self.binary + self.code + self.ets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn test_node_stats() {
+        let stats: NodeStats = from_value(json!({
+            "couchdb": {
+                "httpd": {
+                    "requests": { "value": 42, "type": "counter", "desc": "number of HTTP requests" }
+                },
+                "request_time": {
+                    "value": {
+                        "min": 1.5,
+                        "max": 20.0,
+                        "arithmetic_mean": 5.0,
+                        "median": 4.0,
+                        "n": 10,
+                        "percentile": [[50, 4.0], [99, 19.5]],
+                        "histogram": [[0, 5], [10, 5]]
+                    },
+                    "type": "histogram",
+                    "desc": "length of a request inside CouchDB without MochiWeb"
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(stats.requests(), Some(42.0));
+        let request_time = stats.request_time().unwrap();
+        assert_eq!(request_time.n, 10);
+        assert_eq!(request_time.percentile(99.0), Some(19.5));
+        assert!(stats.get(&["couchdb", "httpd"]).is_none());
+        assert!(stats.get(&["fabric"]).is_none());
+    }
+}
@@ -1,4 +1,6 @@
+use crate::types::changes::Seq;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Couch vendor abstraction
 #[derive(Serialize, Deserialize, Debug)]
@@ -25,8 +27,15 @@ pub struct CouchResponse {
     pub reason: Option<String>,
 }
 
-/// Cluster information
+/// UUIDs generated by the server
 #[derive(Serialize, Deserialize, Debug)]
+pub struct Uuids {
+    pub uuids: Vec<String>,
+}
+
+/// Cluster information: the number of shards (`q`) and replicas (`n`) of a database, and the
+/// default read (`r`) and write (`w`) quorums
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ClusterInfo {
     pub n: u32,
     pub q: u32,
@@ -34,22 +43,26 @@ pub struct ClusterInfo {
     pub w: u32,
 }
 
-/// Size information
-#[derive(Serialize, Deserialize, Debug)]
+/// Size information, in bytes
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct SizeInfo {
+    /// The size of the live data in the database files
     pub active: u64,
+    /// The uncompressed size of the documents and attachments
     pub external: u64,
+    /// The size of the database files on disk
     pub file: u64,
 }
 
-/// Database information
-#[derive(Serialize, Deserialize, Debug)]
+/// Database properties
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
 pub struct DbProperties {
-    partitioned: Option<bool>,
+    pub partitioned: Option<bool>,
 }
 
 /// Database information
-#[derive(Serialize, Deserialize, Debug)]
+/// See [common](https://docs.couchdb.org/en/stable/api/database/common.html#get--db) for details.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DbInfo {
     pub cluster: ClusterInfo,
     pub compact_running: bool,
@@ -58,8 +71,205 @@ pub struct DbInfo {
     pub doc_count: u64,
     pub doc_del_count: u64,
     pub instance_start_time: String,
-    pub purge_seq: String,
+    pub purge_seq: Seq,
     pub sizes: SizeInfo,
-    pub update_seq: String,
+    /// The current sequence, e.g. to follow the changes from now on
+    pub update_seq: Seq,
+    #[serde(default)]
     pub props: DbProperties,
 }
+
+/// Size information of a partition
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PartitionSizeInfo {
+    pub active: u64,
+    pub external: u64,
+}
+
+/// Partition information
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PartitionInfo {
+    pub db_name: String,
+    pub partition: String,
+    pub doc_count: u64,
+    pub doc_del_count: u64,
+    pub sizes: PartitionSizeInfo,
+}
+
+/// The information of one of the databases of `Client::dbs_info`: the `info`, or the `error`
+/// when the database does not exist
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DbsInfo {
+    pub key: String,
+    pub info: Option<DbInfo>,
+    pub error: Option<String>,
+}
+
+/// Options to page through the databases of `Client::all_dbs`, in the order of their names:
+/// ```
+/// use couch_rs::types::system::AllDbsOptions;
+/// let _options = AllDbsOptions::default().start_key("tenant-a").end_key("tenant-z").limit(100);
+/// ```
+/// See [all_dbs](https://docs.couchdb.org/en/stable/api/server/common.html#all-dbs) for details.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, Default)]
+pub struct AllDbsOptions {
+    /// The name to start at, inclusive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_key: Option<String>,
+    /// The name to end at, inclusive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_key: Option<String>,
+    /// The maximum number of names
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// The number of names to skip
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skip: Option<u64>,
+    /// Whether to return the names in reverse order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub descending: Option<bool>,
+}
+
+impl AllDbsOptions {
+    pub fn start_key(mut self, start_key: &str) -> Self {
+        self.start_key = Some(start_key.to_string());
+        self
+    }
+
+    pub fn end_key(mut self, end_key: &str) -> Self {
+        self.end_key = Some(end_key.to_string());
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn skip(mut self, skip: u64) -> Self {
+        self.skip = Some(skip);
+        self
+    }
+
+    pub fn descending(mut self, descending: bool) -> Self {
+        self.descending = Some(descending);
+        self
+    }
+
+    /// The options as query string parameters, with the keys as JSON strings
+    pub(crate) fn query_params(&self) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        if let Some(start_key) = &self.start_key {
+            params.insert(s!("startkey"), serde_json::Value::from(start_key.as_str()).to_string());
+        }
+        if let Some(end_key) = &self.end_key {
+            params.insert(s!("endkey"), serde_json::Value::from(end_key.as_str()).to_string());
+        }
+        if let Some(limit) = self.limit {
+            params.insert(s!("limit"), limit.to_string());
+        }
+        if let Some(skip) = self.skip {
+            params.insert(s!("skip"), skip.to_string());
+        }
+        if let Some(descending) = self.descending {
+            params.insert(s!("descending"), descending.to_string());
+        }
+        params
+    }
+}
+
+/// Options to create a database. You can use the builder paradigm to construct these options:
+/// ```
+/// use couch_rs::types::system::CreateDatabaseOptions;
+/// let _options = CreateDatabaseOptions::default().partitioned(true).q(8);
+/// ```
+/// See [create database](https://docs.couchdb.org/en/stable/api/database/common.html#put--db) for details.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, Default)]
+pub struct CreateDatabaseOptions {
+    /// The number of shards
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub q: Option<u32>,
+    /// The number of replicas of each shard
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+    /// Whether to create a partitioned database
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partitioned: Option<bool>,
+}
+
+impl CreateDatabaseOptions {
+    pub fn q(mut self, q: u32) -> Self {
+        self.q = Some(q);
+        self
+    }
+
+    pub fn n(mut self, n: u32) -> Self {
+        self.n = Some(n);
+        self
+    }
+
+    pub fn partitioned(mut self, partitioned: bool) -> Self {
+        self.partitioned = Some(partitioned);
+        self
+    }
+
+    /// The options as query string parameters
+    pub(crate) fn query_params(&self) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        if let Some(q) = self.q {
+            params.insert(s!("q"), q.to_string());
+        }
+        if let Some(n) = self.n {
+            params.insert(s!("n"), n.to_string());
+        }
+        if let Some(partitioned) = self.partitioned {
+            params.insert(s!("partitioned"), partitioned.to_string());
+        }
+        params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_db_info() {
+        let info: DbInfo = serde_json::from_value(json!({
+            "instance_start_time": "0",
+            "db_name": "receipts",
+            "purge_seq": "0-g1AAAABPeJzLYWBgYMpgTmHgz8tPSTV0MDQy1zMAQsMckEQiQ1L9____szKYE1lzgQLsZsmmKRaGKdi04DEij4XBoIFI6VZWGMuDQEAARDiDZcAAja",
+            "update_seq": "52232-g1AAAABPeJzLYWBgYMpgTmHgz8tPSTV0MDQy1zMAQsMckEQiQ1L9____szKYE1lzgQLsZsmmKRaGKdi04DEij4XBoIFI6VZWGMuDQEAARDiDZcA",
+            "sizes": {"file": 1178613587, "external": 1713103872, "active": 1162451555},
+            "props": {},
+            "doc_del_count": 0,
+            "doc_count": 52224,
+            "disk_format_version": 8,
+            "compact_running": false,
+            "cluster": {"q": 8, "n": 3, "w": 2, "r": 2}
+        }))
+        .unwrap();
+        assert_eq!(info.cluster, ClusterInfo { n: 3, q: 8, r: 2, w: 2 });
+        assert_eq!(info.sizes.file, 1178613587);
+        assert_eq!(info.props.partitioned, None);
+        assert!(info.update_seq.as_str().unwrap().starts_with("52232-"));
+
+        // CouchDB 1.x has numeric sequences, and no props
+        let info: DbInfo = serde_json::from_value(json!({
+            "instance_start_time": "1376269325408900",
+            "db_name": "receipts",
+            "purge_seq": 0,
+            "update_seq": 292786,
+            "sizes": {"file": 1, "external": 1, "active": 1},
+            "doc_del_count": 0,
+            "doc_count": 6146,
+            "disk_format_version": 6,
+            "compact_running": false,
+            "cluster": {"q": 1, "n": 1, "w": 1, "r": 1}
+        }))
+        .unwrap();
+        assert_eq!(info.update_seq, json!(292786));
+        assert_eq!(info.props, DbProperties::default());
+    }
+}
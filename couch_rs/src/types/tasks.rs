@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A task running on the server, see
+/// [_active_tasks](https://docs.couchdb.org/en/stable/api/server/common.html#active-tasks)
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ActiveTask {
+    /// Builds the index of a design document
+    Indexer(IndexerTask),
+    DatabaseCompaction(CompactionTask),
+    ViewCompaction(CompactionTask),
+    Replication(ReplicationTask),
+    /// A kind of task that is not supported by this crate, e.g. `search_indexer`
+    #[serde(other)]
+    Other,
+}
+
+impl ActiveTask {
+    /// The progress in percent, for indexer and compaction tasks
+    pub fn progress(&self) -> Option<u32> {
+        match self {
+            ActiveTask::Indexer(task) => task.progress,
+            ActiveTask::DatabaseCompaction(task) | ActiveTask::ViewCompaction(task) => task.progress,
+            ActiveTask::Replication(_) | ActiveTask::Other => None,
+        }
+    }
+
+    /// The database the task works on, for indexer and compaction tasks
+    pub fn database(&self) -> Option<&str> {
+        match self {
+            ActiveTask::Indexer(task) => Some(&task.database),
+            ActiveTask::DatabaseCompaction(task) | ActiveTask::ViewCompaction(task) => Some(&task.database),
+            ActiveTask::Replication(_) | ActiveTask::Other => None,
+        }
+    }
+}
+
+/// The compaction state of a database, see `Database::compaction_status`
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct CompactionStatus {
+    /// Whether the database itself is being compacted, as reported by the database info
+    pub compact_running: bool,
+    /// The database and view compaction tasks of the shards of the database
+    pub tasks: Vec<ActiveTask>,
+}
+
+impl CompactionStatus {
+    pub fn is_running(&self) -> bool {
+        self.compact_running || !self.tasks.is_empty()
+    }
+
+    /// The average progress in percent of the compaction tasks
+    pub fn progress(&self) -> Option<u32> {
+        let progress: Vec<u32> = self.tasks.iter().filter_map(ActiveTask::progress).collect();
+        if progress.is_empty() {
+            None
+        } else {
+            Some(progress.iter().sum::<u32>() / progress.len() as u32)
+        }
+    }
+}
+
+/// Whether `shard`, e.g. `shards/00000000-1fffffff/mydb.1609459200`, belongs to the database
+/// `db_name`. Databases of a single node CouchDB are not sharded.
+pub(crate) fn is_shard_of(shard: &str, db_name: &str) -> bool {
+    match shard.strip_prefix("shards/") {
+        Some(shard) => shard
+            .split_once('/')
+            .and_then(|(_range, name)| name.rsplit_once('.'))
+            .is_some_and(|(name, _suffix)| name == db_name),
+        None => shard == db_name,
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct IndexerTask {
+    pub node: Option<String>,
+    pub pid: Option<String>,
+    /// The shard of the database, e.g. `shards/00000000-1fffffff/mydb.1609459200`
+    pub database: String,
+    pub design_document: String,
+    #[serde(default)]
+    pub changes_done: u64,
+    #[serde(default)]
+    pub total_changes: u64,
+    pub progress: Option<u32>,
+    pub started_on: Option<u64>,
+    pub updated_on: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct CompactionTask {
+    pub node: Option<String>,
+    pub pid: Option<String>,
+    /// The shard of the database, e.g. `shards/00000000-1fffffff/mydb.1609459200`
+    pub database: String,
+    /// The compacted design document, for view compactions
+    pub design_document: Option<String>,
+    #[serde(default)]
+    pub changes_done: u64,
+    #[serde(default)]
+    pub total_changes: u64,
+    pub progress: Option<u32>,
+    pub started_on: Option<u64>,
+    pub updated_on: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ReplicationTask {
+    pub node: Option<String>,
+    pub pid: Option<String>,
+    pub replication_id: Option<String>,
+    /// The replication document, for replications started through the `_replicator` database
+    pub doc_id: Option<String>,
+    pub source: Option<String>,
+    pub target: Option<String>,
+    #[serde(default)]
+    pub continuous: bool,
+    #[serde(default)]
+    pub docs_read: u64,
+    #[serde(default)]
+    pub docs_written: u64,
+    #[serde(default)]
+    pub doc_write_failures: u64,
+    #[serde(default)]
+    pub revisions_checked: u64,
+    #[serde(default)]
+    pub missing_revisions_found: u64,
+    pub changes_pending: Option<u64>,
+    pub checkpointed_source_seq: Option<Value>,
+    pub source_seq: Option<Value>,
+    pub through_seq: Option<Value>,
+    pub started_on: Option<u64>,
+    pub updated_on: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn test_active_tasks() {
+        let tasks: Vec<ActiveTask> = from_value(json!([
+            {
+                "type": "indexer",
+                "node": "couchdb@127.0.0.1",
+                "database": "shards/00000000-1fffffff/mydb.1609459200",
+                "design_document": "_design/users",
+                "changes_done": 500,
+                "total_changes": 1000,
+                "progress": 50,
+                "started_on": 1609459200,
+                "updated_on": 1609459210
+            },
+            {
+                "type": "database_compaction",
+                "database": "shards/00000000-1fffffff/mydb.1609459200",
+                "progress": 10
+            },
+            {
+                "type": "replication",
+                "replication_id": "abc+continuous",
+                "source": "http://localhost:5984/a/",
+                "target": "http://localhost:5984/b/",
+                "continuous": true,
+                "docs_written": 3
+            },
+            { "type": "search_indexer", "index": "idx" }
+        ]))
+        .unwrap();
+        assert_eq!(tasks[0].progress(), Some(50));
+        assert!(matches!(tasks[1], ActiveTask::DatabaseCompaction(_)));
+        assert!(matches!(&tasks[2], ActiveTask::Replication(task) if task.continuous));
+        assert_eq!(tasks[3], ActiveTask::Other);
+    }
+
+    #[test]
+    fn test_is_shard_of() {
+        assert!(is_shard_of("shards/00000000-1fffffff/mydb.1609459200", "mydb"));
+        assert!(is_shard_of("shards/00000000-1fffffff/org/mydb.1609459200", "org/mydb"));
+        assert!(!is_shard_of("shards/00000000-1fffffff/mydb2.1609459200", "mydb"));
+        assert!(is_shard_of("mydb", "mydb"));
+    }
+}
@@ -0,0 +1,154 @@
+use crate::document::TypedCouchDocument;
+use crate::types::document::DocumentId;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// The prefix of the ids of the documents in the `_users` database
+pub const USER_PREFIX: &str = "org.couchdb.user:";
+
+/// The default prefix of the databases that `couch_peruser` creates for every user
+pub const USER_DB_PREFIX: &str = "userdb-";
+
+/// A user in the `_users` database. CouchDB hashes the plain text `password` when the document
+/// is saved, and only stores the derived key. So a user read from the database has no `password`,
+/// but keeps its credentials when it's saved again. See
+/// [users](https://docs.couchdb.org/en/stable/intro/security.html#users-documents) for details.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct UserDocument {
+    pub _id: DocumentId,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub _rev: String,
+    pub name: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(rename = "type", default = "default_type")]
+    pub user_type: String,
+    /// The new plain text password, CouchDB replaces it by the hashed credentials
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password_scheme: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iterations: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub derived_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+}
+
+fn default_type() -> String {
+    "user".to_string()
+}
+
+impl UserDocument {
+    pub fn new(name: &str, password: &str, roles: Vec<String>) -> Self {
+        UserDocument {
+            _id: user_id(name),
+            _rev: String::new(),
+            name: name.to_string(),
+            roles,
+            user_type: default_type(),
+            password: Some(password.to_string()),
+            password_scheme: None,
+            iterations: None,
+            derived_key: None,
+            salt: None,
+        }
+    }
+
+    /// Sets a new plain text password, replacing the current credentials when the user is saved
+    pub fn set_password(&mut self, password: &str) {
+        self.password = Some(password.to_string());
+        self.password_scheme = None;
+        self.iterations = None;
+        self.derived_key = None;
+        self.salt = None;
+    }
+}
+
+/// The id of the user document of the user `name`
+pub fn user_id(name: &str) -> DocumentId {
+    if name.starts_with(USER_PREFIX) {
+        name.to_string()
+    } else {
+        format!("{}{}", USER_PREFIX, name)
+    }
+}
+
+/// The name of the database of the user `name`, as created by `couch_peruser`: the prefix
+/// `userdb-` followed by the hex encoded UTF-8 bytes of the name
+pub fn user_db_name(name: &str) -> String {
+    let hex: String = name.bytes().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", USER_DB_PREFIX, hex)
+}
+
+/// The name of the user of a `couch_peruser` database, or `None` when `dbname` is not the name of
+/// a user database
+pub fn user_db_user(dbname: &str) -> Option<String> {
+    let hex = dbname.strip_prefix(USER_DB_PREFIX)?;
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+impl TypedCouchDocument for UserDocument {
+    fn get_id(&self) -> Cow<'_, str> {
+        Cow::from(&self._id)
+    }
+
+    fn get_rev(&self) -> Cow<'_, str> {
+        Cow::from(&self._rev)
+    }
+
+    fn set_rev(&mut self, rev: &str) {
+        self._rev = rev.to_string();
+    }
+
+    fn set_id(&mut self, id: &str) {
+        self._id = id.to_string();
+    }
+
+    fn merge_ids(&mut self, other: &Self) {
+        self.set_id(&other.get_id());
+        self.set_rev(&other.get_rev());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, to_value};
+
+    #[test]
+    fn test_user_document() {
+        let user = UserDocument::new("john", "secret", vec!["users".to_string()]);
+        assert_eq!(
+            to_value(&user).unwrap(),
+            json!({
+                "_id": "org.couchdb.user:john",
+                "name": "john",
+                "roles": ["users"],
+                "type": "user",
+                "password": "secret"
+            })
+        );
+        assert_eq!(user_id("org.couchdb.user:john"), "org.couchdb.user:john");
+    }
+
+    #[test]
+    fn test_user_db_name() {
+        assert_eq!(user_db_name("john"), "userdb-6a6f686e");
+        assert_eq!(user_db_name("Jöhn.Doe"), "userdb-4ac3b6686e2e446f65");
+        assert_eq!(user_db_user("userdb-4ac3b6686e2e446f65"), Some("Jöhn.Doe".to_string()));
+        assert_eq!(user_db_user(&user_db_name("john")), Some("john".to_string()));
+        assert_eq!(user_db_user("userdb-6a6f686"), None);
+        assert_eq!(user_db_user("userdb-zz"), None);
+        assert_eq!(user_db_user("invoices"), None);
+    }
+}
@@ -1,8 +1,11 @@
 use crate::document::TypedCouchDocument;
+use crate::error::CouchResult;
+use futures_core::Stream;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::pin::Pin;
 
 #[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
 #[serde(bound(deserialize = "T: TypedCouchDocument"))]
@@ -24,6 +27,25 @@ pub struct ViewItem<K: DeserializeOwned, V: DeserializeOwned, T: TypedCouchDocum
     pub doc: Option<T>,
 }
 
+/// A stream of view rows, returned by [Database::query_view_stream](crate::database::Database::query_view_stream)
+pub type ViewStream<K, V, T> = Pin<Box<dyn Stream<Item = CouchResult<ViewItem<K, V, T>>> + Send>>;
+
+/// The rows of a reduced view query. Without `group` or `group_level` a view reduces to a single
+/// row with a `null` key; use `Option<K>` or `Value` for `K` in that case.
+#[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(bound(deserialize = "K: DeserializeOwned, V: DeserializeOwned"))]
+pub struct ReducedViewCollection<K: DeserializeOwned, V: DeserializeOwned> {
+    pub rows: Vec<ReducedViewItem<K, V>>,
+}
+
+/// A row of a reduced view: the (grouped) key and the reduced value
+#[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(bound(deserialize = "K: DeserializeOwned, V: DeserializeOwned"))]
+pub struct ReducedViewItem<K: DeserializeOwned, V: DeserializeOwned> {
+    pub key: K,
+    pub value: V,
+}
+
 /// CouchViews can be used to create one of more views in a particular design document.
 #[derive(Serialize)]
 pub struct CouchViews {
@@ -42,7 +64,7 @@ pub struct CouchViews {
 ///     reduce: None,
 /// };
 /// ```
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
 pub struct CouchFunc {
     pub map: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -115,3 +137,23 @@ impl From<CouchUpdate> for serde_json::Value {
         serde_json::to_value(u).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_reduced_view() {
+        let grouped: ReducedViewCollection<(u32, u32), u64> = serde_json::from_value(json!({
+            "rows": [{"key": [2021, 1], "value": 12}, {"key": [2021, 2], "value": 7}]
+        }))
+        .unwrap();
+        assert_eq!(grouped.rows[1].key, (2021, 2));
+        assert_eq!(grouped.rows[1].value, 7);
+
+        let total: ReducedViewCollection<Option<Value>, u64> =
+            serde_json::from_value(json!({"rows": [{"key": null, "value": 19}]})).unwrap();
+        assert_eq!(total.rows[0].key, None);
+    }
+}
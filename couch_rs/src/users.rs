@@ -0,0 +1,112 @@
+use crate::client::Client;
+use crate::database::Database;
+use crate::error::CouchResult;
+use crate::types::document::DocumentCreatedResult;
+use crate::types::security::{SecurityMembers, SecurityObject};
+use crate::types::user::{user_db_name, user_id, UserDocument};
+
+/// The name of the CouchDB database that holds the users
+const USERS_DB: &str = "_users";
+
+/// Manages the users in the `_users` database. Users are identified by their name, the
+/// `org.couchdb.user:` prefix of their document ids is added where needed.
+///
+/// Usage:
+/// ```
+/// use couch_rs::error::CouchResult;
+///
+/// #[tokio::main]
+/// async fn main() -> CouchResult<()> {
+///     let client = couch_rs::Client::new_local_test()?;
+///     let users = client.users();
+///
+///     users.create_user("john", "secret", vec!["readers".to_string()]).await?;
+///     users.change_password("john", "n3w-secret").await?;
+///
+///     let john = users.get_user("john").await?;
+///     assert_eq!(john.roles, vec!["readers".to_string()]);
+///
+///     users.delete_user("john").await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Users {
+    client: Client,
+    db: Database,
+}
+
+impl Users {
+    pub fn new(client: &Client) -> Self {
+        // the _users database is never prefixed
+        Users {
+            client: client.clone(),
+            db: Database::new(USERS_DB.to_string(), client.clone()),
+        }
+    }
+
+    /// Creates a user with a plain text password, which CouchDB hashes before storing it
+    pub async fn create_user(&self, name: &str, password: &str, roles: Vec<String>) -> DocumentCreatedResult {
+        let mut user = UserDocument::new(name, password, roles);
+        self.db.save(&mut user).await
+    }
+
+    /// Gets the user with the given name
+    pub async fn get_user(&self, name: &str) -> CouchResult<UserDocument> {
+        self.db.get(&user_id(name)).await
+    }
+
+    /// Updates a user, e.g. its roles. The document must include its current `_rev`.
+    pub async fn update_user(&self, user: &mut UserDocument) -> DocumentCreatedResult {
+        self.db.save(user).await
+    }
+
+    /// Replaces the password of a user
+    pub async fn change_password(&self, name: &str, password: &str) -> DocumentCreatedResult {
+        let mut user = self.get_user(name).await?;
+        user.set_password(password);
+        self.db.save(&mut user).await
+    }
+
+    /// Deletes a user. Returns success in a `bool`
+    pub async fn delete_user(&self, name: &str) -> CouchResult<bool> {
+        let user = self.get_user(name).await?;
+        Ok(self.db.remove(&user).await)
+    }
+
+    /// The database of the user `name`, following the conventions of `couch_peruser`, see
+    /// [user_db_name]. Like the `_users` database, the name is never prefixed. The database is
+    /// not checked or created, use `provision_user_db` for that.
+    pub fn user_db(&self, name: &str) -> Database {
+        Database::new(user_db_name(name), self.client.clone())
+    }
+
+    /// Creates the database of the user `name`, when it doesn't exist, and makes the user its
+    /// only admin and member, like `couch_peruser` does. Use this when `couch_peruser` is not
+    /// enabled on the server, or to provision the database before the user signs in.
+    ///
+    /// Usage:
+    /// ```
+    /// use couch_rs::error::CouchResult;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> CouchResult<()> {
+    ///     let client = couch_rs::Client::new_local_test()?;
+    ///     let users = client.users();
+    ///
+    ///     users.create_user("john", "secret", vec![]).await?;
+    ///     let db = users.provision_user_db("john").await?;
+    ///     assert_eq!(db.name(), "userdb-6a6f686e");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn provision_user_db(&self, name: &str) -> CouchResult<Database> {
+        let mut client = self.client.clone();
+        client.set_prefix(String::new());
+        let db = client.db(&user_db_name(name)).await?;
+        let members = SecurityMembers::default().name(name);
+        let security = SecurityObject::default().admins(members.clone()).members(members);
+        db.set_security(&security).await?;
+        Ok(db)
+    }
+}
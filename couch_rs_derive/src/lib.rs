@@ -3,39 +3,657 @@ extern crate quote;
 
 use proc_macro::TokenStream;
 
+/// Implements `TypedCouchDocument` for a struct with an `_id` field of type `String`, and a
+/// `_rev` field of type `String` or `Option<String>`. A revision of `None` is read as an empty
+/// revision, and an empty revision is set as `None`, so a new document can leave out the `_rev`
+/// with `#[serde(skip_serializing_if = "Option::is_none")]`.
+///
+/// Other fields can hold the id and the revision when serde renames them to `_id` and `_rev`, or
+/// by marking them with `#[couch(id)]` and `#[couch(rev)]`; they still have to be serialized as
+/// `_id` and `_rev`, also with a `#[serde(rename_all = "..")]` on the struct:
+/// ```ignore
+/// #[derive(Serialize, Deserialize, CouchDocument)]
+/// #[couch(type_name = "user")]
+/// pub struct UserDetails {
+///     #[couch(id)]
+///     #[serde(rename = "_id", skip_serializing_if = "String::is_empty")]
+///     pub id: String,
+///     #[couch(rev)]
+///     #[serde(rename = "_rev", skip_serializing_if = "String::is_empty")]
+///     pub rev: String,
+///     pub name: String,
+/// }
+/// ```
+///
+/// With `#[couch(type_name = "..")]` on the struct, the documents are written with a `type` field
+/// of that value, see `TypedCouchDocument::type_name`. With `#[couch(timestamps)]`, the
+/// `created_at` and `updated_at` fields are set when the documents are saved, see
+/// `couch_rs::timestamps::Timestamp`.
+///
+/// For a generic struct, e.g. with a `#[serde(flatten)]` field of a type parameter, the
+/// implementation is bound to the type parameters for which the struct can be serialized and
+/// deserialized.
 #[allow(clippy::missing_panics_doc)]
-#[proc_macro_derive(CouchDocument, attributes(serde))]
+#[proc_macro_derive(CouchDocument, attributes(serde, couch))]
 pub fn derive_couch_doc(input: TokenStream) -> TokenStream {
     impl_derive_couch_doc(&syn::parse(input).unwrap())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
-fn impl_derive_couch_doc(ast: &syn::DeriveInput) -> TokenStream {
+/// Implements `TypedCouchDocument` for an enum of document types, e.g. for the results of a query
+/// that returns several types of documents. Each variant holds one `TypedCouchDocument`, and serde
+/// tells the variants apart by a field of the documents, usually `type`:
+/// ```ignore
+/// #[derive(Serialize, Deserialize, CouchDocumentEnum)]
+/// #[serde(tag = "type", rename_all = "snake_case")]
+/// pub enum Sale {
+///     Invoice(Invoice),
+///     Order(Order),
+/// }
+/// ```
+///
+/// The methods of `TypedCouchDocument` are passed on to the document of the variant.
+#[allow(clippy::missing_panics_doc)]
+#[proc_macro_derive(CouchDocumentEnum, attributes(serde))]
+pub fn derive_couch_doc_enum(input: TokenStream) -> TokenStream {
+    impl_derive_couch_doc_enum(&syn::parse(input).unwrap())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `couch_rs::types::find::Projection` for a struct with some of the fields of a type
+/// of documents, for `Database::find_fields`. The fields of the projection are the names that
+/// serde deserializes the fields of the struct from, following `#[serde(rename)]`,
+/// `#[serde(rename_all)]` and `#[serde(skip)]`, so they always match the struct:
+/// ```ignore
+/// #[derive(Deserialize, Projection)]
+/// #[serde(rename_all = "camelCase")]
+/// pub struct UserName {
+///     pub first_name: String,
+///     pub last_name: String,
+/// }
+/// // UserName::fields() == ["firstName", "lastName"]
+/// ```
+#[allow(clippy::missing_panics_doc)]
+#[proc_macro_derive(Projection, attributes(serde))]
+pub fn derive_projection(input: TokenStream) -> TokenStream {
+    impl_derive_projection(&syn::parse(input).unwrap())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// The markers a field can have in `#[couch(..)]`
+const FIELD_MARKERS: &[&str] = &["id", "rev"];
+
+/// The named fields of the struct
+fn fields(ast: &syn::DeriveInput) -> syn::Result<Vec<&syn::Field>> {
+    match &ast.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => Ok(fields.named.iter().collect()),
+        _ => Err(syn::Error::new_spanned(
+            &ast.ident,
+            "CouchDocument can only be derived for structs with named fields",
+        )),
+    }
+}
+
+/// The items of the `#[couch(..)]` attributes
+fn couch_attrs(attrs: &[syn::Attribute]) -> syn::Result<Vec<syn::NestedMeta>> {
+    let mut items = vec![];
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("couch")) {
+        match attr.parse_meta()? {
+            syn::Meta::List(list) => items.extend(list.nested),
+            meta => return Err(syn::Error::new_spanned(meta, "expected #[couch(..)]")),
+        }
+    }
+    Ok(items)
+}
+
+/// The `#[couch(..)]` attributes of the struct
+#[derive(Default)]
+struct StructAttrs {
+    type_name: Option<syn::LitStr>,
+    timestamps: bool,
+}
+
+fn struct_attrs(ast: &syn::DeriveInput) -> syn::Result<StructAttrs> {
+    let mut attrs = StructAttrs::default();
+    for meta in couch_attrs(&ast.attrs)? {
+        match meta {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                path,
+                lit: syn::Lit::Str(name),
+                ..
+            })) if path.is_ident("type_name") => attrs.type_name = Some(name),
+            syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("timestamps") => attrs.timestamps = true,
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "unknown couch attribute, expected type_name = \"..\" or timestamps",
+                ))
+            }
+        }
+    }
+    Ok(attrs)
+}
+
+/// The markers in the `#[couch(..)]` attributes of a field
+fn markers(field: &syn::Field) -> syn::Result<Vec<syn::Path>> {
+    let mut markers = vec![];
+    for meta in couch_attrs(&field.attrs)? {
+        match meta {
+            syn::NestedMeta::Meta(syn::Meta::Path(path))
+                if FIELD_MARKERS.iter().any(|marker| path.is_ident(marker)) =>
+            {
+                markers.push(path)
+            }
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    format!("unknown couch attribute, expected one of: {}", FIELD_MARKERS.join(", ")),
+                ))
+            }
+        }
+    }
+    Ok(markers)
+}
+
+/// The names in the `#[serde(<key> = "..")]` or `#[serde(<key>(serialize = "..", deserialize = ".."))]`
+/// attributes, e.g. of `rename`. Attributes that can't be parsed are left to serde to report.
+fn serde_names(attrs: &[syn::Attribute], key: &str) -> Vec<syn::LitStr> {
+    let mut names = vec![];
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("serde")) {
+        let Ok(syn::Meta::List(list)) = attr.parse_meta() else {
+            continue;
+        };
+        for meta in list.nested {
+            match meta {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(name),
+                    ..
+                })) if path.is_ident(key) => names.push(name),
+                syn::NestedMeta::Meta(syn::Meta::List(list)) if list.path.is_ident(key) => {
+                    names.extend(list.nested.into_iter().filter_map(|meta| match meta {
+                        syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                            lit: syn::Lit::Str(name),
+                            ..
+                        })) => Some(name),
+                        _ => None,
+                    }))
+                }
+                _ => {}
+            }
+        }
+    }
+    names
+}
+
+/// The field that is marked with `#[couch(<marker>)]`, or else the field that serde renames to
+/// the default name, or else the field with the default name
+fn field_for<'a>(fields: &[&'a syn::Field], marker: &str, default_name: &str) -> syn::Result<Option<&'a syn::Field>> {
+    let mut marked = None;
+    for field in fields {
+        if let Some(path) = markers(field)?.into_iter().find(|path| path.is_ident(marker)) {
+            if marked.is_some() {
+                return Err(syn::Error::new_spanned(
+                    path,
+                    format!("only one field can be marked with #[couch({})]", marker),
+                ));
+            }
+            marked = Some(*field);
+        }
+    }
+    let renamed = || {
+        fields.iter().copied().find(|f| {
+            serde_names(&f.attrs, "rename")
+                .iter()
+                .any(|name| name.value() == default_name)
+        })
+    };
+    let named = || {
+        fields
+            .iter()
+            .copied()
+            .find(|f| f.ident.as_ref().is_some_and(|i| i == default_name))
+    };
+    Ok(marked.or_else(renamed).or_else(named))
+}
+
+/// Checks that serde serializes the field as `couch_name`, e.g. `_id`, also with a
+/// `#[serde(rename_all = "..")]` on the struct
+fn check_serialized_name(ast: &syn::DeriveInput, field: &syn::Field, couch_name: &str) -> syn::Result<()> {
+    let renames = serde_names(&field.attrs, "rename");
+    if let Some(rename) = renames.iter().find(|rename| rename.value() != couch_name) {
+        return Err(syn::Error::new_spanned(
+            rename,
+            format!("this field must be serialized as `{}`", couch_name),
+        ));
+    }
+    if !renames.is_empty() {
+        return Ok(());
+    }
+    if field.ident.as_ref().is_some_and(|i| i != couch_name) {
+        return Err(syn::Error::new_spanned(
+            field,
+            format!(
+                "add #[serde(rename = \"{}\")] to serialize this field as `{}`",
+                couch_name, couch_name
+            ),
+        ));
+    }
+    // these rules leave names like `_id` unchanged
+    let preserving = ["lowercase", "snake_case"];
+    match serde_names(&ast.attrs, "rename_all")
+        .into_iter()
+        .find(|rule| !preserving.contains(&rule.value().as_str()))
+    {
+        Some(rule) => Err(syn::Error::new_spanned(
+            rule,
+            format!(
+                "this also renames `{}`, add #[serde(rename = \"{}\")] to that field",
+                couch_name, couch_name
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The field that is marked, or else the field with the default name, which is required
+fn required_field_for<'a>(
+    ast: &syn::DeriveInput,
+    fields: &[&'a syn::Field],
+    marker: &str,
+    default_name: &str,
+) -> syn::Result<&'a syn::Field> {
+    field_for(fields, marker, default_name)?.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &ast.ident,
+            format!(
+                "CouchDocument needs a `{}` field, or a field marked with #[couch({})]",
+                default_name, marker
+            ),
+        )
+    })
+}
+
+/// Whether the type is an `Option`
+fn is_option(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(path) => path.path.segments.last().is_some_and(|s| s.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// Whether the struct has a named field with the given name
+fn has_field(fields: &[&syn::Field], field_name: &str) -> bool {
+    fields.iter().any(|f| f.ident.as_ref().is_some_and(|i| i == field_name))
+}
+
+/// The generics of the type, bound to the type parameters for which the documents are
+/// (de)serializable
+fn bounded_generics(ast: &syn::DeriveInput) -> syn::Generics {
+    generics_where(ast, quote! { ::serde::Serialize + ::serde::de::DeserializeOwned })
+}
+
+/// The generics of the type, bound to the type parameters for which the type has the bounds
+fn generics_where(ast: &syn::DeriveInput, bounds: proc_macro2::TokenStream) -> syn::Generics {
+    let name = &ast.ident;
+    let mut generics = ast.generics.clone();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+    if !generics.params.is_empty() {
+        generics
+            .make_where_clause()
+            .predicates
+            .push(syn::parse_quote! { #name #ty_generics: #bounds });
+    }
+    generics
+}
+
+fn impl_derive_couch_doc(ast: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+    let fields = fields(ast)?;
+    let id_field = required_field_for(ast, &fields, "id", "_id")?;
+    check_serialized_name(ast, id_field, "_id")?;
+    let id = &id_field.ident;
+    let rev_field = required_field_for(ast, &fields, "rev", "_rev")?;
+    check_serialized_name(ast, rev_field, "_rev")?;
+    let rev = &rev_field.ident;
+
+    let generics = bounded_generics(ast);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+    let attrs = struct_attrs(ast)?;
+    let type_name = attrs.type_name.map(|type_name| {
+        quote! {
+            fn type_name() -> Option<&'static str> {
+                Some(#type_name)
+            }
+        }
+    });
+
+    // a new document can have a revision of `None`
+    let (get_rev, set_rev) = if is_option(&rev_field.ty) {
+        (
+            quote! { couch_rs::Cow::from(self.#rev.as_deref().unwrap_or_default()) },
+            quote! { self.#rev = Some(rev.to_string()).filter(|rev| !rev.is_empty()); },
+        )
+    } else {
+        (
+            quote! { couch_rs::Cow::from(&self.#rev) },
+            quote! { self.#rev = rev.to_string(); },
+        )
+    };
+
+    // `created_at` and `updated_at` fields are expected to implement `couch_rs::timestamps::Timestamp`
+    let timestamps = if attrs.timestamps {
+        for field_name in ["created_at", "updated_at"] {
+            if !has_field(&fields, field_name) {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    format!("#[couch(timestamps)] needs a `{}` field", field_name),
+                ));
+            }
+        }
+        quote! {
+            fn set_timestamps(&mut self, now: std::time::SystemTime) {
+                if self.get_rev().is_empty() {
+                    self.created_at = couch_rs::timestamps::Timestamp::from_system_time(now);
+                }
+                self.updated_at = couch_rs::timestamps::Timestamp::from_system_time(now);
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // an `_attachments` field is expected to be of type `couch_rs::types::document::Attachments`
+    let attachments = if has_field(&fields, "_attachments") {
+        quote! {
+            fn get_attachments(&self) -> Option<couch_rs::Cow<'_, couch_rs::types::document::Attachments>> {
+                Some(couch_rs::Cow::Borrowed(&self._attachments))
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // `_conflicts` and `_deleted_conflicts` fields are expected to be of type `Vec<String>`
+    let conflicts = if has_field(&fields, "_conflicts") {
+        quote! {
+            fn get_conflicts(&self) -> Option<couch_rs::Cow<'_, [String]>> {
+                Some(couch_rs::Cow::Borrowed(&self._conflicts))
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let deleted_conflicts = if has_field(&fields, "_deleted_conflicts") {
+        quote! {
+            fn get_deleted_conflicts(&self) -> Option<couch_rs::Cow<'_, [String]>> {
+                Some(couch_rs::Cow::Borrowed(&self._deleted_conflicts))
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let gen = quote! {
+        impl #impl_generics TypedCouchDocument for #name #ty_generics #where_clause {
+            fn get_id(&self) -> couch_rs::Cow<'_, str> {
+                couch_rs::Cow::from(&self.#id)
+            }
+
+            fn get_rev(&self) -> couch_rs::Cow<'_, str> {
+                #get_rev
+            }
+
+            fn set_id(&mut self, id: &str) {
+                self.#id = id.to_string();
+            }
+
+            fn set_rev(&mut self, rev: &str) {
+                #set_rev
+            }
+
+            fn merge_ids(&mut self, other: &Self) {
+                self.set_id(&other.get_id());
+                self.set_rev(&other.get_rev());
+            }
+
+            #attachments
+            #conflicts
+            #deleted_conflicts
+            #type_name
+            #timestamps
+        }
+    };
+
+    Ok(gen)
+}
+
+/// Whether there is a `#[serde(<flag>)]` attribute, e.g. `untagged`
+fn has_serde_flag(attrs: &[syn::Attribute], flag: &str) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("serde"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .any(|meta| matches!(meta, syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident(flag)))
+}
+
+fn impl_derive_couch_doc_enum(ast: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let name = &ast.ident;
+    let syn::Data::Enum(data) = &ast.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "CouchDocumentEnum can only be derived for enums",
+        ));
+    };
+    // an externally tagged variant would be serialized as `{"Variant": {..}}`, which is no document
+    if serde_names(&ast.attrs, "tag").is_empty() && !has_serde_flag(&ast.attrs, "untagged") {
+        return Err(syn::Error::new_spanned(
+            name,
+            "CouchDocumentEnum needs #[serde(tag = \"type\")], or #[serde(untagged)]",
+        ));
+    }
+    let mut variants = vec![];
+    for variant in &data.variants {
+        match &variant.fields {
+            syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => variants.push(&variant.ident),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "each variant of a CouchDocumentEnum must hold one document, e.g. `Invoice(Invoice)`",
+                ))
+            }
+        }
+    }
+
+    let generics = bounded_generics(ast);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
 
     let gen = quote! {
-        impl TypedCouchDocument for #name {
-            fn get_id(&self) -> couch_rs::Cow<str> {
-                couch_rs::Cow::from(&self._id)
+        impl #impl_generics TypedCouchDocument for #name #ty_generics #where_clause {
+            fn get_id(&self) -> couch_rs::Cow<'_, str> {
+                match self {
+                    #(#name::#variants(doc) => doc.get_id(),)*
+                }
             }
 
-            fn get_rev(&self) -> couch_rs::Cow<str> {
-                couch_rs::Cow::from(&self._rev)
+            fn get_rev(&self) -> couch_rs::Cow<'_, str> {
+                match self {
+                    #(#name::#variants(doc) => doc.get_rev(),)*
+                }
             }
 
             fn set_id(&mut self, id: &str) {
-                self._id = id.to_string();
+                match self {
+                    #(#name::#variants(doc) => doc.set_id(id),)*
+                }
             }
 
             fn set_rev(&mut self, rev: &str) {
-                self._rev = rev.to_string();
+                match self {
+                    #(#name::#variants(doc) => doc.set_rev(rev),)*
+                }
             }
 
             fn merge_ids(&mut self, other: &Self) {
                 self.set_id(&other.get_id());
                 self.set_rev(&other.get_rev());
             }
+
+            fn get_attachments(&self) -> Option<couch_rs::Cow<'_, couch_rs::types::document::Attachments>> {
+                match self {
+                    #(#name::#variants(doc) => doc.get_attachments(),)*
+                }
+            }
+
+            // the document replaces a current version of another type
+            #[allow(unreachable_patterns)]
+            fn merge(&mut self, current: Self) {
+                match (self, current) {
+                    #((#name::#variants(doc), #name::#variants(current)) => doc.merge(current),)*
+                    _ => {}
+                }
+            }
+
+            fn get_conflicts(&self) -> Option<couch_rs::Cow<'_, [String]>> {
+                match self {
+                    #(#name::#variants(doc) => doc.get_conflicts(),)*
+                }
+            }
+
+            fn get_deleted_conflicts(&self) -> Option<couch_rs::Cow<'_, [String]>> {
+                match self {
+                    #(#name::#variants(doc) => doc.get_deleted_conflicts(),)*
+                }
+            }
+
+            fn set_timestamps(&mut self, now: std::time::SystemTime) {
+                match self {
+                    #(#name::#variants(doc) => doc.set_timestamps(now),)*
+                }
+            }
+        }
+    };
+
+    Ok(gen)
+}
+
+/// The name in `#[serde(<key> = "..")]` or `#[serde(<key>(deserialize = ".."))]`
+fn serde_deserialize_name(attrs: &[syn::Attribute], key: &str) -> Option<syn::LitStr> {
+    let mut name = None;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("serde")) {
+        let Ok(syn::Meta::List(list)) = attr.parse_meta() else {
+            continue;
+        };
+        for meta in list.nested {
+            match meta {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit),
+                    ..
+                })) if path.is_ident(key) => name = Some(lit),
+                syn::NestedMeta::Meta(syn::Meta::List(list)) if list.path.is_ident(key) => {
+                    for meta in list.nested {
+                        if let syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                            path,
+                            lit: syn::Lit::Str(lit),
+                            ..
+                        })) = meta
+                        {
+                            if path.is_ident("deserialize") {
+                                name = Some(lit);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    name
+}
+
+/// Renames a field with a `rename_all` rule, like serde does
+fn rename_field(field: &str, rule: &syn::LitStr) -> syn::Result<String> {
+    let pascal = || {
+        let mut pascal = String::new();
+        let mut capitalize = true;
+        for ch in field.chars() {
+            if ch == '_' {
+                capitalize = true;
+            } else if capitalize {
+                pascal.push(ch.to_ascii_uppercase());
+                capitalize = false;
+            } else {
+                pascal.push(ch);
+            }
+        }
+        pascal
+    };
+    Ok(match rule.value().as_str() {
+        "lowercase" | "snake_case" => field.to_string(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => field.to_ascii_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        }
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.to_ascii_uppercase().replace('_', "-"),
+        _ => return Err(syn::Error::new_spanned(rule, "unknown rename_all rule")),
+    })
+}
+
+fn impl_derive_projection(ast: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    use syn::ext::IdentExt;
+
+    let name = &ast.ident;
+    let rename_all = serde_deserialize_name(&ast.attrs, "rename_all");
+    let mut names = vec![];
+    for field in fields(ast)? {
+        if has_serde_flag(&field.attrs, "skip") || has_serde_flag(&field.attrs, "skip_deserializing") {
+            continue;
+        }
+        if has_serde_flag(&field.attrs, "flatten") {
+            return Err(syn::Error::new_spanned(
+                field,
+                "the fields of a flattened field are not known to a Projection",
+            ));
+        }
+        let field_name = match (serde_deserialize_name(&field.attrs, "rename"), &rename_all) {
+            (Some(rename), _) => rename.value(),
+            (None, Some(rule)) => rename_field(&field.ident.as_ref().unwrap().unraw().to_string(), rule)?,
+            (None, None) => field.ident.as_ref().unwrap().unraw().to_string(),
+        };
+        names.push(field_name);
+    }
+
+    let generics = generics_where(ast, quote! { ::serde::de::DeserializeOwned });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+
+    let gen = quote! {
+        impl #impl_generics couch_rs::types::find::Projection for #name #ty_generics #where_clause {
+            fn fields() -> &'static [&'static str] {
+                &[#(#names),*]
+            }
         }
     };
 
-    gen.into()
+    Ok(gen)
 }
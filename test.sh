@@ -17,8 +17,9 @@ done
 echo "CouchDB is up. Starting tests in 1s"
 sleep 1
 
-# Do a quick check for any issues using rustls-tls
-cargo clippy --features rustls-tls
+# Do a quick check for any issues using rustls-tls, alone and together with all other features
+cargo clippy --no-default-features --features derive,rustls
+cargo clippy --all-features
 cargo test -- --test-threads=1 --nocapture
 
 docker kill "$docker_id" &> /dev/null || true